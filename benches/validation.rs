@@ -0,0 +1,62 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Validation benchmarks
+//!
+//! Compares validating against a `CENNZnutV0` with its `CENNZnutIndex`
+//!
+
+use cennznut::v0::{method::Method, module::Module, set::ContractSet};
+use cennznut::{CENNZnutIndex, CENNZnutV0, Constraint};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pact::interpreter::types::{Numeric, PactType};
+
+const MODULES: usize = 200;
+const METHODS: usize = 32;
+
+/// A CENNZnut granting constrained methods of many modules, as a broad delegation would
+fn make_cennznut() -> CENNZnutV0 {
+    let constraint = Constraint::arg(0).lte(PactType::Numeric(Numeric(1_000)));
+    let modules = (0..MODULES)
+        .map(|m| {
+            let methods = (0..METHODS)
+                .map(|n| {
+                    Method::new(&format!("method_{}", n))
+                        .constraint(&constraint)
+                        .expect("it encodes")
+                })
+                .collect();
+            Module::new(&format!("module_{}", m)).methods(methods)
+        })
+        .collect();
+    CENNZnutV0 {
+        modules,
+        contracts: ContractSet::new(),
+    }
+}
+
+fn validate_module(c: &mut Criterion) {
+    let cennznut = make_cennznut();
+    let index = CENNZnutIndex::from(&cennznut);
+    let module = format!("module_{}", MODULES - 1);
+    let method = format!("method_{}", METHODS - 1);
+    let args = [PactType::Numeric(Numeric(100))];
+
+    let mut group = c.benchmark_group("validate_module");
+    group.bench_function("CENNZnutV0", |b| {
+        b.iter(|| cennznut.validate_module(black_box(&module), black_box(&method), &args))
+    });
+    group.bench_function("CENNZnutIndex", |b| {
+        b.iter(|| index.validate_module(black_box(&module), black_box(&method), &args))
+    });
+    group.finish();
+}
+
+fn build_index(c: &mut Criterion) {
+    let cennznut = make_cennznut();
+    c.bench_function("CENNZnutIndex::from", |b| {
+        b.iter(|| CENNZnutIndex::from(black_box(&cennznut)))
+    });
+}
+
+criterion_group!(benches, validate_module, build_index);
+criterion_main!(benches);
@@ -2,7 +2,10 @@
 
 //! Provide JS-Rust API bindings to create and inspect Cennznut
 use cennznut::{
-    v0::{contract::Contract, module::Module, CENNZnutV0},
+    v0::{
+        set::{ContractSet, ModuleSet},
+        CENNZnutV0,
+    },
     CENNZnut,
 };
 use parity_scale_codec::{Decode, Encode};
@@ -36,16 +39,13 @@ impl JsHandle {
     #[wasm_bindgen(constructor)]
     /// Create a new Cennznut, it is always v0 for now
     pub fn new(modules: &JsValue, contracts: &JsValue) -> Self {
-        let modules_vec: Vec<(String, Module)> = modules
+        let modules: ModuleSet = modules
             .into_serde()
             .expect("Deserialization of modules failed");
-        let contract_vec: Vec<([u8; 32], Contract)> = contracts
+        let contracts: ContractSet = contracts
             .into_serde()
             .expect("Deserialization of contracts failed");
-        let cennznut: CENNZnutV0 = CENNZnutV0 {
-            modules: modules_vec,
-            contracts: contract_vec,
-        };
+        let cennznut: CENNZnutV0 = CENNZnutV0 { modules, contracts };
         JsHandle(CENNZnut::V0(cennznut))
     }
 
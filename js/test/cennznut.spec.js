@@ -71,7 +71,11 @@ describe("wasm cennznut", () => {
     let extract_module = cennznutNew.getModule("test_module_check1");
     expect(extract_module.name).toEqual('test_module_check1');
     expect(extract_module.block_cooldown).toEqual(270549120);
-    expect(extract_module.methods[0]).toContain("test_method_check1");
+    // Legacy method keys which do not match the method names are migrated to the names
+    expect(extract_module.methods[0]).toContain("test_method_check11");
+    expect(extract_module.methods.map(([key]) => key)).toEqual(["test_method_check11", "test_method_check12"]);
+    let decoded = Cennznut.decode(cennznutNew.encode());
+    expect(decoded.getModule("test_module_check1")).toEqual(extract_module);
     let extract_contract = cennznutNew.getContract(contract_address);
     expect(extract_contract.block_cooldown).toEqual(270549120);
     expect(cennznutNew.verifyContract(contract_address)).toEqual(true);
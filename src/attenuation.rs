@@ -0,0 +1,181 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Attenuation
+//!
+//! Errors which may occur while checking a delegated CENNZnut grants
+//! no more than its parent
+//!
+
+use crate::cennznut::{ContractAddress, MethodName, ModuleName};
+use alloc::fmt::{self, Display, Formatter};
+
+/// The first grant of a child CENNZnut which exceeds its parent.
+/// Names are those requested of the CENNZnut, which may be
+/// covered by a wildcard grant in the child.
+#[derive(Debug, Eq, PartialEq)]
+pub enum AttenuationErr {
+    ModuleNotGranted {
+        module: ModuleName,
+    },
+    ModuleCooldownShortened {
+        module: ModuleName,
+    },
+    ModuleValidityWidened {
+        module: ModuleName,
+    },
+    ModuleRateLimitLoosened {
+        module: ModuleName,
+    },
+    ModuleConstraintsLoosened {
+        module: ModuleName,
+    },
+    MethodNotGranted {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodCooldownShortened {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodValidityWidened {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodUsesIncreased {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodRateLimitLoosened {
+        module: ModuleName,
+        method: MethodName,
+    },
+    ConstraintsLoosened {
+        module: ModuleName,
+        method: MethodName,
+    },
+    ContractNotGranted {
+        contract: ContractAddress,
+    },
+    ContractCooldownShortened {
+        contract: ContractAddress,
+    },
+    ContractValidityWidened {
+        contract: ContractAddress,
+    },
+    ContractUsesIncreased {
+        contract: ContractAddress,
+    },
+    ContractRateLimitLoosened {
+        contract: ContractAddress,
+    },
+    ContractSelectorsWidened {
+        contract: ContractAddress,
+    },
+    ContractConstraintsLoosened {
+        contract: ContractAddress,
+    },
+}
+
+impl Display for AttenuationErr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ModuleNotGranted { module } => {
+                write!(f, "module {} is not granted by the parent", module)
+            }
+            Self::ModuleCooldownShortened { module } => write!(
+                f,
+                "module {} block cooldown is shorter than the parent",
+                module
+            ),
+            Self::ModuleValidityWidened { module } => write!(
+                f,
+                "module {} validity window is wider than the parent",
+                module
+            ),
+            Self::ModuleRateLimitLoosened { module } => write!(
+                f,
+                "module {} rate limit is not as strict as the parent",
+                module
+            ),
+            Self::ModuleConstraintsLoosened { module } => write!(
+                f,
+                "module {} constraints are not as strict as the parent",
+                module
+            ),
+            Self::MethodNotGranted { module, method } => write!(
+                f,
+                "method {}::{} is not granted by the parent",
+                module, method
+            ),
+            Self::MethodCooldownShortened { module, method } => write!(
+                f,
+                "method {}::{} block cooldown is shorter than the parent",
+                module, method
+            ),
+            Self::MethodValidityWidened { module, method } => write!(
+                f,
+                "method {}::{} validity window is wider than the parent",
+                module, method
+            ),
+            Self::MethodUsesIncreased { module, method } => write!(
+                f,
+                "method {}::{} usage quota is larger than the parent",
+                module, method
+            ),
+            Self::MethodRateLimitLoosened { module, method } => write!(
+                f,
+                "method {}::{} rate limit is not as strict as the parent",
+                module, method
+            ),
+            Self::ConstraintsLoosened { module, method } => write!(
+                f,
+                "method {}::{} constraints are not as strict as the parent",
+                module, method
+            ),
+            Self::ContractNotGranted { contract } => {
+                write!(f, "contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " is not granted by the parent")
+            }
+            Self::ContractCooldownShortened { contract } => {
+                write!(f, "contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " block cooldown is shorter than the parent")
+            }
+            Self::ContractValidityWidened { contract } => {
+                write!(f, "contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " validity window is wider than the parent")
+            }
+            Self::ContractUsesIncreased { contract } => {
+                write!(f, "contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " usage quota is larger than the parent")
+            }
+            Self::ContractRateLimitLoosened { contract } => {
+                write!(f, "contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " rate limit is not as strict as the parent")
+            }
+            Self::ContractSelectorsWidened { contract } => {
+                write!(f, "contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " selectors are not granted by the parent")
+            }
+            Self::ContractConstraintsLoosened { contract } => {
+                write!(f, "contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " constraints are not as strict as the parent")
+            }
+        }
+    }
+}
+
+/// Writes a contract address or selector as lower case hex
+pub(crate) fn write_hex(f: &mut Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    write!(f, "0x")?;
+    for byte in bytes {
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,446 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Call Schema
+//!
+//! Argument types of CENNZnet runtime calls, used to decode SCALE encoded
+//! call arguments into Pact types
+//!
+
+use crate::cennznut::{MethodName, ModuleName};
+use alloc::collections::BTreeMap;
+use alloc::fmt::{self, Display, Formatter};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use codec::{Compact, Decode};
+use core::convert::TryFrom;
+use pact::interpreter::types::{Numeric, PactType, StringLike};
+
+/// The SCALE encoded type of a runtime call argument.
+/// Numerics must fit a `u64` to be checked by Pact constraints, and fail to decode otherwise.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArgType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    /// A compact encoded unsigned integer, such as a `Compact<Balance>`
+    Compact,
+    /// Decoded as the numeric `0` or `1`
+    Bool,
+    /// A compact length prefixed byte string
+    Bytes,
+    /// A compact length prefixed utf8 string
+    String,
+    /// A 32 byte account id
+    AccountId,
+}
+
+/// Error which may occur while decoding runtime call arguments.
+/// `offset` is the position of the offending byte(s) in the call arguments and
+/// `index` is the position of the argument in the call.
+#[derive(Debug, Eq, PartialEq)]
+pub enum CallDecodeError {
+    /// The schema does not describe the requested method
+    UnknownCall {
+        module: ModuleName,
+        method: MethodName,
+    },
+    Truncated {
+        offset: usize,
+        index: usize,
+    },
+    InvalidCompact {
+        offset: usize,
+        index: usize,
+    },
+    InvalidBool {
+        offset: usize,
+        index: usize,
+    },
+    InvalidUtf8 {
+        offset: usize,
+        index: usize,
+    },
+    /// A numeric argument is larger than a `u64`
+    NumericOverflow {
+        offset: usize,
+        index: usize,
+    },
+    TrailingBytes {
+        offset: usize,
+    },
+}
+
+impl Display for CallDecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownCall { module, method } => {
+                write!(f, "no call schema for method {}::{}", module, method)
+            }
+            Self::Truncated { offset, index } => {
+                write!(f, "expected argument {} at byte {}", index, offset)
+            }
+            Self::InvalidCompact { offset, index } => {
+                write!(f, "invalid compact argument {} at byte {}", index, offset)
+            }
+            Self::InvalidBool { offset, index } => {
+                write!(f, "invalid bool argument {} at byte {}", index, offset)
+            }
+            Self::InvalidUtf8 { offset, index } => write!(
+                f,
+                "argument {} is not utf8 encoded at byte {}",
+                index, offset
+            ),
+            Self::NumericOverflow { offset, index } => write!(
+                f,
+                "argument {} is too large for a numeric at byte {}",
+                index, offset
+            ),
+            Self::TrailingBytes { offset } => {
+                write!(f, "unexpected trailing bytes at byte {}", offset)
+            }
+        }
+    }
+}
+
+/// Describes the argument types of runtime calls by module and method
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CallSchema {
+    calls: BTreeMap<(ModuleName, MethodName), Vec<ArgType>>,
+}
+
+impl CallSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Describes the `args` of `module_name::method_name`, replacing any earlier description
+    pub fn call(mut self, module_name: &str, method_name: &str, args: Vec<ArgType>) -> Self {
+        self.calls
+            .insert((module_name.to_string(), method_name.to_string()), args);
+        self
+    }
+
+    /// Returns the argument types of `module_name::method_name`, if described
+    pub fn args(&self, module_name: &str, method_name: &str) -> Option<&[ArgType]> {
+        self.calls
+            .get(&(module_name.to_string(), method_name.to_string()))
+            .map(Vec::as_slice)
+    }
+
+    /// Decodes the SCALE encoded `call_args` of `module_name::method_name` into Pact types.
+    /// Byte strings and account ids borrow from `call_args`.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the call is not described, or `call_args` do not match its
+    /// argument types exactly
+    pub fn decode_args<'a>(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        call_args: &'a [u8],
+    ) -> Result<Vec<PactType<'a>>, CallDecodeError> {
+        let arg_types =
+            self.args(module_name, method_name)
+                .ok_or_else(|| CallDecodeError::UnknownCall {
+                    module: module_name.into(),
+                    method: method_name.into(),
+                })?;
+        let mut input = call_args;
+        let mut args = Vec::with_capacity(arg_types.len());
+        for (index, arg_type) in arg_types.iter().enumerate() {
+            let offset = call_args.len() - input.len();
+            args.push(decode_arg(*arg_type, &mut input, offset, index)?);
+        }
+        if input.is_empty() {
+            Ok(args)
+        } else {
+            Err(CallDecodeError::TrailingBytes {
+                offset: call_args.len() - input.len(),
+            })
+        }
+    }
+}
+
+/// Takes the next `len` bytes of `input`
+fn take<'a>(
+    input: &mut &'a [u8],
+    len: usize,
+    offset: usize,
+    index: usize,
+) -> Result<&'a [u8], CallDecodeError> {
+    if input.len() < len {
+        return Err(CallDecodeError::Truncated { offset, index });
+    }
+    let (taken, rest) = input.split_at(len);
+    *input = rest;
+    Ok(taken)
+}
+
+/// Takes the next `len` little endian bytes of `input` as a `u128`
+fn take_uint(
+    input: &mut &[u8],
+    len: usize,
+    offset: usize,
+    index: usize,
+) -> Result<u128, CallDecodeError> {
+    let mut buf = [0_u8; 16];
+    buf[..len].copy_from_slice(take(input, len, offset, index)?);
+    Ok(u128::from_le_bytes(buf))
+}
+
+/// Decodes the next argument of `arg_type` from `input`, which starts at `offset`
+fn decode_arg<'a>(
+    arg_type: ArgType,
+    input: &mut &'a [u8],
+    offset: usize,
+    index: usize,
+) -> Result<PactType<'a>, CallDecodeError> {
+    let value = match arg_type {
+        ArgType::U8 => take_uint(input, 1, offset, index)?,
+        ArgType::U16 => take_uint(input, 2, offset, index)?,
+        ArgType::U32 => take_uint(input, 4, offset, index)?,
+        ArgType::U64 => take_uint(input, 8, offset, index)?,
+        ArgType::U128 => take_uint(input, 16, offset, index)?,
+        ArgType::Compact => decode_compact(input, offset, index)?,
+        ArgType::Bool => match take(input, 1, offset, index)? {
+            [0] => 0,
+            [1] => 1,
+            _ => return Err(CallDecodeError::InvalidBool { offset, index }),
+        },
+        ArgType::Bytes => {
+            let len = decode_len(input, offset, index)?;
+            return Ok(PactType::StringLike(StringLike(take(
+                input, len, offset, index,
+            )?)));
+        }
+        ArgType::String => {
+            let len = decode_len(input, offset, index)?;
+            let bytes = take(input, len, offset, index)?;
+            if core::str::from_utf8(bytes).is_err() {
+                return Err(CallDecodeError::InvalidUtf8 { offset, index });
+            }
+            return Ok(PactType::StringLike(StringLike(bytes)));
+        }
+        ArgType::AccountId => {
+            return Ok(PactType::StringLike(StringLike(take(
+                input, 32, offset, index,
+            )?)))
+        }
+    };
+    u64::try_from(value)
+        .map(|value| PactType::Numeric(Numeric(value)))
+        .map_err(|_| CallDecodeError::NumericOverflow { offset, index })
+}
+
+/// Decodes a compact encoded unsigned integer from `input`
+fn decode_compact(input: &mut &[u8], offset: usize, index: usize) -> Result<u128, CallDecodeError> {
+    if input.is_empty() {
+        return Err(CallDecodeError::Truncated { offset, index });
+    }
+    Compact::<u128>::decode(input)
+        .map(|compact| compact.0)
+        .map_err(|_| CallDecodeError::InvalidCompact { offset, index })
+}
+
+/// Decodes a compact encoded length prefix from `input`
+fn decode_len(input: &mut &[u8], offset: usize, index: usize) -> Result<usize, CallDecodeError> {
+    let len = decode_compact(input, offset, index)?;
+    usize::try_from(len).map_err(|_| CallDecodeError::Truncated { offset, index })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArgType, CallDecodeError, CallSchema};
+    use crate::cennznut::v1::{method::Method, module::Module};
+    use crate::{CENNZnut, CENNZnutV1, Constraint, RuntimeDomain, ValidationErr};
+    use codec::{Compact, Encode};
+    use pact::interpreter::types::{Numeric, PactType, StringLike};
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    fn make_schema() -> CallSchema {
+        CallSchema::new().call(
+            "generic_asset",
+            "transfer",
+            vec![ArgType::U32, ArgType::AccountId, ArgType::Compact],
+        )
+    }
+
+    fn make_call_args(asset_id: u32, amount: u128) -> Vec<u8> {
+        [
+            asset_id.encode(),
+            [0x12_u8; 32].encode(),
+            Compact(amount).encode(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn it_decodes_call_args() {
+        let schema = CallSchema::new().call(
+            "module_test",
+            "method_test",
+            vec![
+                ArgType::U8,
+                ArgType::U16,
+                ArgType::U64,
+                ArgType::U128,
+                ArgType::Bool,
+                ArgType::Bytes,
+                ArgType::String,
+            ],
+        );
+        let call_args = [
+            7_u8.encode(),
+            300_u16.encode(),
+            70_000_u64.encode(),
+            5_u128.encode(),
+            true.encode(),
+            vec![1_u8, 2, 3].encode(),
+            "alice".encode(),
+        ]
+        .concat();
+
+        assert_eq!(
+            schema.decode_args("module_test", "method_test", &call_args),
+            Ok(vec![
+                PactType::Numeric(Numeric(7)),
+                PactType::Numeric(Numeric(300)),
+                PactType::Numeric(Numeric(70_000)),
+                PactType::Numeric(Numeric(5)),
+                PactType::Numeric(Numeric(1)),
+                PactType::StringLike(StringLike(&[1, 2, 3])),
+                PactType::StringLike(StringLike(b"alice")),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_fails_to_decode_mismatched_call_args() {
+        let schema = make_schema();
+        let call_args = make_call_args(16_000, 100);
+
+        assert_eq!(
+            schema.decode_args("generic_asset", "burn", &call_args),
+            Err(CallDecodeError::UnknownCall {
+                module: "generic_asset".into(),
+                method: "burn".into(),
+            })
+        );
+        assert_eq!(
+            schema.decode_args("generic_asset", "transfer", &call_args[..20]),
+            Err(CallDecodeError::Truncated {
+                offset: 4,
+                index: 1
+            })
+        );
+        assert_eq!(
+            schema.decode_args(
+                "generic_asset",
+                "transfer",
+                &[&call_args[..], &[0]].concat()
+            ),
+            Err(CallDecodeError::TrailingBytes { offset: 38 })
+        );
+        let overflow = make_call_args(16_000, u128::from(u64::max_value()) + 1);
+        assert_eq!(
+            schema.decode_args("generic_asset", "transfer", &overflow),
+            Err(CallDecodeError::NumericOverflow {
+                offset: 36,
+                index: 2
+            })
+        );
+
+        let schema =
+            CallSchema::new().call("system", "remark", vec![ArgType::Bool, ArgType::String]);
+        assert_eq!(
+            schema.decode_args("system", "remark", &[2, 0]),
+            Err(CallDecodeError::InvalidBool {
+                offset: 0,
+                index: 0
+            })
+        );
+        assert_eq!(
+            schema
+                .decode_args("system", "remark", &[1, 1 << 2, 0xff])
+                .unwrap_err()
+                .to_string(),
+            "argument 1 is not utf8 encoded at byte 1"
+        );
+    }
+
+    #[test]
+    fn it_validates_encoded_calls() {
+        let amount = Constraint::arg(2).lte(PactType::Numeric(Numeric(100)));
+        let module = Module::new("generic_asset").methods(
+            vec![Method::new("transfer")
+                .constraint(&amount)
+                .expect("it encodes")]
+            .into(),
+        );
+        let cennznut = CENNZnut::V1(CENNZnutV1 {
+            modules: vec![module].into(),
+            contracts: vec![].into(),
+        });
+        let schema = make_schema();
+
+        assert_eq!(
+            cennznut.validate_encoded_call(
+                "generic_asset",
+                "transfer",
+                &make_call_args(16_000, 100),
+                &schema
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            cennznut.validate_encoded_call(
+                "generic_asset",
+                "transfer",
+                &make_call_args(16_000, 101),
+                &schema
+            ),
+            Err(ValidationErr::NoPermission(
+                RuntimeDomain::MethodArguments {
+                    module: "generic_asset".into(),
+                    method: "transfer".into(),
+                }
+            ))
+        );
+        let unknown = cennznut
+            .validate_encoded_call("generic_asset", "burn", &[], &schema)
+            .unwrap_err();
+        assert_eq!(
+            unknown.to_string(),
+            "error while decoding call arguments: no call schema for method generic_asset::burn"
+        );
+    }
+
+    #[test]
+    fn it_fails_to_validate_encoded_calls_with_overflowing_args() {
+        let module = Module::new("generic_asset").methods(vec![Method::new("transfer")].into());
+        let cennznut = CENNZnut::V1(CENNZnutV1 {
+            modules: vec![module].into(),
+            contracts: vec![].into(),
+        });
+
+        // The amount is not read by any constraint, but cannot be decoded
+        assert_eq!(
+            cennznut.validate_encoded_call(
+                "generic_asset",
+                "transfer",
+                &make_call_args(16_000, u128::from(u64::max_value()) + 1),
+                &make_schema()
+            ),
+            Err(ValidationErr::ArgumentsDecoding(
+                CallDecodeError::NumericOverflow {
+                    offset: 36,
+                    index: 2
+                }
+            ))
+        );
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Canonicalize
+//!
+//! Errors which may occur while canonicalizing a CENNZnut
+//!
+
+use crate::attenuation::write_hex;
+use crate::cennznut::{ContractAddress, MethodName, ModuleName};
+use alloc::fmt::{self, Display, Formatter};
+
+/// Error which may occur while canonicalizing a CENNZnut
+#[derive(Debug, Eq, PartialEq)]
+pub enum CanonicalizeError {
+    /// A module name is listed more than once with different grants
+    ConflictingModules(ModuleName),
+    /// A method name is listed more than once in a module with different grants
+    ConflictingMethods {
+        module: ModuleName,
+        method: MethodName,
+    },
+    /// A contract address is listed more than once with different grants
+    ConflictingContracts(ContractAddress),
+}
+
+impl Display for CanonicalizeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConflictingModules(module) => {
+                write!(f, "module {} is listed with different grants", module)
+            }
+            Self::ConflictingMethods { module, method } => write!(
+                f,
+                "method {}::{} is listed with different grants",
+                module, method
+            ),
+            Self::ConflictingContracts(contract) => {
+                write!(f, "contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " is listed with different grants")
+            }
+        }
+    }
+}
@@ -4,48 +4,110 @@
 //! Collection of versioned `CENNZnuts`
 //!
 
+use alloc::borrow::Cow;
 use alloc::fmt::{self, Display, Formatter};
 use alloc::string::String;
+use alloc::vec::Vec;
 
 use codec::{Decode, Encode, Input, Output};
 use pact::interpreter::types::PactType;
 
-use crate::PartialDecode;
+use crate::attenuation::write_hex;
+use crate::decode::{DecodeField, DecodeInput};
+use crate::AttenuationErr;
+use crate::CallHistory;
+use crate::CallSchema;
+use crate::CooldownTracker;
+use crate::DecodeError;
+use crate::EncodeError;
+use crate::GrantPath;
+use crate::NutHash;
+use crate::PermissionDiff;
+use crate::UsageCounter;
 use crate::ValidationErr;
+use crate::ValidationTrace;
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 pub mod v0;
+pub mod v1;
 
 use core::convert::TryFrom;
 use v0::CENNZnutV0;
-use CENNZnut::V0;
+use v1::CENNZnutV1;
+use CENNZnut::{V0, V1};
 
 pub type ModuleName = String;
 pub type MethodName = String;
 pub type ContractAddress = [u8; 32];
+pub type ContractSelector = [u8; 4];
 pub const CONTRACT_WILDCARD: ContractAddress = [0_u8; 32];
 pub const WILDCARD: &str = "*";
 
-/// A CENNZnet module permission domain
+/// Returns the distinct `granted` names followed by the `wildcard`.
+/// Any name which is not granted explicitly resolves to the same grants as the `wildcard`.
+pub(crate) fn requested_names<T: Copy + PartialEq>(
+    granted: impl Iterator<Item = T>,
+    wildcard: T,
+) -> Vec<T> {
+    let mut names: Vec<T> = Vec::default();
+    for name in granted.chain(Some(wildcard)) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// A CENNZnet module permission domain, naming the requested module and method
 #[derive(Debug, Eq, PartialEq)]
 pub enum RuntimeDomain {
-    Method,
-    MethodArguments,
-    Module,
+    Method {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodArguments {
+        module: ModuleName,
+        method: MethodName,
+    },
+    /// The arguments of a method call, as constrained by its module
+    ModuleArguments {
+        module: ModuleName,
+        method: MethodName,
+    },
+    Module {
+        module: ModuleName,
+    },
 }
 
-/// A CENNZnet contract permission domain
+/// A CENNZnet contract permission domain, naming the requested contract and message selector
 #[derive(Debug, Eq, PartialEq)]
 pub enum ContractDomain {
-    Contract,
+    Contract {
+        address: ContractAddress,
+    },
+    Selector {
+        address: ContractAddress,
+        selector: ContractSelector,
+    },
+    SelectorArguments {
+        address: ContractAddress,
+        selector: ContractSelector,
+    },
 }
 
 impl Display for RuntimeDomain {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Method => write!(f, "method"),
-            Self::MethodArguments => write!(f, "method arguments"),
-            Self::Module => write!(f, "module"),
+            Self::Method { module, method } => write!(f, "method {}::{}", module, method),
+            Self::MethodArguments { module, method } => {
+                write!(f, "method {}::{} arguments", module, method)
+            }
+            Self::ModuleArguments { module, method } => write!(
+                f,
+                "method {}::{} arguments under the module constraints",
+                module, method
+            ),
+            Self::Module { module } => write!(f, "module {}", module),
         }
     }
 }
@@ -53,15 +115,33 @@ impl Display for RuntimeDomain {
 impl Display for ContractDomain {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Contract => write!(f, "contract"),
+            Self::Contract { address } => {
+                write!(f, "contract ")?;
+                write_hex(f, address)
+            }
+            Self::Selector { address, selector } => {
+                write!(f, "contract ")?;
+                write_hex(f, address)?;
+                write!(f, " selector ")?;
+                write_hex(f, selector)
+            }
+            Self::SelectorArguments { address, selector } => {
+                write!(f, "contract ")?;
+                write_hex(f, address)?;
+                write!(f, " selector ")?;
+                write_hex(f, selector)?;
+                write!(f, " arguments")
+            }
         }
     }
 }
 
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[cfg_attr(test, derive(Clone, Debug, Eq, PartialEq))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub enum CENNZnut {
     V0(CENNZnutV0),
+    V1(CENNZnutV1),
 }
 
 #[allow(unreachable_patterns)]
@@ -75,28 +155,135 @@ impl TryFrom<CENNZnut> for CENNZnutV0 {
     }
 }
 
+#[allow(unreachable_patterns)]
+impl TryFrom<CENNZnut> for CENNZnutV1 {
+    type Error = codec::Error;
+    fn try_from(v: CENNZnut) -> Result<Self, Self::Error> {
+        match v {
+            V1(inner) => Ok(inner),
+            _ => Err(codec::Error::from("CENNZnut version is not 1")),
+        }
+    }
+}
+
+impl CENNZnut {
+    /// Encodes the CENNZnut, failing if it exceeds any encoding limits
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the specific cennznut version cannot be encoded
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        match &self {
+            V0(inner) => inner.try_encode(),
+            V1(inner) => inner.try_encode(),
+        }
+    }
+}
+
+impl CENNZnut {
+    /// Converts the CENNZnut to the latest version.
+    /// Every permission of an earlier version is representable in the latest version.
+    pub fn upgrade_to_latest(self) -> Self {
+        match self {
+            V0(inner) => V1(inner.into()),
+            V1(inner) => V1(inner),
+        }
+    }
+}
+
+impl CENNZnut {
+    /// Checks every call permitted by this CENNZnut is also permitted by `parent`,
+    /// i.e. this CENNZnut may be safely delegated from `parent`.
+    /// Module, method and contract cooldowns must be at least as long as the parent's and
+    /// constraints must be the parent's constraints, or stricter.
+    ///
+    /// # Errors
+    ///
+    /// Will return error identifying the first grant which exceeds the `parent`
+    pub fn is_subset_of(&self, parent: &Self) -> Result<(), AttenuationErr> {
+        self.to_latest().is_subset_of(&parent.to_latest())
+    }
+
+    /// Returns the permission changes from CENNZnut `a` to `b`,
+    /// independent of their versions and the order of their entries
+    pub fn diff(a: &Self, b: &Self) -> PermissionDiff {
+        a.to_latest().diff(&b.to_latest())
+    }
+
+    /// Returns the CENNZnut as the latest version, converting earlier versions
+    fn to_latest(&self) -> Cow<'_, CENNZnutV1> {
+        match self {
+            V0(inner) => Cow::Owned(inner.clone().into()),
+            V1(inner) => Cow::Borrowed(inner),
+        }
+    }
+}
+
 impl Encode for CENNZnut {
     fn encode_to<T: Output>(&self, buf: &mut T) {
-        match &self {
-            V0(inner) => inner.encode_to(buf),
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
         }
     }
 }
 
-impl Decode for CENNZnut {
-    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
-        let version = u16::from_le_bytes([input.read_byte()?, input.read_byte()?]);
+impl CENNZnut {
+    /// Decodes a versioned CENNZnut from `encoded`, which must contain no trailing bytes.
+    /// Unlike `Decode`, failures identify the byte offset, field and permission domain.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if `encoded` is not a valid CENNZnut encoding
+    pub fn decode_detailed(encoded: &[u8]) -> Result<Self, DecodeError> {
+        let mut slice = encoded;
+        Self::decode_exact(&mut DecodeInput::new(&mut slice), encoded.len())
+    }
+
+    /// Decodes a versioned CENNZnut from `encoded` as `decode_detailed`, also rejecting
+    /// encodings which are accepted but not canonical: reserved header bits, NUL bytes within
+    /// names, empty constraints, and module, method or contract entries which are duplicated
+    /// or out of order.
+    /// Modules and methods must be sorted by name and contracts by address, as
+    /// `CENNZnutV0::canonicalize` orders them, so each permission set has one encoding.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if `encoded` is not a canonical CENNZnut encoding
+    pub fn decode_strict(encoded: &[u8]) -> Result<Self, DecodeError> {
+        let mut slice = encoded;
+        Self::decode_exact(&mut DecodeInput::strict(&mut slice), encoded.len())
+    }
+
+    /// Decodes a versioned CENNZnut which must consume all `length` bytes of the `input`
+    fn decode_exact(input: &mut DecodeInput<&[u8]>, length: usize) -> Result<Self, DecodeError> {
+        let cennznut = Self::decode_versioned(input)?;
+        if input.offset() < length {
+            return Err(DecodeError::TrailingBytes {
+                offset: input.offset(),
+            });
+        }
+        Ok(cennznut)
+    }
+
+    fn decode_versioned<I: Input>(input: &mut DecodeInput<I>) -> Result<Self, DecodeError> {
+        let mut version_buf = [0_u8; 2];
+        input.read(&mut version_buf, DecodeField::Version)?;
+        let version = u16::from_le_bytes(version_buf);
 
         match version {
-            0 => match CENNZnutV0::partial_decode(input) {
-                Ok(inner) => Ok(V0(inner)),
-                Err(e) => Err(e),
-            },
-            _ => Err(codec::Error::from("unexpected version")),
+            0 => Ok(V0(CENNZnutV0::partial_decode_detailed(input)?)),
+            1 => Ok(V1(CENNZnutV1::partial_decode_detailed(input)?)),
+            _ => Err(DecodeError::BadVersion { offset: 0, version }),
         }
     }
 }
 
+impl Decode for CENNZnut {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::decode_versioned(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
 impl CENNZnut {
     /// Validates a CENNZnut runtime module call by:
     /// (1) identifying the version to be validated
@@ -113,9 +300,136 @@ impl CENNZnut {
     ) -> Result<(), ValidationErr<RuntimeDomain>> {
         match &self {
             V0(inner) => inner.validate_module(module_name, method_name, args),
+            V1(inner) => inner.validate_module(module_name, method_name, args),
+        }
+    }
+
+    /// Validates a CENNZnut runtime module call with SCALE encoded `call_args` by:
+    /// (1) decoding the `call_args` with the argument types of the `schema`
+    /// (2) validating the decoded arguments as `validate_runtime_call`
+    /// Every argument is decoded before validation, so a numeric argument larger than a `u64`
+    /// fails the call even if no constraint reads it.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if decoding fails, or validation fails with the type of error
+    /// embedded in `RuntimeDomain`
+    pub fn validate_encoded_call(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        call_args: &[u8],
+        schema: &CallSchema,
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        let args = schema
+            .decode_args(module_name, method_name, call_args)
+            .map_err(ValidationErr::ArgumentsDecoding)?;
+        self.validate_runtime_call(module_name, method_name, &args)
+    }
+
+    /// Validates a CENNZnut runtime module call at `block_number` by:
+    /// (1) identifying the version to be validated
+    /// (2) executing the specific cennznut version's validation function
+    /// (3) enforcing block cooldowns against, and recording the call in, the `tracker`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_runtime_call_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        match &self {
+            V0(inner) => {
+                inner.validate_module_at(block_number, tracker, module_name, method_name, args)
+            }
+            V1(inner) => {
+                inner.validate_module_at(block_number, tracker, module_name, method_name, args)
+            }
         }
     }
 
+    /// Validates a CENNZnut runtime module call at `block_number` as `validate_runtime_call_at`,
+    /// and by enforcing rate limits against, and recording the call in, the `history`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_runtime_call_rate_limited<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        match &self {
+            // Earlier versions have no rate limits
+            V0(inner) => {
+                inner.validate_module_at(block_number, history, module_name, method_name, args)
+            }
+            V1(inner) => inner.validate_module_rate_limited(
+                block_number,
+                history,
+                module_name,
+                method_name,
+                args,
+            ),
+        }
+    }
+
+    /// Validates a CENNZnut runtime module call at `block_number` as
+    /// `validate_runtime_call_rate_limited`, and by enforcing method usage quotas against,
+    /// and recording the use in, the `counter` for the CENNZnut `nut_hash`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    #[allow(clippy::too_many_arguments)]
+    pub fn validate_runtime_call_metered<H: CallHistory, U: UsageCounter>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        nut_hash: &NutHash,
+        counter: &mut U,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        match &self {
+            // Earlier versions have no usage quotas
+            V0(inner) => {
+                inner.validate_module_at(block_number, history, module_name, method_name, args)
+            }
+            V1(inner) => inner.validate_module_metered(
+                block_number,
+                history,
+                nut_hash,
+                counter,
+                module_name,
+                method_name,
+                args,
+            ),
+        }
+    }
+
+    /// Validates a CENNZnut runtime module call as `validate_runtime_call`,
+    /// recording the matched module and method grants, the block cooldowns in effect
+    /// and the outcome of interpreting constraints
+    pub fn explain_runtime_call(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> ValidationTrace {
+        self.to_latest()
+            .explain_module(module_name, method_name, args)
+    }
+
     /// Validates a CENNZnut smart contract call by:
     /// (1) identifying the version to be validated
     /// (2) executing the specific cennznut version's validation function
@@ -129,30 +443,226 @@ impl CENNZnut {
     ) -> Result<(), ValidationErr<ContractDomain>> {
         match &self {
             V0(inner) => inner.validate_contract(*contract_address),
+            V1(inner) => inner.validate_contract(*contract_address),
+        }
+    }
+
+    /// Validates a CENNZnut smart contract call of the message `selector` with `args` by:
+    /// (1) identifying the version to be validated
+    /// (2) executing the specific cennznut version's validation function,
+    /// checking the selector allow-list and executing the Pact interpreter if constraints exist
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_call_with(
+        &self,
+        contract_address: &ContractAddress,
+        selector: &ContractSelector,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        match &self {
+            // Earlier versions permit every call of a granted contract
+            V0(inner) => inner.validate_contract(*contract_address),
+            V1(inner) => inner.validate_contract_with(*contract_address, selector, args),
+        }
+    }
+
+    /// Validates a CENNZnut smart contract call at `block_number` by:
+    /// (1) identifying the version to be validated
+    /// (2) executing the specific cennznut version's validation function
+    /// (3) enforcing block cooldowns against, and recording the call in, the `tracker`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_call_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        contract_address: &ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        match &self {
+            V0(inner) => inner.validate_contract_at(block_number, tracker, *contract_address),
+            V1(inner) => inner.validate_contract_at(block_number, tracker, *contract_address),
+        }
+    }
+
+    /// Validates a CENNZnut smart contract call of the message `selector` with `args` at
+    /// `block_number` as `validate_contract_call_at`, checking the selector allow-list and
+    /// executing the Pact interpreter if constraints exist
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_call_at_with<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        contract_address: &ContractAddress,
+        selector: &ContractSelector,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        match &self {
+            // Earlier versions permit every call of a granted contract
+            V0(inner) => inner.validate_contract_at(block_number, tracker, *contract_address),
+            V1(inner) => inner.validate_contract_at_with(
+                block_number,
+                tracker,
+                *contract_address,
+                selector,
+                args,
+            ),
+        }
+    }
+
+    /// Validates a CENNZnut smart contract call at `block_number` as `validate_contract_call_at`,
+    /// and by enforcing rate limits against, and recording the call in, the `history`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_call_rate_limited<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        contract_address: &ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        match &self {
+            // Earlier versions have no rate limits
+            V0(inner) => inner.validate_contract_at(block_number, history, *contract_address),
+            V1(inner) => {
+                inner.validate_contract_rate_limited(block_number, history, *contract_address)
+            }
+        }
+    }
+
+    /// Validates a CENNZnut smart contract call of the message `selector` with `args` at
+    /// `block_number` as `validate_contract_call_rate_limited`, checking the selector
+    /// allow-list and executing the Pact interpreter if constraints exist
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_call_rate_limited_with<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        contract_address: &ContractAddress,
+        selector: &ContractSelector,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        match &self {
+            // Earlier versions have no rate limits, and permit every call of a granted contract
+            V0(inner) => inner.validate_contract_at(block_number, history, *contract_address),
+            V1(inner) => inner.validate_contract_rate_limited_with(
+                block_number,
+                history,
+                *contract_address,
+                selector,
+                args,
+            ),
+        }
+    }
+
+    /// Validates a CENNZnut smart contract call at `block_number` as
+    /// `validate_contract_call_rate_limited`, and by enforcing contract usage quotas against,
+    /// and recording the use in, the `counter` for the CENNZnut `nut_hash`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_call_metered<H: CallHistory, U: UsageCounter>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        nut_hash: &NutHash,
+        counter: &mut U,
+        contract_address: &ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        match &self {
+            // Earlier versions have no usage quotas
+            V0(inner) => inner.validate_contract_at(block_number, history, *contract_address),
+            V1(inner) => inner.validate_contract_metered(
+                block_number,
+                history,
+                nut_hash,
+                counter,
+                *contract_address,
+            ),
+        }
+    }
+
+    /// Validates a CENNZnut smart contract call of the message `selector` with `args` at
+    /// `block_number` as `validate_contract_call_metered`, checking the selector allow-list and
+    /// executing the Pact interpreter if constraints exist
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    #[allow(clippy::too_many_arguments)]
+    pub fn validate_contract_call_metered_with<H: CallHistory, U: UsageCounter>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        nut_hash: &NutHash,
+        counter: &mut U,
+        contract_address: &ContractAddress,
+        selector: &ContractSelector,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        match &self {
+            // Earlier versions have no usage quotas, and permit every call of a granted contract
+            V0(inner) => inner.validate_contract_at(block_number, history, *contract_address),
+            V1(inner) => inner.validate_contract_metered_with(
+                block_number,
+                history,
+                nut_hash,
+                counter,
+                *contract_address,
+                selector,
+                args,
+            ),
+        }
+    }
+
+    /// Returns the uses remaining of the `grant` usage quota, as counted by the `counter` for
+    /// the CENNZnut `nut_hash`, or `None` if the grant does not exist or is unlimited
+    pub fn remaining_uses<U: UsageCounter>(
+        &self,
+        nut_hash: &NutHash,
+        counter: &U,
+        grant: &GrantPath,
+    ) -> Option<u32> {
+        match &self {
+            V0(_) => None,
+            V1(inner) => inner.remaining_uses(nut_hash, counter, grant),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::v0::set::{ContractSet, MethodSet, ModuleSet};
     use super::v0::{contract::Contract, method::Method, module::Module};
-    use super::{CENNZnut, CENNZnutV0, ContractAddress, MethodName, ModuleName};
+    use super::{CENNZnut, CENNZnutV0, ContractDomain, RuntimeDomain};
+    use crate::{AttenuationErr, InMemoryCooldownTracker, ValidationErr};
 
-    fn make_methods(method: &Method) -> Vec<(MethodName, Method)> {
-        let mut methods = Vec::<(MethodName, Method)>::default();
-        methods.push((method.name.clone(), method.clone()));
+    fn make_methods(method: &Method) -> MethodSet {
+        let mut methods = MethodSet::new();
+        methods.push(method.clone());
         methods
     }
 
-    fn make_modules(module: &Module) -> Vec<(ModuleName, Module)> {
-        let mut modules = Vec::<(ModuleName, Module)>::default();
-        modules.push((module.name.clone(), module.clone()));
+    fn make_modules(module: &Module) -> ModuleSet {
+        let mut modules = ModuleSet::new();
+        modules.push(module.clone());
         modules
     }
 
-    fn make_contracts(contract: &Contract) -> Vec<(ContractAddress, Contract)> {
-        let mut contracts = Vec::<(ContractAddress, Contract)>::default();
-        contracts.push((contract.address, contract.clone()));
+    fn make_contracts(contract: &Contract) -> ContractSet {
+        let mut contracts = ContractSet::new();
+        contracts.push(contract.clone());
         contracts
     }
 
@@ -163,7 +673,7 @@ mod test {
         let module = Module::new("module_test").methods(methods);
         let modules = make_modules(&module);
 
-        let contracts = Vec::<(ContractAddress, Contract)>::default();
+        let contracts = ContractSet::new();
 
         let cennznut = CENNZnut::V0(CENNZnutV0 { modules, contracts });
 
@@ -175,7 +685,7 @@ mod test {
 
     #[test]
     fn it_validates_v0_contracts() {
-        let modules = Vec::<(ModuleName, Module)>::default();
+        let modules = ModuleSet::new();
 
         let contract = Contract::new(&[0x12_u8; 32]);
         let contracts = make_contracts(&contract);
@@ -183,5 +693,92 @@ mod test {
         let cennznut = CENNZnut::V0(CENNZnutV0 { modules, contracts });
 
         assert_eq!(cennznut.validate_contract_call(&contract.address), Ok(()));
+        assert_eq!(
+            cennznut.validate_contract_call_with(&contract.address, &[0x12_u8; 4], &[]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn it_validates_v0_module_cooldowns() {
+        let method = Method::new("method_test").block_cooldown(5);
+        let methods = make_methods(&method);
+        let module = Module::new("module_test").methods(methods);
+        let modules = make_modules(&module);
+
+        let contracts = ContractSet::new();
+
+        let cennznut = CENNZnut::V0(CENNZnutV0 { modules, contracts });
+        let mut tracker = InMemoryCooldownTracker::new();
+
+        assert_eq!(
+            cennznut.validate_runtime_call_at(10, &mut tracker, &module.name, &method.name, &[]),
+            Ok(())
+        );
+        assert_eq!(
+            cennznut.validate_runtime_call_at(14, &mut tracker, &module.name, &method.name, &[]),
+            Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Method {
+                module: "module_test".into(),
+                method: "method_test".into(),
+            }))
+        );
+        assert_eq!(
+            cennznut.validate_runtime_call_at(15, &mut tracker, &module.name, &method.name, &[]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn it_validates_v0_contract_cooldowns() {
+        let modules = ModuleSet::new();
+
+        let contract = Contract::new(&[0x12_u8; 32]).block_cooldown(5);
+        let contracts = make_contracts(&contract);
+
+        let cennznut = CENNZnut::V0(CENNZnutV0 { modules, contracts });
+        let mut tracker = InMemoryCooldownTracker::new();
+
+        assert_eq!(
+            cennznut.validate_contract_call_at(10, &mut tracker, &contract.address),
+            Ok(())
+        );
+        assert_eq!(
+            cennznut.validate_contract_call_at(14, &mut tracker, &contract.address),
+            Err(ValidationErr::CooldownNotElapsed(
+                ContractDomain::Contract {
+                    address: [0x12_u8; 32],
+                }
+            ))
+        );
+        assert_eq!(
+            cennznut.validate_contract_call_at(15, &mut tracker, &contract.address),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn it_checks_subsets_across_versions() {
+        let method = Method::new("method_test");
+        let methods = make_methods(&method);
+        let module = Module::new("module_test").methods(methods);
+        let modules = make_modules(&module);
+        let contracts = ContractSet::new();
+        let child = CENNZnut::V0(CENNZnutV0 { modules, contracts });
+
+        let method = Method::new("*");
+        let methods = make_methods(&method);
+        let module = Module::new("module_test").methods(methods);
+        let modules = make_modules(&module);
+        let contracts = ContractSet::new();
+        let parent = CENNZnut::V0(CENNZnutV0 { modules, contracts }).upgrade_to_latest();
+
+        assert_eq!(child.is_subset_of(&parent), Ok(()));
+        assert_eq!(
+            parent.is_subset_of(&child),
+            Err(AttenuationErr::MethodNotGranted {
+                module: "module_test".to_string(),
+                method: "*".to_string(),
+            })
+        );
     }
 }
@@ -0,0 +1,426 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V0 Borrowed
+//!
+//! Zero-copy view of V0 CENNZnut bytes for validating a single call
+//!
+
+use super::VERSION_BYTES;
+use crate::cennznut::{ContractAddress, ContractDomain, RuntimeDomain};
+use crate::cennznut::{CONTRACT_WILDCARD, WILDCARD};
+use crate::decode::{DecodeField, DecodePath, DecodeSlice};
+use crate::validation::interpret_constraints;
+use crate::{DecodeError, ValidationErr};
+use core::convert::TryFrom;
+use pact::contract::Contract as PactContract;
+use pact::interpreter::types::PactType;
+
+const BLOCK_COOLDOWN_MASK: u8 = 0x01;
+const CONSTRAINTS_MASK: u8 = 0x02;
+const NAME_BYTES: usize = 32;
+
+/// A V0 CENNZnut borrowed from its encoding.
+/// The layout is checked once by `CENNZnutRef::new`, lookups then walk the encoded bytes
+/// without allocating.
+pub struct CENNZnutRef<'a> {
+    modules: &'a [u8],
+    module_count: usize,
+    contracts: &'a [u8],
+    contract_count: usize,
+}
+
+/// A module borrowed from V0 CENNZnut bytes
+pub struct ModuleRef<'a> {
+    pub name: &'a str,
+    pub block_cooldown: Option<u32>,
+    methods: &'a [u8],
+    method_count: usize,
+}
+
+/// A method borrowed from V0 CENNZnut bytes
+pub struct MethodRef<'a> {
+    pub name: &'a str,
+    pub block_cooldown: Option<u32>,
+    pub constraints: Option<&'a [u8]>,
+}
+
+/// A contract borrowed from V0 CENNZnut bytes
+pub struct ContractRef<'a> {
+    pub address: &'a ContractAddress,
+    pub block_cooldown: Option<u32>,
+}
+
+/// Reads a NUL padded name
+fn read_name<'a>(input: &mut DecodeSlice<'a>, field: DecodeField) -> Result<&'a str, DecodeError> {
+    let offset = input.offset();
+    let name = core::str::from_utf8(input.take(NAME_BYTES, field)?).map_err(|_| {
+        DecodeError::InvalidUtf8Name {
+            offset,
+            path: DecodePath::default(),
+            field,
+        }
+    })?;
+    Ok(name.trim_matches(char::from(0)))
+}
+
+/// Reads a method, checking its constraints decode when `check_constraints` is set
+fn read_method<'a>(
+    input: &mut DecodeSlice<'a>,
+    check_constraints: bool,
+) -> Result<MethodRef<'a>, DecodeError> {
+    let header = input.read_byte(DecodeField::MethodHeader)?;
+    let name = read_name(input, DecodeField::MethodName)?;
+    let block_cooldown = if (header & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK {
+        Some(input.read_u32(DecodeField::MethodCooldown)?)
+    } else {
+        None
+    };
+    let constraints = if (header & CONSTRAINTS_MASK) == CONSTRAINTS_MASK {
+        let length = usize::from(input.read_byte(DecodeField::ConstraintsLength)?) + 1;
+        let offset = input.offset();
+        let constraints = input.take(length, DecodeField::Constraints)?;
+        if check_constraints && PactContract::decode(constraints).is_err() {
+            return Err(DecodeError::InvalidConstraints {
+                offset,
+                path: DecodePath::default(),
+            });
+        }
+        Some(constraints)
+    } else {
+        None
+    };
+    Ok(MethodRef {
+        name,
+        block_cooldown,
+        constraints,
+    })
+}
+
+/// Reads a module and its methods, checking method constraints when `check_constraints` is set
+fn read_module<'a>(
+    input: &mut DecodeSlice<'a>,
+    check_constraints: bool,
+) -> Result<ModuleRef<'a>, DecodeError> {
+    let header = input.read_byte(DecodeField::ModuleHeader)?;
+    let method_count = usize::from(header >> 1) + 1;
+    let name = read_name(input, DecodeField::ModuleName)?;
+    let block_cooldown = if (header & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK {
+        Some(input.read_u32(DecodeField::ModuleCooldown)?)
+    } else {
+        None
+    };
+    let methods_offset = input.offset();
+    for index in 0..method_count {
+        read_method(input, check_constraints).map_err(|e| e.in_method(index))?;
+    }
+    Ok(ModuleRef {
+        name,
+        block_cooldown,
+        methods: input.read_since(methods_offset),
+        method_count,
+    })
+}
+
+fn read_contract<'a>(input: &mut DecodeSlice<'a>) -> Result<ContractRef<'a>, DecodeError> {
+    let header = input.read_byte(DecodeField::ContractHeader)?;
+    let address = <&ContractAddress>::try_from(
+        input.take(CONTRACT_WILDCARD.len(), DecodeField::ContractAddress)?,
+    )
+    .expect("a contract address is 32 bytes");
+    let block_cooldown = if (header & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK {
+        Some(input.read_u32(DecodeField::ContractCooldown)?)
+    } else {
+        None
+    };
+    Ok(ContractRef {
+        address,
+        block_cooldown,
+    })
+}
+
+impl<'a> CENNZnutRef<'a> {
+    /// Borrows the V0 CENNZnut encoded in `encoded`, including its version
+    ///
+    /// # Errors
+    ///
+    /// Will return the error `CENNZnut::decode_detailed` returns for the same bytes,
+    /// or `DecodeError::BadVersion` if they do not encode a V0 CENNZnut
+    pub fn new(encoded: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut input = DecodeSlice::new(encoded);
+        let version = input.take(VERSION_BYTES.len(), DecodeField::Version)?;
+        if version != VERSION_BYTES {
+            return Err(DecodeError::BadVersion {
+                offset: 0,
+                version: u16::from_le_bytes([version[0], version[1]]),
+            });
+        }
+
+        let module_count = usize::from(input.read_byte(DecodeField::ModuleCount)?) + 1;
+        let modules_offset = input.offset();
+        for index in 0..module_count {
+            read_module(&mut input, true).map_err(|e| e.in_module(index))?;
+        }
+        let modules = input.read_since(modules_offset);
+
+        let contract_count = usize::from(input.read_byte(DecodeField::ContractCount)?);
+        let contracts_offset = input.offset();
+        for index in 0..contract_count {
+            read_contract(&mut input).map_err(|e| e.in_contract(index))?;
+        }
+        let contracts = input.read_since(contracts_offset);
+
+        if input.offset() < encoded.len() {
+            return Err(DecodeError::TrailingBytes {
+                offset: input.offset(),
+            });
+        }
+
+        Ok(Self {
+            modules,
+            module_count,
+            contracts,
+            contract_count,
+        })
+    }
+
+    /// Returns the modules in encoded order
+    pub fn modules(&self) -> impl Iterator<Item = ModuleRef<'a>> {
+        let mut input = DecodeSlice::new(self.modules);
+        // The layout was checked in `new`, so reading cannot fail
+        (0..self.module_count).filter_map(move |_| read_module(&mut input, false).ok())
+    }
+
+    /// Returns the contracts in encoded order
+    pub fn contracts(&self) -> impl Iterator<Item = ContractRef<'a>> {
+        let mut input = DecodeSlice::new(self.contracts);
+        (0..self.contract_count).filter_map(move |_| read_contract(&mut input).ok())
+    }
+
+    /// Returns the module, if it exists in the CENNZnut
+    /// Wildcard modules have lower priority than defined modules
+    pub fn get_module(&self, module: &str) -> Option<ModuleRef<'a>> {
+        let mut outcome: Option<ModuleRef<'a>> = None;
+        for m in self.modules() {
+            if m.name == module {
+                return Some(m);
+            } else if m.name == WILDCARD {
+                outcome = Some(m);
+            }
+        }
+        outcome
+    }
+
+    /// Returns the contract, if it exists in the CENNZnut
+    /// Wildcard contracts (addr: 0) have lower priority than defined contracts
+    pub fn get_contract(&self, contract: &ContractAddress) -> Option<ContractRef<'a>> {
+        let mut outcome: Option<ContractRef<'a>> = None;
+        for c in self.contracts() {
+            if c.address == contract {
+                return Some(c);
+            } else if c.address == &CONTRACT_WILDCARD {
+                outcome = Some(c);
+            }
+        }
+        outcome
+    }
+
+    /// Validates a CENNZnut runtime module call as `CENNZnut::validate_runtime_call` by:
+    /// (1) looking for `module_name` and `method_name`
+    /// (2) executing the Pact interpreter if constraints exist
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_runtime_call(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        let module = self.get_module(module_name).ok_or_else(|| {
+            ValidationErr::NoPermission(RuntimeDomain::Module {
+                module: module_name.into(),
+            })
+        })?;
+        let method = module.get_method(method_name).ok_or_else(|| {
+            ValidationErr::NoPermission(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            })
+        })?;
+        if let Some(pact) = method.get_pact() {
+            interpret_constraints(&pact, args, module_name, method_name)?;
+        }
+        Ok(())
+    }
+
+    /// Validates a CENNZnut smart contract call as `CENNZnut::validate_contract_call` by
+    /// (1) looking for `contract_address`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_call(
+        &self,
+        contract_address: &ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        self.get_contract(contract_address)
+            .ok_or(ValidationErr::NoPermission(ContractDomain::Contract {
+                address: *contract_address,
+            }))?;
+        Ok(())
+    }
+}
+
+impl<'a> ModuleRef<'a> {
+    /// Returns the methods in encoded order
+    pub fn methods(&self) -> impl Iterator<Item = MethodRef<'a>> {
+        let mut input = DecodeSlice::new(self.methods);
+        (0..self.method_count).filter_map(move |_| read_method(&mut input, false).ok())
+    }
+
+    /// Returns the method, if it exists in the Module
+    /// Wildcard methods have lower priority than defined methods
+    pub fn get_method(&self, method: &str) -> Option<MethodRef<'a>> {
+        let mut outcome: Option<MethodRef<'a>> = None;
+        for m in self.methods() {
+            if m.name == method {
+                return Some(m);
+            } else if m.name == WILDCARD {
+                outcome = Some(m);
+            }
+        }
+        outcome
+    }
+}
+
+impl<'a> MethodRef<'a> {
+    /// Returns the Pact contract, if it exists in the Method
+    pub fn get_pact(&self) -> Option<PactContract<'a>> {
+        self.constraints
+            .and_then(|constraints| PactContract::decode(constraints).ok())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::tests::make_overlapping_cennznut;
+    use super::CENNZnutRef;
+    use crate::cennznut::{CONTRACT_WILDCARD, WILDCARD};
+    use crate::{CENNZnut, CENNZnutV1, DecodeError, DecodeField, DecodePath};
+    use codec::Encode;
+    use pact::interpreter::types::{Numeric, PactType};
+    use std::assert_eq;
+
+    #[test]
+    fn it_validates_as_the_decoded_cennznut() {
+        let encoded = make_overlapping_cennznut().encode();
+        let borrowed = CENNZnutRef::new(&encoded).expect("it borrows");
+        let owned = CENNZnut::decode_detailed(&encoded).expect("it decodes");
+        let args = [
+            vec![],
+            vec![PactType::Numeric(Numeric(100))],
+            vec![PactType::Numeric(Numeric(101))],
+        ];
+
+        for module in &["balances", "staking", WILDCARD] {
+            for method in &["transfer", "burn", "approve", "mint", "stake", WILDCARD] {
+                for args in &args {
+                    assert_eq!(
+                        borrowed.validate_runtime_call(module, method, args),
+                        owned.validate_runtime_call(module, method, args),
+                    );
+                }
+            }
+        }
+        for address in &[[0x12_u8; 32], [0x34_u8; 32], CONTRACT_WILDCARD] {
+            assert_eq!(
+                borrowed.validate_contract_call(address),
+                owned.validate_contract_call(address)
+            );
+        }
+    }
+
+    #[test]
+    fn it_borrows_names_and_constraints() {
+        let cennznut = make_overlapping_cennznut();
+        let encoded = cennznut.encode();
+        let borrowed = CENNZnutRef::new(&encoded).expect("it borrows");
+
+        let module = borrowed.get_module("balances").expect("it is granted");
+        assert_eq!(module.name, "balances");
+        let method = module.get_method("transfer").expect("it is granted");
+        assert_eq!(method.name, "transfer");
+        assert_eq!(
+            method.constraints,
+            cennznut.modules[1].methods[0].constraints.as_deref()
+        );
+        assert_eq!(
+            module.get_method("stake").map(|m| m.block_cooldown),
+            Some(Some(10))
+        );
+        assert_eq!(
+            borrowed.modules().map(|m| m.name).collect::<Vec<_>>(),
+            vec![WILDCARD, "balances", "balances", WILDCARD]
+        );
+        assert_eq!(
+            borrowed
+                .contracts()
+                .map(|c| c.block_cooldown)
+                .collect::<Vec<_>>(),
+            vec![None, Some(3)]
+        );
+    }
+
+    #[test]
+    fn it_fails_as_the_decoded_cennznut() {
+        let encoded = make_overlapping_cennznut().encode();
+        let mut bad_name = encoded.clone();
+        bad_name[4] = 0xff;
+        let mut bad_constraints = encoded.clone();
+        // The first constraints byte of `balances::transfer`
+        let transfer = encoded
+            .windows(8)
+            .position(|window| window == b"transfer")
+            .expect("it is encoded");
+        bad_constraints[transfer + 33] = 0xff;
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+
+        for bytes in &[
+            &encoded[..1],
+            &encoded[..40],
+            &encoded[..encoded.len() - 1],
+            &bad_name[..],
+            &bad_constraints[..],
+            &trailing[..],
+        ] {
+            let expected = CENNZnut::decode_detailed(bytes).err();
+            assert!(expected.is_some());
+            assert_eq!(CENNZnutRef::new(bytes).err(), expected);
+        }
+    }
+
+    #[test]
+    fn it_only_borrows_v0() {
+        let encoded = CENNZnut::V1(CENNZnutV1::from(make_overlapping_cennznut()))
+            .try_encode()
+            .expect("it encodes");
+
+        assert_eq!(
+            CENNZnutRef::new(&encoded).err(),
+            Some(DecodeError::BadVersion {
+                offset: 0,
+                version: 1
+            })
+        );
+        assert_eq!(
+            CENNZnutRef::new(&[]).err(),
+            Some(DecodeError::Truncated {
+                offset: 0,
+                path: DecodePath::default(),
+                field: DecodeField::Version,
+            })
+        );
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V0 Canonicalize
+//!
+//! Canonical ordering of CENNZnut permission domains
+//!
+
+use super::method::Method;
+use super::module::Module;
+use super::set::{ContractSet, MethodSet, ModuleSet};
+use super::CENNZnutV0;
+use crate::cennznut::{CONTRACT_WILDCARD, WILDCARD};
+use crate::CanonicalizeError;
+
+/// Returns whether `a` and `b` grant the same, ignoring their names
+fn same_method(a: &Method, b: &Method) -> bool {
+    a.block_cooldown == b.block_cooldown && a.constraints == b.constraints
+}
+
+/// Returns whether canonical modules `a` and `b` grant the same, ignoring their names
+fn same_module(a: &Module, b: &Module) -> bool {
+    a.block_cooldown == b.block_cooldown
+        && a.methods.len() == b.methods.len()
+        && a.methods
+            .iter()
+            .zip(&b.methods)
+            .all(|((a_name, a), (b_name, b))| a_name == b_name && same_method(a, b))
+}
+
+/// Returns `module` with its methods in canonical order
+fn canonicalize_module(module: &Module) -> Result<Module, CanonicalizeError> {
+    let mut methods = MethodSet::new();
+    for (name, method) in &module.methods {
+        let mut method = method.clone();
+        // Empty constraints are not encoded
+        method.constraints = method.constraints.filter(|c| !c.is_empty());
+        match methods.get(name) {
+            Some(existing) if same_method(existing, &method) => {}
+            Some(_) => {
+                return Err(CanonicalizeError::ConflictingMethods {
+                    module: module.name.clone(),
+                    method: name.clone(),
+                })
+            }
+            None => methods.push(method),
+        }
+    }
+    methods.sort();
+
+    if let Some(wildcard) = methods.get(WILDCARD).cloned() {
+        methods.retain(|method| method.name == WILDCARD || !same_method(method, &wildcard));
+    }
+
+    let mut canonical = module.clone();
+    canonical.methods = methods;
+    Ok(canonical)
+}
+
+impl CENNZnutV0 {
+    /// Returns this CENNZnut in canonical form, so `CENNZnuts` granting the same permissions
+    /// through the same entries encode identically.
+    /// Modules, methods and contracts are sorted by name or address, exact duplicates are
+    /// removed and entries granting the same as the wildcard entry are left to the wildcard.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if a name or address is listed more than once with different grants
+    pub fn canonicalize(&self) -> Result<Self, CanonicalizeError> {
+        let mut modules = ModuleSet::new();
+        for (name, module) in &self.modules {
+            let module = canonicalize_module(module)?;
+            match modules.get(name) {
+                Some(existing) if same_module(existing, &module) => {}
+                Some(_) => return Err(CanonicalizeError::ConflictingModules(name.clone())),
+                None => modules.push(module),
+            }
+        }
+        modules.sort();
+
+        if let Some(wildcard) = modules.get(WILDCARD).cloned() {
+            modules.retain(|module| module.name == WILDCARD || !same_module(module, &wildcard));
+        }
+
+        let mut contracts = ContractSet::new();
+        for (address, contract) in &self.contracts {
+            match contracts.get(address) {
+                Some(existing) if existing.block_cooldown == contract.block_cooldown => {}
+                Some(_) => return Err(CanonicalizeError::ConflictingContracts(*address)),
+                None => contracts.push(contract.clone()),
+            }
+        }
+        contracts.sort();
+
+        if let Some(wildcard_cooldown) = contracts
+            .get(&CONTRACT_WILDCARD)
+            .map(|contract| contract.block_cooldown)
+        {
+            contracts.retain(|contract| {
+                contract.address == CONTRACT_WILDCARD
+                    || contract.block_cooldown != wildcard_cooldown
+            });
+        }
+
+        Ok(Self { modules, contracts })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::tests::make_module;
+    use super::super::{contract::Contract, method::Method, CENNZnutV0};
+    use crate::cennznut::WILDCARD;
+    use crate::CanonicalizeError;
+    use codec::Encode;
+    use std::assert_eq;
+
+    #[test]
+    fn it_encodes_reordered_cennznuts_identically() {
+        let a = CENNZnutV0 {
+            modules: vec![
+                make_module(
+                    "staking",
+                    vec![Method::new("unbond"), Method::new("bond").block_cooldown(5)],
+                ),
+                make_module("balances", vec![Method::new("transfer")]),
+            ]
+            .into(),
+            contracts: vec![Contract::new(&[0x34_u8; 32]), Contract::new(&[0x12_u8; 32])].into(),
+        };
+        let b = CENNZnutV0 {
+            modules: vec![
+                make_module("balances", vec![Method::new("transfer")]),
+                make_module(
+                    "staking",
+                    vec![Method::new("bond").block_cooldown(5), Method::new("unbond")],
+                ),
+            ]
+            .into(),
+            contracts: vec![Contract::new(&[0x12_u8; 32]), Contract::new(&[0x34_u8; 32])].into(),
+        };
+
+        assert_ne!(a.encode(), b.encode());
+        assert_eq!(
+            a.canonicalize().map(|c| c.encode()),
+            b.canonicalize().map(|c| c.encode())
+        );
+        assert_eq!(a.canonicalize(), Ok(b.clone()));
+        assert_eq!(b.canonicalize(), Ok(b));
+    }
+
+    #[test]
+    fn it_removes_exact_duplicates() {
+        let cennznut = CENNZnutV0 {
+            modules: vec![
+                make_module(
+                    "balances",
+                    vec![Method::new("transfer"), Method::new("transfer")],
+                ),
+                make_module("balances", vec![Method::new("transfer")]),
+            ]
+            .into(),
+            contracts: vec![Contract::new(&[0x12_u8; 32]), Contract::new(&[0x12_u8; 32])].into(),
+        };
+
+        assert_eq!(
+            cennznut.canonicalize(),
+            Ok(CENNZnutV0 {
+                modules: vec![make_module("balances", vec![Method::new("transfer")])].into(),
+                contracts: vec![Contract::new(&[0x12_u8; 32])].into(),
+            })
+        );
+    }
+
+    #[test]
+    fn it_collapses_entries_granting_the_same_as_the_wildcard() {
+        let cennznut = CENNZnutV0 {
+            modules: vec![
+                make_module(
+                    "balances",
+                    vec![
+                        Method::new("transfer").block_cooldown(5),
+                        Method::new(WILDCARD).block_cooldown(5),
+                        Method::new("burn"),
+                    ],
+                ),
+                make_module(
+                    "staking",
+                    vec![Method::new("burn"), Method::new(WILDCARD).block_cooldown(5)],
+                ),
+                make_module(
+                    WILDCARD,
+                    vec![Method::new(WILDCARD).block_cooldown(5), Method::new("burn")],
+                ),
+            ]
+            .into(),
+            contracts: vec![
+                Contract::new(&[0x12_u8; 32]).block_cooldown(1),
+                Contract::new(&[0x34_u8; 32]),
+                Contract::wildcard().block_cooldown(1),
+            ]
+            .into(),
+        };
+
+        assert_eq!(
+            cennznut.canonicalize(),
+            Ok(CENNZnutV0 {
+                modules: vec![make_module(
+                    WILDCARD,
+                    vec![Method::new(WILDCARD).block_cooldown(5), Method::new("burn"),],
+                )]
+                .into(),
+                contracts: vec![
+                    Contract::wildcard().block_cooldown(1),
+                    Contract::new(&[0x34_u8; 32]),
+                ]
+                .into(),
+            })
+        );
+    }
+
+    #[test]
+    fn it_keeps_entries_narrower_than_the_wildcard() {
+        let cennznut = CENNZnutV0 {
+            modules: vec![
+                make_module(WILDCARD, vec![Method::new(WILDCARD)]),
+                make_module("balances", vec![Method::new("transfer")]),
+            ]
+            .into(),
+            contracts: vec![].into(),
+        };
+
+        assert_eq!(cennznut.canonicalize(), Ok(cennznut));
+    }
+
+    #[test]
+    fn it_drops_empty_constraints() {
+        let cennznut = CENNZnutV0 {
+            modules: vec![make_module(
+                "balances",
+                vec![Method::new("transfer").constraints(vec![])],
+            )]
+            .into(),
+            contracts: vec![].into(),
+        };
+
+        assert_eq!(
+            cennznut
+                .canonicalize()
+                .map(|c| c.modules[0].methods[0].constraints.clone()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn it_rejects_conflicting_duplicates() {
+        let cennznut = CENNZnutV0 {
+            modules: vec![
+                make_module("balances", vec![Method::new("transfer")]),
+                make_module("balances", vec![Method::new("burn")]),
+            ]
+            .into(),
+            contracts: vec![].into(),
+        };
+        assert_eq!(
+            cennznut.canonicalize(),
+            Err(CanonicalizeError::ConflictingModules("balances".into()))
+        );
+
+        let cennznut = CENNZnutV0 {
+            modules: vec![make_module(
+                "balances",
+                vec![
+                    Method::new("transfer"),
+                    Method::new("transfer").block_cooldown(1),
+                ],
+            )]
+            .into(),
+            contracts: vec![].into(),
+        };
+        assert_eq!(
+            cennznut.canonicalize(),
+            Err(CanonicalizeError::ConflictingMethods {
+                module: "balances".into(),
+                method: "transfer".into(),
+            })
+        );
+
+        let cennznut = CENNZnutV0 {
+            modules: vec![].into(),
+            contracts: vec![
+                Contract::new(&[0x12_u8; 32]),
+                Contract::new(&[0x12_u8; 32]).block_cooldown(1),
+            ]
+            .into(),
+        };
+        assert_eq!(
+            cennznut.canonicalize(),
+            Err(CanonicalizeError::ConflictingContracts([0x12_u8; 32]))
+        );
+    }
+}
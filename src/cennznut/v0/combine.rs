@@ -0,0 +1,569 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V0 Combine
+//!
+//! Intersection and union of CENNZnut permission domains
+//!
+
+use super::contract::Contract;
+use super::method::Method;
+use super::module::Module;
+use super::set::{ContractSet, ModuleSet};
+use super::{CENNZnutV0, MAX_CONTRACTS, MAX_METHODS, MAX_MODULES};
+use crate::cennznut::{requested_names, MethodName};
+use crate::cennznut::{CONTRACT_WILDCARD, WILDCARD};
+use crate::constraints::conjoin;
+use crate::CombineError;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+
+impl CENNZnutV0 {
+    /// Returns a CENNZnut permitting only the calls permitted by both this CENNZnut and `other`.
+    /// Cooldowns are the longer of the two and constraints are combined where both constrain a
+    /// method.
+    /// A module without methods would be required to prevent a wildcard module granting its
+    /// methods, such a module cannot be encoded.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if constraints cannot be combined, or the result exceeds a V0 limit or
+    /// otherwise cannot be encoded
+    pub fn intersect(&self, other: &Self) -> Result<Self, CombineError> {
+        let mut modules = ModuleSet::new();
+        for module_name in requested_names(
+            self.modules
+                .iter()
+                .chain(other.modules.iter())
+                .map(|(name, _)| name.as_str()),
+            WILDCARD,
+        ) {
+            if let (Some(a), Some(b)) =
+                (self.get_module(module_name), other.get_module(module_name))
+            {
+                modules.push(intersect_module(module_name, a, b)?);
+            }
+        }
+        // Empty modules are only required to shadow a wildcard module
+        let wildcard_granted = modules
+            .iter()
+            .any(|(name, module)| name == WILDCARD && !module.methods.is_empty());
+        modules.retain(|module| wildcard_granted || !module.methods.is_empty());
+
+        let mut contracts = ContractSet::new();
+        for address in requested_names(
+            self.contracts
+                .iter()
+                .chain(other.contracts.iter())
+                .map(|(address, _)| *address),
+            CONTRACT_WILDCARD,
+        ) {
+            if let (Some(a), Some(b)) = (self.get_contract(address), other.get_contract(address)) {
+                let mut contract = Contract::new(&address);
+                contract.block_cooldown = longest_cooldown(a.block_cooldown, b.block_cooldown);
+                contracts.push(contract);
+            }
+        }
+
+        checked_combination(modules, contracts)
+    }
+
+    /// Returns a CENNZnut permitting the calls permitted by either this CENNZnut or `other`.
+    /// Cooldowns are the shorter of the two and constraints are only kept where both
+    /// constrain a method identically.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if both constrain a method differently, as constraints cannot be
+    /// disjoined, or the result exceeds a V0 limit or otherwise cannot be encoded
+    pub fn union(&self, other: &Self) -> Result<Self, CombineError> {
+        let mut modules = ModuleSet::new();
+        for module_name in requested_names(
+            self.modules
+                .iter()
+                .chain(other.modules.iter())
+                .map(|(name, _)| name.as_str()),
+            WILDCARD,
+        ) {
+            let module = match (self.get_module(module_name), other.get_module(module_name)) {
+                (Some(a), Some(b)) => union_module(module_name, a, b)?,
+                (Some(module), None) | (None, Some(module)) => renamed_module(module_name, module),
+                (None, None) => continue,
+            };
+            modules.push(module);
+        }
+
+        let mut contracts = ContractSet::new();
+        for address in requested_names(
+            self.contracts
+                .iter()
+                .chain(other.contracts.iter())
+                .map(|(address, _)| *address),
+            CONTRACT_WILDCARD,
+        ) {
+            let block_cooldown = match (self.get_contract(address), other.get_contract(address)) {
+                (Some(a), Some(b)) => shortest_cooldown(a.block_cooldown, b.block_cooldown),
+                (Some(contract), None) | (None, Some(contract)) => contract.block_cooldown,
+                (None, None) => continue,
+            };
+            let mut contract = Contract::new(&address);
+            contract.block_cooldown = block_cooldown;
+            contracts.push(contract);
+        }
+
+        checked_combination(modules, contracts)
+    }
+}
+
+/// Returns a CENNZnut of `modules` and `contracts` if it satisfies the V0 limits and encodes
+fn checked_combination(
+    modules: ModuleSet,
+    contracts: ContractSet,
+) -> Result<CENNZnutV0, CombineError> {
+    if modules.len() > MAX_MODULES {
+        return Err(CombineError::TooManyModules(modules.len()));
+    }
+    for (name, module) in &modules {
+        if module.methods.len() > MAX_METHODS {
+            return Err(CombineError::TooManyMethods {
+                module: name.clone(),
+                count: module.methods.len(),
+            });
+        }
+    }
+    if contracts.len() > MAX_CONTRACTS {
+        return Err(CombineError::TooManyContracts(contracts.len()));
+    }
+    let combination = CENNZnutV0 { modules, contracts };
+    combination
+        .try_encode()
+        .map_err(CombineError::Unencodable)?;
+    Ok(combination)
+}
+
+fn intersect_module(module_name: &str, a: &Module, b: &Module) -> Result<Module, CombineError> {
+    let mut module = Module::new(module_name);
+    module.block_cooldown = longest_cooldown(a.block_cooldown, b.block_cooldown);
+    for method_name in method_names(a, b) {
+        if let (Some(a), Some(b)) = (a.get_method(method_name), b.get_method(method_name)) {
+            let mut method = Method::new(method_name);
+            method.block_cooldown = longest_cooldown(a.block_cooldown, b.block_cooldown);
+            method.constraints = match (constraints(a), constraints(b)) {
+                (Some(a), Some(b)) if a == b => Some(a.clone()),
+                (Some(a), Some(b)) => {
+                    Some(
+                        conjoin(a, b).ok_or_else(|| CombineError::ConstraintsIncompatible {
+                            module: module_name.into(),
+                            method: method_name.into(),
+                        })?,
+                    )
+                }
+                (Some(constraints), None) | (None, Some(constraints)) => Some(constraints.clone()),
+                (None, None) => None,
+            };
+            module.methods.push(method);
+        }
+    }
+    Ok(module)
+}
+
+fn union_module(module_name: &str, a: &Module, b: &Module) -> Result<Module, CombineError> {
+    let mut module = Module::new(module_name);
+    module.block_cooldown = shortest_cooldown(a.block_cooldown, b.block_cooldown);
+    for method_name in method_names(a, b) {
+        let method = match (a.get_method(method_name), b.get_method(method_name)) {
+            (Some(a), Some(b)) => {
+                let mut method = Method::new(method_name);
+                method.block_cooldown = shortest_cooldown(a.block_cooldown, b.block_cooldown);
+                method.constraints = match (constraints(a), constraints(b)) {
+                    (Some(a), Some(b)) if a == b => Some(a.clone()),
+                    (Some(_), Some(_)) => {
+                        return Err(CombineError::ConstraintsIncompatible {
+                            module: module_name.into(),
+                            method: method_name.into(),
+                        })
+                    }
+                    _ => None,
+                };
+                method
+            }
+            (Some(method), None) | (None, Some(method)) => renamed_method(method_name, method),
+            (None, None) => continue,
+        };
+        module.methods.push(method);
+    }
+    Ok(module)
+}
+
+/// Returns the method names requested of either module
+fn method_names<'a>(a: &'a Module, b: &'a Module) -> Vec<&'a str> {
+    requested_names(
+        a.methods
+            .iter()
+            .chain(b.methods.iter())
+            .map(|(name, _)| name.as_str()),
+        WILDCARD,
+    )
+}
+
+/// Returns the `module` granted as `module_name`, which may differ for a wildcard module
+fn renamed_module(module_name: &str, module: &Module) -> Module {
+    let mut renamed = module.clone();
+    renamed.name = module_name.into();
+    renamed
+}
+
+/// Returns the `method` granted as `method_name`, which may differ for a wildcard method
+fn renamed_method(method_name: &str, method: &Method) -> Method {
+    let mut renamed = method.clone();
+    renamed.name = MethodName::from(method_name);
+    renamed
+}
+
+/// Returns the `method` constraints, if any
+fn constraints(method: &Method) -> Option<&Vec<u8>> {
+    method
+        .constraints
+        .as_ref()
+        .filter(|constraints| !constraints.is_empty())
+}
+
+fn longest_cooldown(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(max(a, b)),
+        (cooldown, None) | (None, cooldown) => cooldown,
+    }
+}
+
+fn shortest_cooldown(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(min(a, b)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::tests::{make_cennznut, make_module, make_pact};
+    use super::super::{contract::Contract, method::Method};
+    use crate::cennznut::CONTRACT_WILDCARD;
+    use crate::{CombineError, EncodeError};
+    use pact::interpreter::OpCode;
+    use pact::types::{Numeric, PactType};
+
+    #[test]
+    fn it_intersects_with_wildcards() {
+        let a = make_cennznut(
+            vec![make_module("*", vec![Method::new("*")])],
+            vec![Contract::wildcard()],
+        );
+        let b = make_cennznut(
+            vec![make_module(
+                "module_test",
+                vec![Method::new("method_test").block_cooldown(5)],
+            )],
+            vec![Contract::new(&[0x12_u8; 32]).block_cooldown(5)],
+        );
+        let expected = b.clone();
+
+        assert_eq!(a.intersect(&b), Ok(expected.clone()));
+        assert_eq!(b.intersect(&a), Ok(expected));
+    }
+
+    #[test]
+    fn it_intersects_with_the_longest_cooldown() {
+        let a = make_cennznut(
+            vec![make_module(
+                "module_test",
+                vec![Method::new("method_test").block_cooldown(10)],
+            )
+            .block_cooldown(3)],
+            vec![Contract::new(&[0x12_u8; 32])],
+        );
+        let b = make_cennznut(
+            vec![make_module(
+                "module_test",
+                vec![Method::new("method_test").block_cooldown(5)],
+            )
+            .block_cooldown(7)],
+            vec![Contract::new(&[0x12_u8; 32]).block_cooldown(5)],
+        );
+        let expected = make_cennznut(
+            vec![make_module(
+                "module_test",
+                vec![Method::new("method_test").block_cooldown(10)],
+            )
+            .block_cooldown(7)],
+            vec![Contract::new(&[0x12_u8; 32]).block_cooldown(5)],
+        );
+
+        assert_eq!(a.intersect(&b), Ok(expected));
+    }
+
+    #[test]
+    fn it_fails_to_intersect_shadowing_wildcard_modules() {
+        let a = make_cennznut(
+            vec![make_module("*", vec![Method::new("method_a")])],
+            vec![],
+        );
+        let b = make_cennznut(
+            vec![
+                make_module("*", vec![Method::new("*")]),
+                make_module("module_test", vec![Method::new("method_b")]),
+            ],
+            vec![],
+        );
+
+        // `module_test` must grant no methods to shadow the wildcard module
+        assert_eq!(
+            a.intersect(&b),
+            Err(CombineError::Unencodable(EncodeError::NoMethods {
+                module: 1
+            }))
+        );
+    }
+
+    #[test]
+    fn it_fails_to_intersect_too_large_constraints() {
+        // Each side fits the V0 constraints limit, but not both conjoined
+        let with_constraints = |value: u64| {
+            let bytecode = (0..12_u8)
+                .flat_map(|index| vec![OpCode::EQ.into(), 0, 0, 1, index])
+                .collect();
+            make_cennznut(
+                vec![make_module(
+                    "module_test",
+                    vec![Method::new("method_test").constraints(make_pact(
+                        vec![PactType::Numeric(Numeric(value)); 12],
+                        bytecode,
+                    ))],
+                )],
+                vec![],
+            )
+        };
+        let a = with_constraints(0);
+        let b = with_constraints(1);
+        assert!(a.try_encode().is_ok());
+
+        assert_eq!(
+            a.intersect(&b),
+            Err(CombineError::Unencodable(
+                EncodeError::ConstraintsTooLarge {
+                    module: 0,
+                    method: 0
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn it_intersects_combining_constraints() {
+        let a = make_pact(
+            vec![PactType::Numeric(Numeric(123))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        );
+        let b = make_pact(
+            vec![PactType::Numeric(Numeric(456))],
+            vec![OpCode::EQ.into(), 0, 1, 1, 0],
+        );
+        let with_constraints = |constraints: Vec<u8>| {
+            make_cennznut(
+                vec![make_module(
+                    "module_test",
+                    vec![Method::new("method_test").constraints(constraints)],
+                )],
+                vec![],
+            )
+        };
+        let intersection = with_constraints(a)
+            .intersect(&with_constraints(b))
+            .expect("it intersects");
+
+        let expected = make_pact(
+            vec![
+                PactType::Numeric(Numeric(123)),
+                PactType::Numeric(Numeric(456)),
+            ],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0, OpCode::EQ.into(), 0, 1, 1, 1],
+        );
+        assert_eq!(intersection, with_constraints(expected));
+
+        let both = [
+            PactType::Numeric(Numeric(123)),
+            PactType::Numeric(Numeric(456)),
+        ];
+        assert_eq!(
+            intersection.validate_module("module_test", "method_test", &both),
+            Ok(())
+        );
+        assert!(intersection
+            .validate_module("module_test", "method_test", &both[..1])
+            .is_err());
+    }
+
+    #[test]
+    fn it_fails_to_intersect_incompatible_constraints() {
+        let with_constraints = |constraints: Vec<u8>| {
+            make_cennznut(
+                vec![make_module(
+                    "module_test",
+                    vec![Method::new("method_test").constraints(constraints)],
+                )],
+                vec![],
+            )
+        };
+        let a = with_constraints(make_pact(
+            vec![PactType::Numeric(Numeric(123))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        ));
+        // Truncated bytecode is not a conjunction of comparisons
+        let b = with_constraints(make_pact(
+            vec![PactType::Numeric(Numeric(456))],
+            vec![OpCode::EQ.into(), 0, 0],
+        ));
+
+        assert_eq!(
+            a.intersect(&b),
+            Err(CombineError::ConstraintsIncompatible {
+                module: "module_test".to_string(),
+                method: "method_test".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn it_unions_with_the_shortest_cooldown() {
+        let a = make_cennznut(
+            vec![make_module(
+                "module_a",
+                vec![Method::new("method_test").block_cooldown(10)],
+            )
+            .block_cooldown(3)],
+            vec![Contract::new(&[0x12_u8; 32]).block_cooldown(5)],
+        );
+        let b = make_cennznut(
+            vec![
+                make_module(
+                    "module_a",
+                    vec![Method::new("method_test").block_cooldown(5)],
+                )
+                .block_cooldown(7),
+                make_module("module_b", vec![Method::new("method_test")]),
+            ],
+            vec![Contract::new(&[0x12_u8; 32])],
+        );
+        let expected = make_cennznut(
+            vec![
+                make_module(
+                    "module_a",
+                    vec![Method::new("method_test").block_cooldown(5)],
+                )
+                .block_cooldown(3),
+                make_module("module_b", vec![Method::new("method_test")]),
+            ],
+            vec![Contract::new(&[0x12_u8; 32])],
+        );
+
+        assert_eq!(a.union(&b), Ok(expected));
+    }
+
+    #[test]
+    fn it_unions_with_wildcards() {
+        let a = make_cennznut(
+            vec![make_module("*", vec![Method::new("method_a")])],
+            vec![Contract::wildcard().block_cooldown(5)],
+        );
+        let b = make_cennznut(
+            vec![make_module("module_test", vec![Method::new("method_b")])],
+            vec![Contract::new(&[0x12_u8; 32])],
+        );
+        let union = a.union(&b).expect("it unions");
+
+        assert_eq!(
+            union.validate_module("module_test", "method_a", &[]),
+            Ok(())
+        );
+        assert_eq!(
+            union.validate_module("module_test", "method_b", &[]),
+            Ok(())
+        );
+        assert_eq!(
+            union.validate_module("module_other", "method_a", &[]),
+            Ok(())
+        );
+        assert!(union
+            .validate_module("module_other", "method_b", &[])
+            .is_err());
+        assert_eq!(
+            union
+                .get_contract(CONTRACT_WILDCARD)
+                .and_then(|c| c.block_cooldown),
+            Some(5)
+        );
+        assert_eq!(union.validate_contract([0x12_u8; 32]), Ok(()));
+    }
+
+    #[test]
+    fn it_fails_to_union_different_constraints() {
+        let with_constraints = |value: u64| {
+            make_cennznut(
+                vec![make_module(
+                    "module_test",
+                    vec![Method::new("method_test").constraints(make_pact(
+                        vec![PactType::Numeric(Numeric(value))],
+                        vec![OpCode::EQ.into(), 0, 0, 1, 0],
+                    ))],
+                )],
+                vec![],
+            )
+        };
+
+        assert_eq!(
+            with_constraints(1).union(&with_constraints(1)),
+            Ok(with_constraints(1))
+        );
+        assert_eq!(
+            with_constraints(1).union(&with_constraints(2)),
+            Err(CombineError::ConstraintsIncompatible {
+                module: "module_test".to_string(),
+                method: "method_test".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn it_fails_to_union_too_many_modules() {
+        let make_modules = |range: core::ops::Range<usize>| {
+            range
+                .map(|i| make_module(&format!("module_{}", i), vec![Method::new("*")]))
+                .collect()
+        };
+        let a = make_cennznut(make_modules(0..200), vec![]);
+        let b = make_cennznut(make_modules(200..300), vec![]);
+
+        assert_eq!(a.union(&b), Err(CombineError::TooManyModules(300)));
+        assert_eq!(a.union(&a).map(|c| c.modules.len()), Ok(200));
+    }
+
+    #[test]
+    fn it_fails_to_intersect_to_nothing() {
+        let a = make_cennznut(vec![make_module("*", vec![Method::new("*")])], vec![]);
+        let b = make_cennznut(
+            vec![make_module("module_test", vec![Method::new("method_test")])],
+            vec![],
+        );
+        let c = make_cennznut(
+            vec![make_module(
+                "module_other",
+                vec![Method::new("method_test")],
+            )],
+            vec![],
+        );
+
+        assert_eq!(a.intersect(&b), Ok(b.clone()));
+        // An intersection permitting nothing has no modules to encode
+        assert_eq!(
+            b.intersect(&c),
+            Err(CombineError::Unencodable(EncodeError::NoModules))
+        );
+    }
+}
@@ -6,6 +6,9 @@
 //!
 
 use crate::cennznut::{ContractAddress, CONTRACT_WILDCARD};
+use crate::decode::{DecodeField, DecodeInput};
+use crate::{DecodeError, EncodeError};
+use alloc::vec::Vec;
 use codec::{Decode, Encode, Input, Output};
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
@@ -13,7 +16,8 @@ const BLOCK_COOLDOWN_MASK: u8 = 0x01;
 
 /// A CENNZnet permission domain contract
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[cfg_attr(test, derive(Clone, Debug, Eq, PartialEq))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct Contract {
     pub address: ContractAddress,
     pub block_cooldown: Option<u32>,
@@ -40,8 +44,14 @@ impl Contract {
     }
 }
 
-impl Encode for Contract {
-    fn encode_to<T: Output>(&self, buf: &mut T) {
+impl Contract {
+    /// Encodes the Contract
+    ///
+    /// # Errors
+    ///
+    /// A Contract always fits its encoding, the `Result` mirrors the other CENNZnut domains
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::<u8>::default();
         let has_cooldown_byte: u8 = if self.block_cooldown.is_some() {
             BLOCK_COOLDOWN_MASK
         } else {
@@ -59,26 +69,39 @@ impl Encode for Contract {
                 buf.push_byte(*b);
             }
         }
+
+        Ok(buf)
     }
 }
 
-impl Decode for Contract {
-    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
-        let has_cooldown_byte: u8 = input.read_byte()?;
+impl Encode for Contract {
+    fn encode_to<T: Output>(&self, buf: &mut T) {
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
+        }
+    }
+}
+
+impl Contract {
+    /// Decodes a Contract, reporting the offset and field of any failure
+    pub(crate) fn decode_detailed<I: Input>(
+        input: &mut DecodeInput<I>,
+    ) -> Result<Self, DecodeError> {
+        let header_offset = input.offset();
+        let has_cooldown_byte: u8 = input.read_byte(DecodeField::ContractHeader)?;
+        input.check_header(
+            has_cooldown_byte,
+            BLOCK_COOLDOWN_MASK,
+            header_offset,
+            DecodeField::ContractHeader,
+        )?;
         let has_cooldown: bool = (has_cooldown_byte & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK;
 
         let mut address = ContractAddress::default();
-        input
-            .read(&mut address)
-            .map_err(|_| "expected 32 byte address")?;
+        input.read(&mut address, DecodeField::ContractAddress)?;
 
         let block_cooldown = if has_cooldown {
-            Some(u32::from_le_bytes([
-                input.read_byte()?,
-                input.read_byte()?,
-                input.read_byte()?,
-                input.read_byte()?,
-            ]))
+            Some(input.read_u32(DecodeField::ContractCooldown)?)
         } else {
             None
         };
@@ -90,6 +113,12 @@ impl Decode for Contract {
     }
 }
 
+impl Decode for Contract {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::decode_detailed(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::{Contract, ContractAddress, BLOCK_COOLDOWN_MASK, CONTRACT_WILDCARD};
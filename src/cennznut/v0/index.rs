@@ -0,0 +1,334 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V0 Index
+//!
+//! Indexed view of a V0 CENNZnut for repeated validation
+//!
+
+use super::contract::Contract;
+use super::method::Method;
+use super::module::Module;
+use super::CENNZnutV0;
+use crate::cennznut::{ContractAddress, ContractDomain, RuntimeDomain};
+use crate::cennznut::{CONTRACT_WILDCARD, WILDCARD};
+use crate::cooldown::cooldown_elapsed;
+use crate::validation::interpret_constraints;
+use crate::{CooldownTracker, ValidationErr};
+use alloc::collections::BTreeMap;
+use pact::contract::Contract as PactContract;
+use pact::interpreter::types::PactType;
+
+/// A method with its constraints decoded ahead of validation
+struct IndexedMethod<'a> {
+    block_cooldown: Option<u32>,
+    pact: Option<PactContract<'a>>,
+}
+
+impl<'a> From<&'a Method> for IndexedMethod<'a> {
+    fn from(method: &'a Method) -> Self {
+        Self {
+            block_cooldown: method.block_cooldown,
+            pact: method.get_pact(),
+        }
+    }
+}
+
+/// A module with its methods sorted by name and its wildcard method resolved
+struct IndexedModule<'a> {
+    block_cooldown: Option<u32>,
+    methods: BTreeMap<&'a str, IndexedMethod<'a>>,
+    wildcard_method: Option<IndexedMethod<'a>>,
+}
+
+impl<'a> IndexedModule<'a> {
+    /// Returns the method, falling back to the wildcard method as `Module::get_method`
+    fn get_method(&self, method: &str) -> Option<&IndexedMethod<'a>> {
+        self.methods.get(method).or(self.wildcard_method.as_ref())
+    }
+}
+
+impl<'a> From<&'a Module> for IndexedModule<'a> {
+    fn from(module: &'a Module) -> Self {
+        let mut methods = BTreeMap::<&str, IndexedMethod>::default();
+        let mut wildcard_method: Option<IndexedMethod> = None;
+        for (name, method) in &module.methods {
+            // The first method with a name is the one matched
+            if !methods.contains_key(name.as_str()) {
+                methods.insert(name, method.into());
+            }
+            // The last wildcard method is the fallback
+            if name == WILDCARD {
+                wildcard_method = Some(method.into());
+            }
+        }
+        Self {
+            block_cooldown: module.block_cooldown,
+            methods,
+            wildcard_method,
+        }
+    }
+}
+
+/// A `CENNZnutV0` indexed for repeated validation.
+/// Modules, methods and contracts are sorted for logarithmic lookups, wildcard fallbacks are
+/// resolved and constraints are decoded once when the index is built.
+/// Validation grants exactly what the indexed `CENNZnutV0` grants.
+pub struct CENNZnutIndex<'a> {
+    modules: BTreeMap<&'a str, IndexedModule<'a>>,
+    wildcard_module: Option<IndexedModule<'a>>,
+    contracts: BTreeMap<ContractAddress, &'a Contract>,
+    wildcard_contract: Option<&'a Contract>,
+}
+
+impl<'a> From<&'a CENNZnutV0> for CENNZnutIndex<'a> {
+    fn from(cennznut: &'a CENNZnutV0) -> Self {
+        let mut modules = BTreeMap::<&str, IndexedModule>::default();
+        let mut wildcard_module: Option<IndexedModule> = None;
+        for (name, module) in &cennznut.modules {
+            if !modules.contains_key(name.as_str()) {
+                modules.insert(name, module.into());
+            }
+            if name == WILDCARD {
+                wildcard_module = Some(module.into());
+            }
+        }
+
+        let mut contracts = BTreeMap::<ContractAddress, &Contract>::default();
+        let mut wildcard_contract: Option<&Contract> = None;
+        for (address, contract) in &cennznut.contracts {
+            if !contracts.contains_key(address) {
+                contracts.insert(*address, contract);
+            }
+            if address == &CONTRACT_WILDCARD {
+                wildcard_contract = Some(contract);
+            }
+        }
+
+        Self {
+            modules,
+            wildcard_module,
+            contracts,
+            wildcard_contract,
+        }
+    }
+}
+
+impl<'a> CENNZnutIndex<'a> {
+    /// Returns the module, falling back to the wildcard module as `CENNZnutV0::get_module`
+    fn get_module(&self, module: &str) -> Option<&IndexedModule<'a>> {
+        self.modules.get(module).or(self.wildcard_module.as_ref())
+    }
+
+    /// Returns the contract, falling back to the wildcard contract as `CENNZnutV0::get_contract`
+    pub fn get_contract(&self, contract: ContractAddress) -> Option<&'a Contract> {
+        self.contracts
+            .get(&contract)
+            .copied()
+            .or(self.wildcard_contract)
+    }
+
+    /// Validates a CENNZnut runtime module as `CENNZnutV0::validate_module`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_module(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        self.get_permitted_method(module_name, method_name, args)?;
+        Ok(())
+    }
+
+    /// Validates a CENNZnut runtime module at `block_number` as `CENNZnutV0::validate_module_at`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_module_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        let (module, method) = self.get_permitted_method(module_name, method_name, args)?;
+        if !cooldown_elapsed(
+            module.block_cooldown,
+            tracker.last_module_call(module_name),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Module {
+                module: module_name.into(),
+            }));
+        }
+        if !cooldown_elapsed(
+            method.block_cooldown,
+            tracker.last_method_call(module_name, method_name),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            }));
+        }
+        tracker.record_module_call(module_name, block_number);
+        tracker.record_method_call(module_name, method_name, block_number);
+        Ok(())
+    }
+
+    /// Validates a CENNZnut smart contract as `CENNZnutV0::validate_contract`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract(
+        &self,
+        contract_address: ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        self.get_contract(contract_address)
+            .ok_or(ValidationErr::NoPermission(ContractDomain::Contract {
+                address: contract_address,
+            }))?;
+        Ok(())
+    }
+
+    /// Validates a CENNZnut smart contract at `block_number` as
+    /// `CENNZnutV0::validate_contract_at`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        contract_address: ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        let contract = self
+            .get_contract(contract_address)
+            .ok_or(ValidationErr::NoPermission(ContractDomain::Contract {
+                address: contract_address,
+            }))?;
+        if !cooldown_elapsed(
+            contract.block_cooldown,
+            tracker.last_contract_call(&contract_address),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(
+                ContractDomain::Contract {
+                    address: contract_address,
+                },
+            ));
+        }
+        tracker.record_contract_call(&contract_address, block_number);
+        Ok(())
+    }
+
+    /// Returns the module and method permitting a call with `args`
+    fn get_permitted_method(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(&IndexedModule<'a>, &IndexedMethod<'a>), ValidationErr<RuntimeDomain>> {
+        let module = self.get_module(module_name).ok_or_else(|| {
+            ValidationErr::NoPermission(RuntimeDomain::Module {
+                module: module_name.into(),
+            })
+        })?;
+        let method = module.get_method(method_name).ok_or_else(|| {
+            ValidationErr::NoPermission(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            })
+        })?;
+        if let Some(pact) = &method.pact {
+            interpret_constraints(pact, args, module_name, method_name)?;
+        }
+        Ok((module, method))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::tests::{make_module, make_overlapping_cennznut};
+    use super::super::{method::Method, CENNZnutV0};
+    use super::CENNZnutIndex;
+    use crate::cennznut::{CONTRACT_WILDCARD, WILDCARD};
+    use crate::InMemoryCooldownTracker;
+    use pact::interpreter::types::{Numeric, PactType};
+    use std::assert_eq;
+
+    #[test]
+    fn it_validates_as_the_cennznut() {
+        let cennznut = make_overlapping_cennznut();
+        let index = CENNZnutIndex::from(&cennznut);
+        let args = [
+            vec![],
+            vec![PactType::Numeric(Numeric(100))],
+            vec![PactType::Numeric(Numeric(101))],
+        ];
+
+        for module in &["balances", "staking", WILDCARD] {
+            for method in &["transfer", "burn", "approve", "mint", "stake", WILDCARD] {
+                for args in &args {
+                    assert_eq!(
+                        index.validate_module(module, method, args),
+                        cennznut.validate_module(module, method, args),
+                    );
+                }
+            }
+        }
+        for address in &[[0x12_u8; 32], [0x34_u8; 32], CONTRACT_WILDCARD] {
+            assert_eq!(
+                index.validate_contract(*address),
+                cennznut.validate_contract(*address)
+            );
+        }
+    }
+
+    #[test]
+    fn it_validates_cooldowns_as_the_cennznut() {
+        let cennznut = make_overlapping_cennznut();
+        let index = CENNZnutIndex::from(&cennznut);
+        let mut index_tracker = InMemoryCooldownTracker::new();
+        let mut cennznut_tracker = InMemoryCooldownTracker::new();
+
+        for block_number in 0..12 {
+            for (module, method) in &[("balances", "stake"), ("staking", "mint")] {
+                assert_eq!(
+                    index.validate_module_at(block_number, &mut index_tracker, module, method, &[]),
+                    cennznut.validate_module_at(
+                        block_number,
+                        &mut cennznut_tracker,
+                        module,
+                        method,
+                        &[]
+                    ),
+                );
+            }
+            assert_eq!(
+                index.validate_contract_at(block_number, &mut index_tracker, [0x34_u8; 32]),
+                cennznut.validate_contract_at(block_number, &mut cennznut_tracker, [0x34_u8; 32]),
+            );
+        }
+    }
+
+    #[test]
+    fn it_denies_everything_without_wildcards() {
+        let cennznut = CENNZnutV0 {
+            modules: vec![make_module("balances", vec![Method::new("transfer")])].into(),
+            contracts: vec![].into(),
+        };
+        let index = CENNZnutIndex::from(&cennznut);
+
+        assert_eq!(index.validate_module("balances", "transfer", &[]), Ok(()));
+        assert!(index.validate_module("balances", "burn", &[]).is_err());
+        assert!(index.validate_module("staking", "transfer", &[]).is_err());
+        assert!(index.validate_contract([0x12_u8; 32]).is_err());
+    }
+}
@@ -6,21 +6,24 @@
 //!
 
 use crate::cennznut::MethodName;
+use crate::decode::{DecodeField, DecodeInput, DecodePath};
+use crate::{Constraint, DecodeError, EncodeError};
 use alloc::string::ToString;
+use alloc::vec;
 use alloc::vec::Vec;
 use codec::{Decode, Encode, Input, Output};
-use core::convert::TryFrom;
 use pact::contract::Contract as PactContract;
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
 const BLOCK_COOLDOWN_MASK: u8 = 0x01;
 const CONSTRAINTS_MASK: u8 = 0x02;
-const MAX_CONSTRAINTS: usize = 256;
+pub const MAX_CONSTRAINTS: usize = 256;
 
 /// A CENNZnet permission domain module method
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[cfg_attr(test, derive(Clone, Debug, Eq, PartialEq))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct Method {
     pub name: MethodName,
     pub block_cooldown: Option<u32>,
@@ -46,6 +49,23 @@ impl Method {
         self
     }
 
+    /// Encodes `constraint` as the Method constraints
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the encoded constraints are larger than `MAX_CONSTRAINTS`
+    pub fn constraint(mut self, constraint: &Constraint) -> Result<Self, EncodeError> {
+        let constraints = constraint.try_encode()?;
+        if constraints.len() > MAX_CONSTRAINTS {
+            return Err(EncodeError::ConstraintsTooLarge {
+                module: 0,
+                method: 0,
+            });
+        }
+        self.constraints = Some(constraints);
+        Ok(self)
+    }
+
     /// Returns the Pact contract, if it exists in the Method
     pub fn get_pact(&self) -> Option<PactContract> {
         match &self.constraints {
@@ -60,28 +80,39 @@ impl Method {
     }
 }
 
-impl Encode for Method {
-    fn encode_to<T: Output>(&self, buf: &mut T) {
+impl Method {
+    /// Encodes the Method, failing if it exceeds any encoding limits
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the name is longer than 32 bytes or the constraints are too large
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        if self.name.len() > 32 {
+            return Err(EncodeError::MethodNameTooLong {
+                module: 0,
+                method: 0,
+            });
+        }
+        let constraints = match &self.constraints {
+            Some(constraints) if !constraints.is_empty() => Some(constraints),
+            _ => None,
+        };
+
+        let mut buf = Vec::<u8>::default();
         let has_cooldown_byte: u8 = if self.block_cooldown.is_some() {
             BLOCK_COOLDOWN_MASK
         } else {
             0
         };
-        let has_constraints_byte: u8 = if let Some(constraints) = &self.constraints {
-            if constraints.is_empty() {
-                0
-            } else {
-                CONSTRAINTS_MASK
-            }
+        let has_constraints_byte: u8 = if constraints.is_some() {
+            CONSTRAINTS_MASK
         } else {
             0
         };
         buf.push_byte(has_cooldown_byte | has_constraints_byte);
 
         let mut name = [0_u8; 32];
-        let length = 32.min(self.name.len());
-
-        name[0..length].clone_from_slice(&self.name.as_bytes()[0..length]);
+        name[0..self.name.len()].clone_from_slice(self.name.as_bytes());
 
         buf.write(&name);
 
@@ -91,52 +122,77 @@ impl Encode for Method {
             }
         }
 
-        if let Some(constraints) = &self.constraints {
-            let constraints_count =
-                u8::try_from(MAX_CONSTRAINTS.min(constraints.len()).wrapping_sub(1));
-            if let Ok(len_byte) = constraints_count {
-                let len: usize = len_byte.into();
-                buf.push_byte(len_byte);
-                buf.write(&constraints[0..=len]);
+        if let Some(constraints) = constraints {
+            if constraints.len() > MAX_CONSTRAINTS {
+                return Err(EncodeError::ConstraintsTooLarge {
+                    module: 0,
+                    method: 0,
+                });
             }
+            #[allow(clippy::cast_possible_truncation)]
+            let len_byte = (constraints.len() - 1) as u8;
+            buf.push_byte(len_byte);
+            buf.write(constraints);
         }
+
+        Ok(buf)
     }
 }
 
-impl Decode for Method {
-    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
-        let block_cooldown_and_constraints = input.read_byte()?;
+impl Encode for Method {
+    fn encode_to<T: Output>(&self, buf: &mut T) {
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
+        }
+    }
+}
 
+impl Method {
+    /// Decodes a Method, reporting the offset and field of any failure
+    pub(crate) fn decode_detailed<I: Input>(
+        input: &mut DecodeInput<I>,
+    ) -> Result<Self, DecodeError> {
+        let header_offset = input.offset();
+        let block_cooldown_and_constraints = input.read_byte(DecodeField::MethodHeader)?;
+        input.check_header(
+            block_cooldown_and_constraints,
+            BLOCK_COOLDOWN_MASK | CONSTRAINTS_MASK,
+            header_offset,
+            DecodeField::MethodHeader,
+        )?;
+
+        let name_offset = input.offset();
         let mut name_buf: [u8; 32] = Default::default();
-        input
-            .read(&mut name_buf)
-            .map_err(|_| "expected 32 byte method name")?;
+        input.read(&mut name_buf, DecodeField::MethodName)?;
+        input.check_name(&name_buf, name_offset, DecodeField::MethodName)?;
         let name = core::str::from_utf8(&name_buf)
-            .map_err(|_| codec::Error::from("method names should be utf8 encoded"))?
+            .map_err(|_| DecodeError::InvalidUtf8Name {
+                offset: name_offset,
+                path: DecodePath::default(),
+                field: DecodeField::MethodName,
+            })?
             .trim_matches(char::from(0))
             .to_string();
 
         let block_cooldown: Option<u32> =
             if (block_cooldown_and_constraints & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK {
-                Some(u32::from_le_bytes([
-                    input.read_byte()?,
-                    input.read_byte()?,
-                    input.read_byte()?,
-                    input.read_byte()?,
-                ]))
+                Some(input.read_u32(DecodeField::MethodCooldown)?)
             } else {
                 None
             };
 
         let constraints: Option<Vec<u8>> =
             if (block_cooldown_and_constraints & CONSTRAINTS_MASK) == CONSTRAINTS_MASK {
-                let constraints_length = (input.read_byte()?).saturating_add(1);
-                let mut constraints_buf = Vec::<u8>::default();
-                for _ in 0..constraints_length {
-                    constraints_buf.push(input.read_byte()?);
-                }
+                let constraints_length =
+                    usize::from(input.read_byte(DecodeField::ConstraintsLength)?) + 1;
+                let constraints_offset = input.offset();
+                let mut constraints_buf = vec![0_u8; constraints_length];
+                input.read(&mut constraints_buf, DecodeField::Constraints)?;
                 if PactContract::decode(&constraints_buf).is_err() {
-                    return Err(codec::Error::from("invalid constraints codec"));
+                    return Err(DecodeError::InvalidConstraints {
+                        offset: constraints_offset,
+                        path: DecodePath::default(),
+                    });
                 };
                 Some(constraints_buf)
             } else {
@@ -151,9 +207,16 @@ impl Decode for Method {
     }
 }
 
+impl Decode for Method {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::decode_detailed(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::{Method, BLOCK_COOLDOWN_MASK, CONSTRAINTS_MASK};
+    use crate::EncodeError;
     use codec::{Decode, Encode};
     use std::assert_eq;
 
@@ -180,8 +243,22 @@ mod test {
     }
 
     #[test]
-    fn it_encodes_only_32_characters_for_name() {
+    fn it_does_not_encode_names_longer_than_32_bytes() {
         let method = Method::new("I am Sam, I am Sam, Sam I am; That Sam I am, That Sam I am, I do not like that Sam I am");
+
+        assert_eq!(
+            method.try_encode(),
+            Err(EncodeError::MethodNameTooLong {
+                module: 0,
+                method: 0
+            })
+        );
+        assert!(method.encode().is_empty());
+    }
+
+    #[test]
+    fn it_encodes_32_byte_names() {
+        let method = Method::new("I am Sam, I am Sam, Sam I am; Th");
         let expected_length = 33;
 
         assert_eq!(method.encode().len(), expected_length);
@@ -229,9 +306,23 @@ mod test {
         assert_eq!(method.get_pact(), None);
     }
 
+    #[test]
+    fn it_does_not_encode_more_than_256_constraints_bytes() {
+        let method = Method::new("TestMethod").constraints(vec![0x55; 257]);
+
+        assert_eq!(
+            method.try_encode(),
+            Err(EncodeError::ConstraintsTooLarge {
+                module: 0,
+                method: 0
+            })
+        );
+        assert!(method.encode().is_empty());
+    }
+
     #[test]
     fn it_encodes_up_to_256_constraints_bytes() {
-        let method = Method::new("TestMethod").constraints(vec![0x55; 300]);
+        let method = Method::new("TestMethod").constraints(vec![0x55; 256]);
 
         let expected_name = String::from("TestMethod").into_bytes();
         let remainder = vec![0x00_u8; 32_usize - expected_name.len()];
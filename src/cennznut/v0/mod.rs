@@ -6,26 +6,37 @@
 //!
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
+
+mod canonicalize;
+mod combine;
+mod policy;
 
-use alloc::borrow::ToOwned;
 use alloc::vec::Vec;
 use codec::{Decode, Encode, Input, Output};
 use core::convert::TryFrom;
-use pact::interpreter::{interpret, types::PactType};
+use pact::interpreter::types::PactType;
 
+pub mod borrowed;
 pub mod contract;
+pub mod index;
 pub mod method;
 pub mod module;
+pub mod set;
 
 use super::{ContractDomain, RuntimeDomain};
-use crate::{PartialDecode, ValidationErr};
+use crate::cooldown::cooldown_elapsed;
+use crate::decode::{DecodeField, DecodeInput};
+use crate::validation::interpret_constraints;
+use crate::{CooldownTracker, DecodeError, EncodeError, PartialDecode, ValidationErr};
 use contract::Contract;
+use method::Method;
 use module::Module;
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
+use set::{ContractSet, ModuleSet};
 
-use super::{ContractAddress, ModuleName, CONTRACT_WILDCARD, WILDCARD};
+use super::{ContractAddress, CONTRACT_WILDCARD, WILDCARD};
 
 pub const MAX_MODULES: usize = 256;
 pub const MAX_METHODS: usize = 128;
@@ -35,10 +46,11 @@ pub const MAX_CENNZNUT_BYTES: usize = u16::max_value() as usize;
 
 /// A CENNZnet permission domain struct for embedding in doughnuts
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[cfg_attr(test, derive(Clone, Debug, Eq, PartialEq))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct CENNZnutV0 {
-    pub modules: Vec<(ModuleName, Module)>,
-    pub contracts: Vec<(ContractAddress, Contract)>,
+    pub modules: ModuleSet,
+    pub contracts: ContractSet,
 }
 
 impl CENNZnutV0 {
@@ -73,70 +85,109 @@ impl CENNZnutV0 {
     }
 }
 
-impl Encode for CENNZnutV0 {
-    fn encode_to<T: Output>(&self, buf: &mut T) {
-        if self.modules.is_empty() || self.modules.len() > MAX_MODULES {
-            return;
+impl CENNZnutV0 {
+    /// Encodes the CENNZnut, failing if it exceeds any encoding limits
+    ///
+    /// # Errors
+    ///
+    /// Will return error identifying the first module, method or contract
+    /// which cannot be encoded, or if the encoded CENNZnut is too large
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        if self.modules.is_empty() {
+            return Err(EncodeError::NoModules);
         }
-        let module_count = u8::try_from(self.modules.len() - 1);
-        let contract_count = u8::try_from(self.contracts.len());
-        if module_count.is_err() || contract_count.is_err() {
-            return;
+        if self.modules.len() > MAX_MODULES {
+            return Err(EncodeError::TooManyModules(self.modules.len()));
         }
-
-        // Encode all modules, but make sure each encoding is valid
-        // before modifying the output buffer.
-        let mut module_payload_buf: Vec<u8> = Vec::<u8>::default();
-        for (_, module) in &self.modules {
-            let mut module_buf: Vec<u8> = Vec::<u8>::default();
-            module.encode_to(&mut module_buf);
-            if module_buf.is_empty() {
-                return;
-            }
-            module_payload_buf.write(module_buf.as_slice());
+        if self.contracts.len() > MAX_CONTRACTS {
+            return Err(EncodeError::TooManyContracts(self.contracts.len()));
         }
+        let module_count = u8::try_from(self.modules.len() - 1)
+            .map_err(|_| EncodeError::TooManyModules(self.modules.len()))?;
+        let contract_count = u8::try_from(self.contracts.len())
+            .map_err(|_| EncodeError::TooManyContracts(self.contracts.len()))?;
 
-        let mut preliminary_buf = Vec::<u8>::default();
+        let mut buf = Vec::<u8>::default();
 
-        preliminary_buf.write(&VERSION_BYTES);
+        buf.write(&VERSION_BYTES);
 
-        preliminary_buf.push_byte(module_count.unwrap());
-        preliminary_buf.write(module_payload_buf.as_slice());
+        buf.push_byte(module_count);
+        for (index, (_, module)) in self.modules.iter().enumerate() {
+            let module_buf = module.try_encode().map_err(|e| e.in_module(index))?;
+            buf.write(&module_buf);
+        }
 
-        preliminary_buf.push_byte(contract_count.unwrap());
+        buf.push_byte(contract_count);
         for (_, contract) in &self.contracts {
-            contract.encode_to(&mut preliminary_buf);
+            buf.write(&contract.try_encode()?);
         }
 
         // Avoid writing outside of the allocated domain buffer
-        if preliminary_buf.len() <= MAX_CENNZNUT_BYTES {
-            buf.write(preliminary_buf.as_slice());
+        if buf.len() > MAX_CENNZNUT_BYTES {
+            return Err(EncodeError::TooLarge(buf.len()));
         }
+
+        Ok(buf)
     }
 }
 
-impl PartialDecode for CENNZnutV0 {
-    fn partial_decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
-        let module_count = input.read_byte()? + 1;
-        let mut modules = Vec::<(ModuleName, Module)>::default();
-
-        for _ in 0..module_count {
-            let m: Module = Decode::decode(input)?;
-            modules.push((m.name.to_owned(), m));
+impl Encode for CENNZnutV0 {
+    fn encode_to<T: Output>(&self, buf: &mut T) {
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
         }
+    }
+}
 
-        let contract_count = input.read_byte()?;
-        let mut contracts = Vec::<(ContractAddress, Contract)>::default();
+impl CENNZnutV0 {
+    /// Decodes a `CENNZnutV0` without the version, reporting the offset and path of any failure
+    pub(crate) fn partial_decode_detailed<I: Input>(
+        input: &mut DecodeInput<I>,
+    ) -> Result<Self, DecodeError> {
+        let module_count = usize::from(input.read_byte(DecodeField::ModuleCount)?) + 1;
+        let mut modules = ModuleSet::new();
+
+        for index in 0..module_count {
+            let offset = input.offset();
+            let m = Module::decode_detailed(input).map_err(|e| e.in_module(index))?;
+            input
+                .check_order(
+                    modules.iter().next_back().map(|(key, _)| key),
+                    &m.name,
+                    offset,
+                    DecodeField::ModuleName,
+                )
+                .map_err(|e| e.in_module(index))?;
+            modules.push(m);
+        }
 
-        for _ in 0..contract_count {
-            let c: Contract = Decode::decode(input)?;
-            contracts.push((c.address, c));
+        let contract_count = input.read_byte(DecodeField::ContractCount)?;
+        let mut contracts = ContractSet::new();
+
+        for index in 0..usize::from(contract_count) {
+            let offset = input.offset();
+            let c = Contract::decode_detailed(input).map_err(|e| e.in_contract(index))?;
+            input
+                .check_order(
+                    contracts.iter().next_back().map(|(key, _)| key),
+                    &c.address,
+                    offset,
+                    DecodeField::ContractAddress,
+                )
+                .map_err(|e| e.in_contract(index))?;
+            contracts.push(c);
         }
 
         Ok(Self { modules, contracts })
     }
 }
 
+impl PartialDecode for CENNZnutV0 {
+    fn partial_decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::partial_decode_detailed(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
 impl Decode for CENNZnutV0 {
     fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
         let version = u16::from_le_bytes([input.read_byte()?, input.read_byte()?]);
@@ -161,21 +212,49 @@ impl CENNZnutV0 {
         method_name: &str,
         args: &[PactType],
     ) -> Result<(), ValidationErr<RuntimeDomain>> {
-        let module = self
-            .get_module(module_name)
-            .ok_or_else(|| ValidationErr::NoPermission(RuntimeDomain::Module))?;
-        let method = module
-            .get_method(method_name)
-            .ok_or_else(|| ValidationErr::NoPermission(RuntimeDomain::Method))?;
-        if let Some(pact) = method.get_pact() {
-            match interpret(args, pact.data_table.as_ref(), &pact.bytecode) {
-                Ok(true) => {}
-                Ok(false) => {
-                    return Err(ValidationErr::NoPermission(RuntimeDomain::MethodArguments))
-                }
-                Err(_) => return Err(ValidationErr::ConstraintsInterpretation),
-            }
+        self.get_permitted_method(module_name, method_name, args)?;
+        Ok(())
+    }
+
+    /// Validates a CENNZnut runtime module at `block_number` by:
+    /// (1) looking for `module_name` and `method_name`
+    /// (2) executing the Pact interpreter if constraints exist
+    /// (3) checking the module and method block cooldowns against the `tracker` history
+    /// (4) recording the call in the `tracker`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_module_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        let (module, method) = self.get_permitted_method(module_name, method_name, args)?;
+        if !cooldown_elapsed(
+            module.block_cooldown,
+            tracker.last_module_call(module_name),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Module {
+                module: module_name.into(),
+            }));
         }
+        if !cooldown_elapsed(
+            method.block_cooldown,
+            tracker.last_method_call(module_name, method_name),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            }));
+        }
+        tracker.record_module_call(module_name, block_number);
+        tracker.record_method_call(module_name, method_name, block_number);
         Ok(())
     }
 
@@ -190,25 +269,84 @@ impl CENNZnutV0 {
         contract_address: ContractAddress,
     ) -> Result<(), ValidationErr<ContractDomain>> {
         self.get_contract(contract_address)
-            .ok_or_else(|| ValidationErr::NoPermission(ContractDomain::Contract))?;
+            .ok_or(ValidationErr::NoPermission(ContractDomain::Contract {
+                address: contract_address,
+            }))?;
         Ok(())
     }
+
+    /// Validates a CENNZnut smart contract at `block_number` by
+    /// (1) looking for `contract_address`
+    /// (2) checking the contract block cooldown against the `tracker` history
+    /// (3) recording the call in the `tracker`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        contract_address: ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        let contract = self
+            .get_contract(contract_address)
+            .ok_or(ValidationErr::NoPermission(ContractDomain::Contract {
+                address: contract_address,
+            }))?;
+        if !cooldown_elapsed(
+            contract.block_cooldown,
+            tracker.last_contract_call(&contract_address),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(
+                ContractDomain::Contract {
+                    address: contract_address,
+                },
+            ));
+        }
+        tracker.record_contract_call(&contract_address, block_number);
+        Ok(())
+    }
+
+    /// Returns the module and method permitting a call with `args`
+    fn get_permitted_method(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(&Module, &Method), ValidationErr<RuntimeDomain>> {
+        let module = self.get_module(module_name).ok_or_else(|| {
+            ValidationErr::NoPermission(RuntimeDomain::Module {
+                module: module_name.into(),
+            })
+        })?;
+        let method = module.get_method(method_name).ok_or_else(|| {
+            ValidationErr::NoPermission(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            })
+        })?;
+        if let Some(pact) = method.get_pact() {
+            interpret_constraints(&pact, args, module_name, method_name)?;
+        }
+        Ok((module, method))
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::CENNZnutV0;
     use super::Contract;
-    use super::ContractAddress;
-    use super::Module;
-    use super::ModuleName;
+    use super::ContractSet;
+    use super::ModuleSet;
     use super::CONTRACT_WILDCARD;
 
     #[test]
     fn it_gets_no_contract_from_empty_list() {
         let cennznut = CENNZnutV0 {
-            modules: Vec::<(ModuleName, Module)>::default(),
-            contracts: Vec::<(ContractAddress, Contract)>::default(),
+            modules: ModuleSet::new(),
+            contracts: ContractSet::new(),
         };
 
         assert_eq!(cennznut.get_contract([0x55; 32]), None);
@@ -216,14 +354,14 @@ mod test {
 
     #[test]
     fn it_gets_no_contract_from_list() {
-        let mut contracts = Vec::<(ContractAddress, Contract)>::default();
+        let mut contracts = ContractSet::new();
         let contract_a = Contract::new(&[0x45_u8; 32]);
         let contract_b = Contract::new(&[0x12_u8; 32]);
-        contracts.push((contract_a.address, contract_a));
-        contracts.push((contract_b.address, contract_b));
+        contracts.push(contract_a);
+        contracts.push(contract_b);
 
         let cennznut = CENNZnutV0 {
-            modules: Vec::<(ModuleName, Module)>::default(),
+            modules: ModuleSet::new(),
             contracts,
         };
 
@@ -232,14 +370,14 @@ mod test {
 
     #[test]
     fn it_gets_a_contract() {
-        let mut contracts = Vec::<(ContractAddress, Contract)>::default();
+        let mut contracts = ContractSet::new();
         let contract_a = Contract::new(&[0x45_u8; 32]);
         let contract_b = Contract::new(&[0x12_u8; 32]);
-        contracts.push((contract_a.address, contract_a));
-        contracts.push((contract_b.address, contract_b.clone()));
+        contracts.push(contract_a);
+        contracts.push(contract_b.clone());
 
         let cennznut = CENNZnutV0 {
-            modules: Vec::<(ModuleName, Module)>::default(),
+            modules: ModuleSet::new(),
             contracts,
         };
 
@@ -248,17 +386,17 @@ mod test {
 
     #[test]
     fn it_gets_a_wildcard() {
-        let mut contracts = Vec::<(ContractAddress, Contract)>::default();
+        let mut contracts = ContractSet::new();
         let contract_a = Contract::new(&[0x45_u8; 32]);
         let contract_wildcard = Contract::new(&CONTRACT_WILDCARD);
         let contract_b = Contract::new(&[0x12_u8; 32]);
 
-        contracts.push((contract_a.address, contract_a));
-        contracts.push((contract_wildcard.address, contract_wildcard.clone()));
-        contracts.push((contract_b.address, contract_b));
+        contracts.push(contract_a);
+        contracts.push(contract_wildcard.clone());
+        contracts.push(contract_b);
 
         let cennznut = CENNZnutV0 {
-            modules: Vec::<(ModuleName, Module)>::default(),
+            modules: ModuleSet::new(),
             contracts,
         };
 
@@ -270,17 +408,17 @@ mod test {
 
     #[test]
     fn it_gives_defined_contracts_prescedence_over_wildcards() {
-        let mut contracts = Vec::<(ContractAddress, Contract)>::default();
+        let mut contracts = ContractSet::new();
         let contract_a = Contract::new(&[0x45_u8; 32]);
         let contract_wildcard = Contract::new(&CONTRACT_WILDCARD);
         let contract_b = Contract::new(&[0x12_u8; 32]);
 
-        contracts.push((contract_a.address, contract_a));
-        contracts.push((contract_wildcard.address, contract_wildcard));
-        contracts.push((contract_b.address, contract_b.clone()));
+        contracts.push(contract_a);
+        contracts.push(contract_wildcard);
+        contracts.push(contract_b.clone());
 
         let cennznut = CENNZnutV0 {
-            modules: Vec::<(ModuleName, Module)>::default(),
+            modules: ModuleSet::new(),
             contracts,
         };
 
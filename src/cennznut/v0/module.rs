@@ -6,12 +6,14 @@
 //!
 
 use super::method::Method;
+use super::set::MethodSet;
 use super::MAX_METHODS;
 use super::WILDCARD;
-use crate::cennznut::{MethodName, ModuleName};
+use crate::cennznut::ModuleName;
+use crate::decode::{DecodeField, DecodeInput, DecodePath};
+use crate::{DecodeError, EncodeError};
 #[cfg(feature = "std")]
 use ::serde::{Deserialize, Serialize};
-use alloc::borrow::ToOwned;
 use alloc::string::ToString;
 use alloc::vec::Vec;
 use codec::{Decode, Encode, Input, Output};
@@ -20,11 +22,12 @@ const BLOCK_COOLDOWN_MASK: u8 = 0b0000_0001;
 
 /// A CENNZnet permission domain module
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[cfg_attr(test, derive(Clone, Debug, Eq, PartialEq))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct Module {
     pub name: ModuleName,
     pub block_cooldown: Option<u32>,
-    pub methods: Vec<(MethodName, Method)>,
+    pub methods: MethodSet,
 }
 
 impl Module {
@@ -32,7 +35,7 @@ impl Module {
         Self {
             name: name.into(),
             block_cooldown: None,
-            methods: Vec::new(),
+            methods: MethodSet::new(),
         }
     }
 
@@ -41,7 +44,7 @@ impl Module {
         self
     }
 
-    pub fn methods(mut self, methods: Vec<(MethodName, Method)>) -> Self {
+    pub fn methods(mut self, methods: MethodSet) -> Self {
         self.methods = methods;
         self
     }
@@ -62,24 +65,41 @@ impl Module {
     }
 }
 
-impl Encode for Module {
-    fn encode_to<T: Output>(&self, buf: &mut T) {
-        if self.methods.is_empty() || self.methods.len() > MAX_METHODS {
-            return;
+impl Module {
+    /// Encodes the Module, failing if it exceeds any encoding limits
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the module has no methods, too many methods,
+    /// or a name, method or constraint which cannot be encoded
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        if self.methods.is_empty() {
+            return Err(EncodeError::NoMethods { module: 0 });
         }
-        let method_count = u8::try_from(self.methods.len() - 1);
-        if method_count.is_err() {
-            return;
+        if self.methods.len() > MAX_METHODS {
+            return Err(EncodeError::TooManyMethods {
+                module: 0,
+                count: self.methods.len(),
+            });
         }
-        let mut method_count_and_has_cooldown_byte = method_count.unwrap() << 1;
+        if self.name.len() > 32 {
+            return Err(EncodeError::ModuleNameTooLong { module: 0 });
+        }
+        let method_count =
+            u8::try_from(self.methods.len() - 1).map_err(|_| EncodeError::TooManyMethods {
+                module: 0,
+                count: self.methods.len(),
+            })?;
+
+        let mut buf = Vec::<u8>::default();
+        let mut method_count_and_has_cooldown_byte = method_count << 1;
         if self.block_cooldown.is_some() {
             method_count_and_has_cooldown_byte |= BLOCK_COOLDOWN_MASK;
         }
         buf.push_byte(method_count_and_has_cooldown_byte);
 
         let mut name = [0_u8; 32];
-        let length = 32.min(self.name.len());
-        name[0..length].clone_from_slice(&self.name.as_bytes()[0..length]);
+        name[0..self.name.len()].clone_from_slice(self.name.as_bytes());
 
         buf.write(&name);
 
@@ -89,43 +109,65 @@ impl Encode for Module {
             }
         }
 
-        for (_, method) in &self.methods {
-            method.encode_to(buf);
+        for (index, (_, method)) in self.methods.iter().enumerate() {
+            let method_buf = method.try_encode().map_err(|e| e.in_method(index))?;
+            buf.write(&method_buf);
         }
+
+        Ok(buf)
     }
 }
 
-impl Decode for Module {
-    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
-        let block_cooldown_and_method_count: u8 = input.read_byte()?;
+impl Encode for Module {
+    fn encode_to<T: Output>(&self, buf: &mut T) {
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
+        }
+    }
+}
+
+impl Module {
+    /// Decodes a Module, reporting the offset and field of any failure
+    pub(crate) fn decode_detailed<I: Input>(
+        input: &mut DecodeInput<I>,
+    ) -> Result<Self, DecodeError> {
+        let block_cooldown_and_method_count: u8 = input.read_byte(DecodeField::ModuleHeader)?;
         let method_count = (block_cooldown_and_method_count >> 1) + 1;
 
+        let name_offset = input.offset();
         let mut name_buf: [u8; 32] = Default::default();
-        input
-            .read(&mut name_buf)
-            .map_err(|_| "expected 32 byte module name")?;
+        input.read(&mut name_buf, DecodeField::ModuleName)?;
+        input.check_name(&name_buf, name_offset, DecodeField::ModuleName)?;
         let name = core::str::from_utf8(&name_buf)
-            .map_err(|_| codec::Error::from("module names should be utf8 encoded"))?
+            .map_err(|_| DecodeError::InvalidUtf8Name {
+                offset: name_offset,
+                path: DecodePath::default(),
+                field: DecodeField::ModuleName,
+            })?
             .trim_matches(char::from(0))
             .to_string();
 
         let module_cooldown =
             if (block_cooldown_and_method_count & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK {
-                Some(u32::from_le_bytes([
-                    input.read_byte()?,
-                    input.read_byte()?,
-                    input.read_byte()?,
-                    input.read_byte()?,
-                ]))
+                Some(input.read_u32(DecodeField::ModuleCooldown)?)
             } else {
                 None
             };
 
-        let mut methods: Vec<(MethodName, Method)> = Vec::default();
-
-        for _ in 0..method_count {
-            let m: Method = Decode::decode(input)?;
-            methods.push((m.name.to_owned(), m));
+        let mut methods = MethodSet::new();
+
+        for index in 0..usize::from(method_count) {
+            let offset = input.offset();
+            let m = Method::decode_detailed(input).map_err(|e| e.in_method(index))?;
+            input
+                .check_order(
+                    methods.iter().next_back().map(|(key, _)| key),
+                    &m.name,
+                    offset,
+                    DecodeField::MethodName,
+                )
+                .map_err(|e| e.in_method(index))?;
+            methods.push(m);
         }
 
         Ok(Self {
@@ -136,17 +178,24 @@ impl Decode for Module {
     }
 }
 
+impl Decode for Module {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::decode_detailed(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::{Method, Module, BLOCK_COOLDOWN_MASK};
+    use crate::EncodeError;
     use codec::{Decode, Encode};
     use std::assert_eq;
 
     macro_rules! methods {
         ($($name:expr),*) => {
             vec![
-                $( ( $name.to_string(), Method::new($name) ), )*
-            ]
+                $( Method::new($name), )*
+            ].into()
         }
     }
 
@@ -157,7 +206,7 @@ mod test {
 
         assert_eq!(module.name, "TestModule");
         assert_eq!(module.block_cooldown, None);
-        assert_eq!(module.methods, vec![]);
+        assert!(module.methods.is_empty());
     }
 
     // Encoding Tests
@@ -182,18 +231,53 @@ mod test {
     fn it_does_not_encode_without_methods() {
         let module = Module::new("TestModule");
 
-        assert_eq!(module.encode(), []);
+        assert_eq!(
+            module.try_encode(),
+            Err(EncodeError::NoMethods { module: 0 })
+        );
+        assert!(module.encode().is_empty());
     }
 
     #[test]
-    fn it_encodes_only_32_characters_for_name() {
+    fn it_does_not_encode_names_longer_than_32_bytes() {
         let module = Module::new("I don't like green eggs and ham, I don't like you Sam I am;")
             .methods(methods!("TestMethod"));
+
+        assert_eq!(
+            module.try_encode(),
+            Err(EncodeError::ModuleNameTooLong { module: 0 })
+        );
+        assert!(module.encode().is_empty());
+    }
+
+    #[test]
+    fn it_encodes_32_byte_names() {
+        let module =
+            Module::new("I don't like green eggs and ham,").methods(methods!("TestMethod"));
         let expected_length = 33 + 33;
 
         assert_eq!(module.encode().len(), expected_length);
     }
 
+    #[test]
+    fn it_reports_the_method_which_fails_to_encode() {
+        let module = Module::new("TestModule").methods(
+            vec![
+                Method::new("TestMethod"),
+                Method::new("BadMethod").constraints(vec![0x55; 257]),
+            ]
+            .into(),
+        );
+
+        assert_eq!(
+            module.try_encode(),
+            Err(EncodeError::ConstraintsTooLarge {
+                module: 0,
+                method: 1
+            })
+        );
+    }
+
     #[test]
     fn it_encodes_with_block_cooldown() {
         let module = Module::new("TestModule")
@@ -333,12 +417,12 @@ mod test {
 
         let module = Module::decode(&mut &encoded[..]).unwrap();
 
-        assert_eq!(module.methods[0].0, "I");
-        assert_eq!(module.methods[1].0, "do");
-        assert_eq!(module.methods[2].0, "not");
-        assert_eq!(module.methods[3].0, "like");
-        assert_eq!(module.methods[4].0, "them");
-        assert_eq!(module.methods[5].0, "Sam");
-        assert_eq!(module.methods[6].0, "I am");
+        assert_eq!(module.methods[0].name, "I");
+        assert_eq!(module.methods[1].name, "do");
+        assert_eq!(module.methods[2].name, "not");
+        assert_eq!(module.methods[3].name, "like");
+        assert_eq!(module.methods[4].name, "them");
+        assert_eq!(module.methods[5].name, "Sam");
+        assert_eq!(module.methods[6].name, "I am");
     }
 }
@@ -0,0 +1,659 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V0 Policy
+//!
+//! A declarative text format for CENNZnut permission domains, e.g.
+//!
+//! ```text
+//! # comments run to the end of the line
+//! module generic_asset cooldown 10 {
+//!     method transfer cooldown 5 constraints 0x00...;
+//!     method *;
+//! }
+//! contract 0x1212...12 cooldown 5;
+//! contract *;
+//! ```
+//!
+//! Names may be quoted with `"`, escaping `"` and `\` with `\`.
+//! Semicolons after methods and contracts are optional.
+//!
+
+use super::contract::Contract;
+use super::method::{Method, MAX_CONSTRAINTS};
+use super::module::Module;
+use super::set::{ContractSet, ModuleSet};
+use super::{CENNZnutV0, MAX_CONTRACTS, MAX_METHODS, MAX_MODULES};
+use crate::cennznut::{ContractAddress, CONTRACT_WILDCARD};
+use crate::{PolicyError, PolicyErrorKind};
+use alloc::fmt::Write;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::iter::Peekable;
+use core::str::Chars;
+use pact::contract::Contract as PactContract;
+
+const MAX_NAME_BYTES: usize = 32;
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    OpenBrace,
+    CloseBrace,
+    Semicolon,
+}
+
+/// A token and the line and column it starts at
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, kind: PolicyErrorKind) -> PolicyError {
+        PolicyError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+
+    /// Returns all tokens of the policy
+    fn tokenize(mut self) -> Result<(Vec<Spanned>, (usize, usize)), PolicyError> {
+        let mut tokens = Vec::<Spanned>::default();
+        while let Some(&c) = self.chars.peek() {
+            let (line, column) = (self.line, self.column);
+            let token = match c {
+                '#' => {
+                    while self.chars.peek().map_or(false, |c| *c != '\n') {
+                        self.bump();
+                    }
+                    continue;
+                }
+                c if c.is_whitespace() => {
+                    self.bump();
+                    continue;
+                }
+                '{' => {
+                    self.bump();
+                    Token::OpenBrace
+                }
+                '}' => {
+                    self.bump();
+                    Token::CloseBrace
+                }
+                ';' => {
+                    self.bump();
+                    Token::Semicolon
+                }
+                '"' => {
+                    self.bump();
+                    let mut quoted = String::new();
+                    loop {
+                        match self.bump() {
+                            Some('"') => break,
+                            Some('\\') => match self.bump() {
+                                Some(c) => quoted.push(c),
+                                None => return Err(self.error(PolicyErrorKind::UnterminatedString)),
+                            },
+                            Some(c) => quoted.push(c),
+                            None => return Err(self.error(PolicyErrorKind::UnterminatedString)),
+                        }
+                    }
+                    Token::Quoted(quoted)
+                }
+                c if is_delimiter(c) => {
+                    return Err(self.error(PolicyErrorKind::UnexpectedToken {
+                        expected: "a name or keyword",
+                    }))
+                }
+                _ => {
+                    let mut word = String::new();
+                    while let Some(&c) = self.chars.peek() {
+                        if is_delimiter(c) {
+                            break;
+                        }
+                        word.push(c);
+                        self.bump();
+                    }
+                    Token::Word(word)
+                }
+            };
+            tokens.push(Spanned {
+                token,
+                line,
+                column,
+            });
+        }
+        Ok((tokens, (self.line, self.column)))
+    }
+}
+
+/// Returns whether `c` ends an unquoted word
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || c.is_control() || "{};#\"\\".contains(c)
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    position: usize,
+    end: (usize, usize),
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Spanned> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<&Spanned, PolicyError> {
+        let (line, column) = self.end;
+        let spanned = self.tokens.get(self.position).ok_or(PolicyError {
+            line,
+            column,
+            kind: PolicyErrorKind::UnexpectedEnd { expected },
+        })?;
+        self.position += 1;
+        Ok(spanned)
+    }
+
+    /// Consumes the next token if it is the `keyword`
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Spanned {
+                token: Token::Word(word),
+                ..
+            }) if word == keyword => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Consumes the next token if it is `token`
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek().map_or(false, |spanned| &spanned.token == token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, expected: &'static str) -> Result<(), PolicyError> {
+        let spanned = self.next(expected)?;
+        if &spanned.token == token {
+            Ok(())
+        } else {
+            Err(error_at(
+                spanned,
+                PolicyErrorKind::UnexpectedToken { expected },
+            ))
+        }
+    }
+
+    /// Returns a name which is at most 32 bytes and the position it starts at
+    fn name(&mut self, expected: &'static str) -> Result<(String, usize, usize), PolicyError> {
+        let spanned = self.next(expected)?;
+        let name = match &spanned.token {
+            Token::Word(name) | Token::Quoted(name) => name.clone(),
+            _ => {
+                return Err(error_at(
+                    spanned,
+                    PolicyErrorKind::UnexpectedToken { expected },
+                ))
+            }
+        };
+        if name.len() > MAX_NAME_BYTES {
+            return Err(error_at(spanned, PolicyErrorKind::NameTooLong));
+        }
+        Ok((name, spanned.line, spanned.column))
+    }
+
+    /// Returns the unquoted word following a keyword
+    fn word(&mut self, expected: &'static str) -> Result<(&str, &Spanned), PolicyError> {
+        let spanned = self.next(expected)?;
+        match &spanned.token {
+            Token::Word(word) => Ok((word, spanned)),
+            _ => Err(error_at(
+                spanned,
+                PolicyErrorKind::UnexpectedToken { expected },
+            )),
+        }
+    }
+
+    fn cooldown(&mut self) -> Result<Option<u32>, PolicyError> {
+        if !self.eat_keyword("cooldown") {
+            return Ok(None);
+        }
+        let (word, spanned) = self.word("block cooldown")?;
+        word.parse::<u32>()
+            .map(Some)
+            .map_err(|_| error_at(spanned, PolicyErrorKind::InvalidCooldown))
+    }
+
+    fn constraints(&mut self) -> Result<Option<Vec<u8>>, PolicyError> {
+        if !self.eat_keyword("constraints") {
+            return Ok(None);
+        }
+        let (word, spanned) = self.word("constraints")?;
+        let constraints = decode_hex(word)
+            .filter(|constraints| PactContract::decode(constraints).is_ok())
+            .ok_or_else(|| error_at(spanned, PolicyErrorKind::InvalidConstraints))?;
+        if constraints.len() > MAX_CONSTRAINTS {
+            return Err(error_at(spanned, PolicyErrorKind::ConstraintsTooLarge));
+        }
+        Ok(Some(constraints))
+    }
+
+    fn method(&mut self) -> Result<Method, PolicyError> {
+        let (name, _, _) = self.name("method name")?;
+        let mut method = Method::new(&name);
+        method.block_cooldown = self.cooldown()?;
+        method.constraints = self.constraints()?;
+        self.eat(&Token::Semicolon);
+        Ok(method)
+    }
+
+    fn module(&mut self) -> Result<Module, PolicyError> {
+        let (name, line, column) = self.name("module name")?;
+        let mut module = Module::new(&name);
+        module.block_cooldown = self.cooldown()?;
+        self.expect(&Token::OpenBrace, "`{`")?;
+        while !self.eat(&Token::CloseBrace) {
+            let spanned = self.next("`method` or `}`")?;
+            if spanned.token != Token::Word("method".into()) {
+                return Err(error_at(
+                    spanned,
+                    PolicyErrorKind::UnexpectedToken {
+                        expected: "`method` or `}`",
+                    },
+                ));
+            }
+            let (line, column) = (spanned.line, spanned.column);
+            if module.methods.len() == MAX_METHODS {
+                return Err(PolicyError {
+                    line,
+                    column,
+                    kind: PolicyErrorKind::TooManyMethods,
+                });
+            }
+            let method = self.method()?;
+            module.methods.push(method);
+        }
+        if module.methods.is_empty() {
+            return Err(PolicyError {
+                line,
+                column,
+                kind: PolicyErrorKind::NoMethods,
+            });
+        }
+        Ok(module)
+    }
+
+    fn contract(&mut self) -> Result<Contract, PolicyError> {
+        let (word, spanned) = self.word("contract address")?;
+        let address = if word == "*" {
+            CONTRACT_WILDCARD
+        } else {
+            let mut address: ContractAddress = Default::default();
+            match decode_hex(word) {
+                Some(bytes) if bytes.len() == address.len() => address.copy_from_slice(&bytes),
+                _ => return Err(error_at(spanned, PolicyErrorKind::InvalidAddress)),
+            }
+            address
+        };
+        let mut contract = Contract::new(&address);
+        contract.block_cooldown = self.cooldown()?;
+        self.eat(&Token::Semicolon);
+        Ok(contract)
+    }
+
+    fn cennznut(&mut self) -> Result<CENNZnutV0, PolicyError> {
+        let mut cennznut = CENNZnutV0 {
+            modules: ModuleSet::new(),
+            contracts: ContractSet::new(),
+        };
+        while self.peek().is_some() {
+            let (word, spanned) = self.word("`module` or `contract`")?;
+            let (line, column) = (spanned.line, spanned.column);
+            let too_many = |kind| PolicyError { line, column, kind };
+            match word {
+                "module" => {
+                    if cennznut.modules.len() == MAX_MODULES {
+                        return Err(too_many(PolicyErrorKind::TooManyModules));
+                    }
+                    let module = self.module()?;
+                    cennznut.modules.push(module);
+                }
+                "contract" => {
+                    if cennznut.contracts.len() == MAX_CONTRACTS {
+                        return Err(too_many(PolicyErrorKind::TooManyContracts));
+                    }
+                    let contract = self.contract()?;
+                    cennznut.contracts.push(contract);
+                }
+                _ => {
+                    return Err(too_many(PolicyErrorKind::UnexpectedToken {
+                        expected: "`module` or `contract`",
+                    }))
+                }
+            }
+        }
+        Ok(cennznut)
+    }
+}
+
+fn error_at(spanned: &Spanned, kind: PolicyErrorKind) -> PolicyError {
+    PolicyError {
+        line: spanned.line,
+        column: spanned.column,
+        kind,
+    }
+}
+
+/// Decodes `0x` prefixed hex
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let digits = hex.as_bytes();
+    if digits.len() < 2 || &digits[..2] != b"0x" || digits.len() % 2 != 0 {
+        return None;
+    }
+    digits[2..]
+        .chunks(2)
+        .map(|pair| {
+            let high = char::from(pair[0]).to_digit(16)?;
+            let low = char::from(pair[1]).to_digit(16)?;
+            u8::try_from(high * 16 + low).ok()
+        })
+        .collect()
+}
+
+/// Writes `bytes` as `0x` prefixed lower case hex
+fn write_hex(policy: &mut String, bytes: &[u8]) {
+    policy.push_str("0x");
+    for byte in bytes {
+        let _ = write!(policy, "{:02x}", byte);
+    }
+}
+
+/// Writes `name`, quoting it if it cannot be written as a word
+fn write_name(policy: &mut String, name: &str) {
+    if !name.is_empty() && !name.chars().any(is_delimiter) {
+        policy.push_str(name);
+        return;
+    }
+    policy.push('"');
+    for c in name.chars() {
+        if c == '"' || c == '\\' {
+            policy.push('\\');
+        }
+        policy.push(c);
+    }
+    policy.push('"');
+}
+
+fn write_cooldown(policy: &mut String, cooldown: Option<u32>) {
+    if let Some(cooldown) = cooldown {
+        let _ = write!(policy, " cooldown {}", cooldown);
+    }
+}
+
+impl CENNZnutV0 {
+    /// Parses a CENNZnut from the policy text format
+    ///
+    /// # Errors
+    ///
+    /// Will return error with the line and column of the first invalid token
+    pub fn from_policy(policy: &str) -> Result<Self, PolicyError> {
+        let (tokens, end) = Lexer::new(policy).tokenize()?;
+        Parser {
+            tokens,
+            position: 0,
+            end,
+        }
+        .cennznut()
+    }
+
+    /// Returns the CENNZnut in the policy text format.
+    /// Entries are written by the name or address they are matched with during validation.
+    pub fn to_policy(&self) -> String {
+        let mut policy = String::new();
+        for (module_name, module) in &self.modules {
+            policy.push_str("module ");
+            write_name(&mut policy, module_name);
+            write_cooldown(&mut policy, module.block_cooldown);
+            policy.push_str(" {\n");
+            for (method_name, method) in &module.methods {
+                policy.push_str("    method ");
+                write_name(&mut policy, method_name);
+                write_cooldown(&mut policy, method.block_cooldown);
+                if let Some(constraints) = &method.constraints {
+                    if !constraints.is_empty() {
+                        policy.push_str(" constraints ");
+                        write_hex(&mut policy, constraints);
+                    }
+                }
+                policy.push_str(";\n");
+            }
+            policy.push_str("}\n");
+        }
+        for (address, contract) in &self.contracts {
+            policy.push_str("contract ");
+            if address == &CONTRACT_WILDCARD {
+                policy.push('*');
+            } else {
+                write_hex(&mut policy, address);
+            }
+            write_cooldown(&mut policy, contract.block_cooldown);
+            policy.push_str(";\n");
+        }
+        policy
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::set::{MethodSet, ModuleSet};
+    use super::super::{contract::Contract, method::Method, module::Module, CENNZnutV0};
+    use crate::{PolicyError, PolicyErrorKind};
+    use core::fmt::Write;
+    use pact::contract::{Contract as PactContract, DataTable};
+    use pact::interpreter::OpCode;
+    use pact::types::{Numeric, PactType};
+
+    fn make_pact() -> Vec<u8> {
+        let contract = PactContract {
+            data_table: DataTable::new(vec![PactType::Numeric(Numeric(123))]),
+            bytecode: [OpCode::EQ.into(), 0, 0, 1, 0].to_vec(),
+        };
+        let mut constraints: Vec<u8> = Vec::new();
+        contract.encode(&mut constraints);
+        constraints
+    }
+
+    fn make_cennznut() -> CENNZnutV0 {
+        let methods: MethodSet = vec![
+            Method::new("transfer")
+                .block_cooldown(5)
+                .constraints(make_pact()),
+            Method::new("*"),
+        ]
+        .into();
+        let module = Module::new("generic_asset")
+            .block_cooldown(10)
+            .methods(methods);
+        let quoted = Module::new("a \"quoted\" {name}").methods(vec![Method::new("")].into());
+        let modules: ModuleSet = vec![module, quoted].into();
+        CENNZnutV0 {
+            modules,
+            contracts: vec![
+                Contract::new(&[0x12_u8; 32]).block_cooldown(5),
+                Contract::wildcard(),
+            ]
+            .into(),
+        }
+    }
+
+    fn parse_error(policy: &str) -> PolicyError {
+        CENNZnutV0::from_policy(policy).expect_err("it fails to parse")
+    }
+
+    #[test]
+    fn it_parses() {
+        let policy = "
+            # the generic asset module
+            module generic_asset cooldown 10 { method transfer; method * }
+            contract 0x1212121212121212121212121212121212121212121212121212121212121212 cooldown 5
+            contract *
+        ";
+        let methods: MethodSet = vec![Method::new("transfer"), Method::new("*")].into();
+        let module = Module::new("generic_asset")
+            .block_cooldown(10)
+            .methods(methods);
+
+        assert_eq!(
+            CENNZnutV0::from_policy(policy),
+            Ok(CENNZnutV0 {
+                modules: vec![module].into(),
+                contracts: vec![
+                    Contract::new(&[0x12_u8; 32]).block_cooldown(5),
+                    Contract::wildcard(),
+                ]
+                .into(),
+            })
+        );
+    }
+
+    #[test]
+    fn it_pretty_prints() {
+        let mut expected = String::from(
+            "module generic_asset cooldown 10 {\n    method transfer cooldown 5 constraints ",
+        );
+        super::write_hex(&mut expected, &make_pact());
+        expected.push_str(
+            ";\n    method *;\n}\nmodule \"a \\\"quoted\\\" {name}\" {\n    method \"\";\n}\n\
+             contract 0x1212121212121212121212121212121212121212121212121212121212121212 cooldown 5;\n\
+             contract *;\n",
+        );
+
+        assert_eq!(make_cennznut().to_policy(), expected);
+    }
+
+    #[test]
+    fn it_round_trips() {
+        let cennznut = make_cennznut();
+        assert_eq!(CENNZnutV0::from_policy(&cennznut.to_policy()), Ok(cennznut));
+    }
+
+    #[test]
+    fn it_reports_the_position_of_errors() {
+        assert_eq!(
+            parse_error("module test {\n  method a;\n  modul b;\n}"),
+            PolicyError {
+                line: 3,
+                column: 3,
+                kind: PolicyErrorKind::UnexpectedToken {
+                    expected: "`method` or `}`"
+                },
+            }
+        );
+        assert_eq!(
+            parse_error("module test {\n  method a cooldown soon;\n}"),
+            PolicyError {
+                line: 2,
+                column: 21,
+                kind: PolicyErrorKind::InvalidCooldown,
+            }
+        );
+        assert_eq!(
+            parse_error("module test {\n  method a;"),
+            PolicyError {
+                line: 2,
+                column: 12,
+                kind: PolicyErrorKind::UnexpectedEnd {
+                    expected: "`method` or `}`"
+                },
+            }
+        );
+        assert_eq!(
+            parse_error("contract 0x1234"),
+            PolicyError {
+                line: 1,
+                column: 10,
+                kind: PolicyErrorKind::InvalidAddress,
+            }
+        );
+        assert_eq!(
+            parse_error("module \"test { method a; }"),
+            PolicyError {
+                line: 1,
+                column: 27,
+                kind: PolicyErrorKind::UnterminatedString,
+            }
+        );
+        assert_eq!(
+            parse_error("module test { method a constraints 0x0g; }").kind,
+            PolicyErrorKind::InvalidConstraints
+        );
+        assert_eq!(
+            parse_error("module \\ { method a; }").kind,
+            PolicyErrorKind::UnexpectedToken {
+                expected: "a name or keyword"
+            }
+        );
+    }
+
+    #[test]
+    fn it_fails_to_parse_unencodable_cennznuts() {
+        assert_eq!(
+            parse_error("module test {}"),
+            PolicyError {
+                line: 1,
+                column: 8,
+                kind: PolicyErrorKind::NoMethods,
+            }
+        );
+        assert_eq!(
+            parse_error("module test { method a_method_name_which_is_longer_than_32_bytes }"),
+            PolicyError {
+                line: 1,
+                column: 22,
+                kind: PolicyErrorKind::NameTooLong,
+            }
+        );
+
+        let mut policy = String::from("module test {");
+        for i in 0..=128 {
+            let _ = write!(policy, " method m{};", i);
+        }
+        policy.push('}');
+        assert_eq!(parse_error(&policy).kind, PolicyErrorKind::TooManyMethods);
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V0 Sets
+//!
+//! Permission domain entries keyed by their own name or address
+//!
+
+use super::contract::Contract;
+use super::method::Method;
+use super::module::Module;
+use crate::cennznut::{ContractAddress, MethodName, ModuleName};
+use alloc::vec::Vec;
+use core::iter::{FromIterator, Map};
+use core::ops::{Index, IndexMut};
+use core::slice;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A permission domain entry, identified by its name or address
+pub trait Keyed {
+    type Key: PartialEq;
+    fn key(&self) -> &Self::Key;
+}
+
+impl Keyed for Module {
+    type Key = ModuleName;
+    fn key(&self) -> &ModuleName {
+        &self.name
+    }
+}
+
+impl Keyed for Method {
+    type Key = MethodName;
+    fn key(&self) -> &MethodName {
+        &self.name
+    }
+}
+
+impl Keyed for Contract {
+    type Key = ContractAddress;
+    fn key(&self) -> &ContractAddress {
+        &self.address
+    }
+}
+
+/// Permission domain entries in encoded order, each keyed by its own name or address.
+/// Entries may share a key as the encoding allows it, lookups match the first.
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct DomainSet<T: Keyed>(Vec<T>);
+
+/// The modules of a `CENNZnutV0`, keyed by `Module::name`
+pub type ModuleSet = DomainSet<Module>;
+/// The methods of a `Module`, keyed by `Method::name`
+pub type MethodSet = DomainSet<Method>;
+/// The contracts of a `CENNZnutV0`, keyed by `Contract::address`
+pub type ContractSet = DomainSet<Contract>;
+
+/// Iterator over the entries of a `DomainSet`, paired with their keys
+pub type Iter<'a, T> = Map<slice::Iter<'a, T>, fn(&'a T) -> (&'a <T as Keyed>::Key, &'a T)>;
+
+/// Pairs an entry with its key
+fn keyed<T: Keyed>(entry: &T) -> (&T::Key, &T) {
+    (entry.key(), entry)
+}
+
+impl<T: Keyed> DomainSet<T> {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Appends `entry`, keyed by its name or address
+    pub fn push(&mut self, entry: T) {
+        self.0.push(entry);
+    }
+
+    /// Returns the first entry keyed by `key`, ignoring wildcards
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&T>
+    where
+        T::Key: PartialEq<Q>,
+    {
+        self.0.iter().find(|entry| entry.key() == key)
+    }
+
+    /// Returns the entries in order, paired with their keys
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.0.iter().map(keyed)
+    }
+
+    /// Returns the entries in order. Changing an entry's name or address changes its key.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.0.iter_mut()
+    }
+
+    /// Retains only the entries for which `f` returns true
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.0.retain(f);
+    }
+
+    /// Returns the entries in order
+    pub fn entries(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T: Keyed> DomainSet<T>
+where
+    T::Key: Ord + Clone,
+{
+    /// Sorts the entries by key, keeping the order of entries sharing a key
+    pub fn sort(&mut self) {
+        self.0.sort_by_key(|entry| entry.key().clone());
+    }
+}
+
+impl<T: Keyed> Default for DomainSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Keyed> From<Vec<T>> for DomainSet<T> {
+    fn from(entries: Vec<T>) -> Self {
+        Self(entries)
+    }
+}
+
+impl<T: Keyed> FromIterator<T> for DomainSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(entries: I) -> Self {
+        Self(entries.into_iter().collect())
+    }
+}
+
+impl<T: Keyed> Extend<T> for DomainSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, entries: I) {
+        self.0.extend(entries);
+    }
+}
+
+impl<T: Keyed> IntoIterator for DomainSet<T> {
+    type Item = T;
+    type IntoIter = alloc::vec::IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T: Keyed> IntoIterator for &'a DomainSet<T> {
+    type Item = (&'a T::Key, &'a T);
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Keyed> Index<usize> for DomainSet<T> {
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        &self.0[index]
+    }
+}
+
+/// Changing the entry's name or address changes its key
+impl<T: Keyed> IndexMut<usize> for DomainSet<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.0[index]
+    }
+}
+
+/// Serializes as `[key, entry]` pairs, the format of the former `Vec<(key, entry)>` fields
+#[cfg(feature = "std")]
+impl<T: Keyed + Serialize> Serialize for DomainSet<T>
+where
+    T::Key: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Deserializes `[key, entry]` pairs or bare entries.
+/// A legacy pair whose key does not match its entry is migrated to be keyed by the entry's
+/// name or address, as it is after an encode and decode round trip.
+#[cfg(feature = "std")]
+impl<'de, T: Keyed + Deserialize<'de>> Deserialize<'de> for DomainSet<T>
+where
+    T::Key: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Entry<K, T> {
+            Keyed(K, T),
+            Bare(T),
+        }
+
+        Ok(Vec::<Entry<T::Key, T>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|entry| match entry {
+                Entry::Keyed(_, entry) | Entry::Bare(entry) => entry,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{contract::Contract, method::Method, module::Module};
+    use super::{ContractSet, ModuleSet};
+    use std::assert_eq;
+
+    #[test]
+    fn it_keys_entries_by_name() {
+        let mut modules = ModuleSet::from(vec![Module::new("balances"), Module::new("staking")]);
+        for module in modules.iter_mut() {
+            module.name.push_str("_v2");
+        }
+
+        assert_eq!(
+            modules
+                .iter()
+                .map(|(key, _)| key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["balances_v2", "staking_v2"]
+        );
+    }
+
+    #[test]
+    fn it_serializes_as_keyed_pairs() {
+        let module = Module::new("balances").methods(vec![Method::new("transfer")].into());
+        let modules = ModuleSet::from(vec![module]);
+
+        let json = serde_json::to_string(&modules).expect("it serializes");
+        assert_eq!(
+            json,
+            r#"[["balances",{"name":"balances","block_cooldown":null,"methods":[["transfer",{"name":"transfer","block_cooldown":null,"constraints":null}]]}]]"#
+        );
+        assert_eq!(serde_json::from_str::<ModuleSet>(&json).ok(), Some(modules));
+    }
+
+    #[test]
+    fn it_deserializes_bare_entries() {
+        let contracts: ContractSet = serde_json::from_str(&format!(
+            r#"[{{"address":{:?},"block_cooldown":5}}]"#,
+            [7_u8; 32]
+        ))
+        .expect("it deserializes");
+
+        assert_eq!(
+            contracts,
+            ContractSet::from(vec![Contract::new(&[7_u8; 32]).block_cooldown(5)])
+        );
+    }
+
+    #[test]
+    fn it_migrates_keys_which_do_not_match() {
+        let json = r#"[["transfer",{"name":"burn","block_cooldown":null,"constraints":null}]]"#;
+        let methods: super::MethodSet = serde_json::from_str(json).expect("it deserializes");
+
+        assert_eq!(methods, super::MethodSet::from(vec![Method::new("burn")]));
+        assert!(methods.get("transfer").is_none());
+        assert_eq!(
+            serde_json::to_string(&methods).expect("it serializes"),
+            r#"[["burn",{"name":"burn","block_cooldown":null,"constraints":null}]]"#
+        );
+    }
+}
@@ -8,11 +8,15 @@
 use super::contract::Contract;
 use super::method::Method;
 use super::module::Module;
+use super::set::{ContractSet, MethodSet, ModuleSet};
 use crate::cennznut::{
-    v0::{MAX_CONTRACTS, MAX_METHODS, MAX_MODULES},
-    ContractAddress, ContractDomain, MethodName, ModuleName, RuntimeDomain, WILDCARD,
+    v0::{MAX_CENNZNUT_BYTES, MAX_CONTRACTS, MAX_METHODS, MAX_MODULES},
+    ContractDomain, RuntimeDomain, WILDCARD,
+};
+use crate::{
+    CENNZnut, CENNZnutV0, Constraint, CooldownTracker, EncodeError, InMemoryCooldownTracker,
+    TryFrom, ValidationErr,
 };
-use crate::{CENNZnut, CENNZnutV0, TryFrom, ValidationErr};
 
 use codec::{Decode, Encode};
 use pact::contract::{Contract as PactContract, DataTable};
@@ -26,31 +30,77 @@ const MODULE_CONTRACT_BYTES: [u8; 67] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-fn module_for_contracts() -> Vec<(ModuleName, Module)> {
+fn module_for_contracts() -> ModuleSet {
     let method = Method::new("contract");
     let methods = make_methods(&method);
     let module = Module::new("call").methods(methods);
     make_modules(&module)
 }
 
-fn make_methods(method: &Method) -> Vec<(MethodName, Method)> {
-    let mut methods = Vec::<(MethodName, Method)>::default();
-    methods.push((method.name.clone(), method.clone()));
+fn make_methods(method: &Method) -> MethodSet {
+    let mut methods = MethodSet::new();
+    methods.push(method.clone());
     methods
 }
 
-fn make_modules(module: &Module) -> Vec<(ModuleName, Module)> {
-    let mut modules = Vec::<(ModuleName, Module)>::default();
-    modules.push((module.name.clone(), module.clone()));
+fn make_modules(module: &Module) -> ModuleSet {
+    let mut modules = ModuleSet::new();
+    modules.push(module.clone());
     modules
 }
 
-fn make_contracts(contract: &Contract) -> Vec<(ContractAddress, Contract)> {
-    let mut contracts = Vec::<(ContractAddress, Contract)>::default();
-    contracts.push((contract.address, contract.clone()));
+fn make_contracts(contract: &Contract) -> ContractSet {
+    let mut contracts = ContractSet::new();
+    contracts.push(contract.clone());
     contracts
 }
 
+pub(crate) fn make_pact(data: Vec<PactType>, bytecode: Vec<u8>) -> Vec<u8> {
+    let contract = PactContract {
+        data_table: DataTable::new(data),
+        bytecode,
+    };
+    let mut constraints: Vec<u8> = Vec::new();
+    contract.encode(&mut constraints);
+    constraints
+}
+
+pub(crate) fn make_module(name: &str, methods: Vec<Method>) -> Module {
+    Module::new(name).methods(methods.into())
+}
+
+pub(crate) fn make_cennznut(modules: Vec<Module>, contracts: Vec<Contract>) -> CENNZnutV0 {
+    CENNZnutV0 {
+        modules: modules.into(),
+        contracts: contracts.into(),
+    }
+}
+
+/// A CENNZnut with repeated and wildcard modules, methods and contracts
+pub(crate) fn make_overlapping_cennznut() -> CENNZnutV0 {
+    let constraint = Constraint::arg(0).lte(PactType::Numeric(Numeric(100)));
+    make_cennznut(
+        vec![
+            make_module(WILDCARD, vec![Method::new("approve")]),
+            make_module(
+                "balances",
+                vec![
+                    Method::new("transfer")
+                        .constraint(&constraint)
+                        .expect("it encodes"),
+                    Method::new(WILDCARD).block_cooldown(10),
+                ],
+            ),
+            make_module("balances", vec![Method::new("burn")]),
+            make_module(WILDCARD, vec![Method::new("mint").block_cooldown(5)]),
+        ],
+        vec![
+            Contract::new(&[0x12_u8; 32]),
+            Contract::wildcard().block_cooldown(3),
+        ],
+    )
+}
+
 #[test]
 fn it_works_encode() {
     let method = Method::new("method_test");
@@ -87,7 +137,7 @@ fn it_works_encode_one_module() {
     let module = Module::new("module_test").methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
 
@@ -153,7 +203,7 @@ fn it_works_encode_with_module_cooldown() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
 
@@ -222,7 +272,7 @@ fn it_works_encode_with_method_cooldown() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
 
@@ -270,7 +320,7 @@ fn it_works_encode_two_contracts() {
     let contract_a = Contract::new(&[0x4a_u8; 32]);
     let contract_b = Contract::new(&[0x8b_u8; 32]).block_cooldown(0xaa55_33cc);
     let mut contracts = make_contracts(&contract_a);
-    contracts.push((contract_b.address, contract_b));
+    contracts.push(contract_b);
 
     let cennznut = CENNZnutV0 { modules, contracts };
 
@@ -372,7 +422,7 @@ fn it_works_encode_with_constraints() {
     let module = Module::new("module_test").methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let encoded = cennznut.encode();
@@ -425,9 +475,9 @@ fn it_works_with_lots_of_things_codec() {
     let method = Method::new("method_test").block_cooldown(123);
     let method2 = Method::new("method_test2").block_cooldown(321);
 
-    let mut methods: Vec<(MethodName, Method)> = Vec::default();
-    methods.push((method.name.clone(), method));
-    methods.push((method2.name.clone(), method2));
+    let mut methods = MethodSet::new();
+    methods.push(method);
+    methods.push(method2);
 
     let module = Module::new("module_test")
         .block_cooldown(86_400)
@@ -436,11 +486,11 @@ fn it_works_with_lots_of_things_codec() {
         .block_cooldown(55_555)
         .methods(methods);
 
-    let mut modules: Vec<(ModuleName, Module)> = Vec::default();
-    modules.push((module.name.clone(), module));
-    modules.push((module2.name.clone(), module2));
+    let mut modules = ModuleSet::new();
+    modules.push(module);
+    modules.push(module2);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
 
@@ -482,7 +532,7 @@ fn it_validates_modules() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [
@@ -496,17 +546,22 @@ fn it_validates_modules() {
     );
     assert_eq!(
         cennznut.validate_module("module_test2", &method.name, &args),
-        Err(ValidationErr::NoPermission(RuntimeDomain::Module))
+        Err(ValidationErr::NoPermission(RuntimeDomain::Module {
+            module: "module_test2".into(),
+        }))
     );
     assert_eq!(
         cennznut.validate_module(&module.name, "method_test2", &args),
-        Err(ValidationErr::NoPermission(RuntimeDomain::Method))
+        Err(ValidationErr::NoPermission(RuntimeDomain::Method {
+            module: "module_test".into(),
+            method: "method_test2".into(),
+        }))
     );
 }
 
 #[test]
 fn it_validates_contracts() {
-    let modules = Vec::<(ModuleName, Module)>::default();
+    let modules = ModuleSet::new();
 
     let contract = Contract::new(&[0x12_u8; 32]);
     let contracts = make_contracts(&contract);
@@ -518,7 +573,7 @@ fn it_validates_contracts() {
 
 #[test]
 fn it_invalidates_missing_contract() {
-    let modules = Vec::<(ModuleName, Module)>::default();
+    let modules = ModuleSet::new();
 
     let contract = Contract::new(&[0x12_u8; 32]);
     let contracts = make_contracts(&contract);
@@ -527,13 +582,15 @@ fn it_invalidates_missing_contract() {
 
     assert_eq!(
         cennznut.validate_contract([0x34_u8; 32]),
-        Err(ValidationErr::NoPermission(ContractDomain::Contract))
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x34_u8; 32],
+        }))
     );
 }
 
 #[test]
 fn it_validates_wildcard_contract() {
-    let modules = Vec::<(ModuleName, Module)>::default();
+    let modules = ModuleSet::new();
 
     let contract = Contract::wildcard();
     let contracts = make_contracts(&contract);
@@ -543,6 +600,74 @@ fn it_validates_wildcard_contract() {
     assert_eq!(cennznut.validate_contract(contract.address), Ok(()));
 }
 
+#[test]
+fn it_validates_module_cooldowns() {
+    let method = Method::new("method_test");
+    let methods = make_methods(&method);
+
+    let module = Module::new("module_test")
+        .block_cooldown(100)
+        .methods(methods);
+    let modules = make_modules(&module);
+
+    let contracts = ContractSet::new();
+
+    let cennznut = CENNZnutV0 { modules, contracts };
+    let mut tracker = InMemoryCooldownTracker::new();
+
+    assert_eq!(
+        cennznut.validate_module_at(1, &mut tracker, &module.name, &method.name, &[]),
+        Ok(())
+    );
+    assert_eq!(tracker.last_module_call(&module.name), Some(1));
+    assert_eq!(
+        tracker.last_method_call(&module.name, &method.name),
+        Some(1)
+    );
+    assert_eq!(
+        cennznut.validate_module_at(100, &mut tracker, &module.name, &method.name, &[]),
+        Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Module {
+            module: "module_test".into(),
+        }))
+    );
+    assert_eq!(tracker.last_module_call(&module.name), Some(1));
+    assert_eq!(
+        cennznut.validate_module_at(101, &mut tracker, &module.name, &method.name, &[]),
+        Ok(())
+    );
+    assert_eq!(tracker.last_module_call(&module.name), Some(101));
+}
+
+#[test]
+fn it_does_not_record_denied_calls() {
+    let method = Method::new("method_test").block_cooldown(10);
+    let methods = make_methods(&method);
+
+    let module = Module::new("module_test").methods(methods);
+    let modules = make_modules(&module);
+
+    let contracts = ContractSet::new();
+
+    let cennznut = CENNZnutV0 { modules, contracts };
+    let mut tracker = InMemoryCooldownTracker::new();
+
+    assert_eq!(
+        cennznut.validate_module_at(1, &mut tracker, &module.name, "method_test2", &[]),
+        Err(ValidationErr::NoPermission(RuntimeDomain::Method {
+            module: "module_test".into(),
+            method: "method_test2".into(),
+        }))
+    );
+    assert_eq!(tracker.last_module_call(&module.name), None);
+    assert_eq!(
+        cennznut.validate_contract_at(1, &mut tracker, [0x12_u8; 32]),
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+    assert_eq!(tracker.last_contract_call(&[0x12_u8; 32]), None);
+}
+
 #[test]
 fn it_validate_modules_error_with_bad_bytecode() {
     let pact = PactContract {
@@ -562,15 +687,15 @@ fn it_validate_modules_error_with_bad_bytecode() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [PactType::StringLike(StringLike(b"test"))];
 
-    assert_eq!(
+    assert!(matches!(
         cennznut.validate_module(&module.name, &method.name, &args),
-        Err(ValidationErr::ConstraintsInterpretation)
-    );
+        Err(ValidationErr::ConstraintsInterpretation(_))
+    ));
 }
 
 #[test]
@@ -595,7 +720,7 @@ fn it_validate_modules_error_with_false_constraints() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [
@@ -605,7 +730,12 @@ fn it_validate_modules_error_with_false_constraints() {
 
     assert_eq!(
         cennznut.validate_module(&module.name, &method.name, &args),
-        Err(ValidationErr::NoPermission(RuntimeDomain::MethodArguments))
+        Err(ValidationErr::NoPermission(
+            RuntimeDomain::MethodArguments {
+                module: "module_test".into(),
+                method: "method_test".into(),
+            }
+        ))
     );
 }
 
@@ -619,7 +749,7 @@ fn it_validate_modules_with_empty_constraints() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [
@@ -709,7 +839,7 @@ fn wildcard_method_validate_modules() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [];
@@ -728,7 +858,7 @@ fn wildcard_module() {
     let module = Module::new(WILDCARD).block_cooldown(1).methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
 
@@ -744,7 +874,7 @@ fn wildcard_module_validate_modules() {
     let module = Module::new(WILDCARD).block_cooldown(1).methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [];
@@ -763,7 +893,7 @@ fn wildcard_module_wildcard_method_validate_modules() {
     let module = Module::new(WILDCARD).block_cooldown(1).methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [];
@@ -784,14 +914,16 @@ fn unregistered_module_fails_validation() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [];
 
     assert_eq!(
         cennznut.validate_module("my_unregistered_module", "registered_method", &args),
-        Err(ValidationErr::NoPermission(RuntimeDomain::Module))
+        Err(ValidationErr::NoPermission(RuntimeDomain::Module {
+            module: "my_unregistered_module".into(),
+        }))
     );
 }
 
@@ -805,14 +937,17 @@ fn unregistered_method_fails_validation() {
         .methods(methods);
     let modules = make_modules(&module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
     let args = [];
 
     assert_eq!(
         cennznut.validate_module("registered_module", "my_unregistered_method", &args),
-        Err(ValidationErr::NoPermission(RuntimeDomain::Method))
+        Err(ValidationErr::NoPermission(RuntimeDomain::Method {
+            module: "registered_module".into(),
+            method: "my_unregistered_method".into(),
+        }))
     );
 }
 
@@ -821,9 +956,9 @@ fn registered_methods_have_priority_over_wildcard_methods() {
     let wild_method = Method::new(WILDCARD).block_cooldown(123);
     let registered_method = Method::new("registered_method").block_cooldown(123);
 
-    let mut methods: Vec<(MethodName, Method)> = Vec::default();
-    methods.push((wild_method.name.clone(), wild_method));
-    methods.push((registered_method.name.clone(), registered_method));
+    let mut methods = MethodSet::new();
+    methods.push(wild_method);
+    methods.push(registered_method);
 
     let module = Module::new("module_test")
         .block_cooldown(1)
@@ -846,11 +981,11 @@ fn registered_modules_have_priority_over_wildcard_modules() {
         .block_cooldown(123)
         .methods(methods);
 
-    let mut modules: Vec<(ModuleName, Module)> = Vec::default();
-    modules.push((wild_module.name.clone(), wild_module));
-    modules.push((registered_module.name.clone(), registered_module));
+    let mut modules = ModuleSet::new();
+    modules.push(wild_module);
+    modules.push(registered_module);
 
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
 
     let cennznut = CENNZnutV0 { modules, contracts };
 
@@ -861,48 +996,64 @@ fn registered_modules_have_priority_over_wildcard_modules() {
 
 #[test]
 fn it_fails_to_encode_with_zero_modules() {
-    let modules: Vec<(ModuleName, Module)> = Vec::default();
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let modules = ModuleSet::new();
+    let contracts = ContractSet::new();
     let cennznut = CENNZnutV0 { modules, contracts };
-    assert_eq!(cennznut.encode(), []);
+    assert_eq!(cennznut.try_encode(), Err(EncodeError::NoModules));
+    assert!(cennznut.encode().is_empty());
 }
 
 #[test]
 fn it_fails_to_encode_with_zero_methods() {
-    let methods: Vec<(MethodName, Method)> = Vec::default();
+    let methods = MethodSet::new();
     let module = Module::new("TestModule").methods(methods);
     let modules = make_modules(&module);
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
     let cennznut = CENNZnutV0 { modules, contracts };
-    assert_eq!(cennznut.encode(), []);
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::NoMethods { module: 0 })
+    );
+    assert!(cennznut.encode().is_empty());
 }
 
 #[test]
 fn it_fails_to_encode_with_too_many_modules() {
     let method = Method::new("registered_method");
     let methods = make_methods(&method);
-    let mut modules: Vec<(ModuleName, Module)> = Vec::default();
+    let mut modules = ModuleSet::new();
     for x in 0..MAX_MODULES + 1 {
         let module = Module::new(&x.to_string()).methods(methods.clone());
-        modules.push((module.name.clone(), module));
+        modules.push(module);
     }
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
     let cennznut = CENNZnutV0 { modules, contracts };
-    assert_eq!(cennznut.encode(), []);
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::TooManyModules(MAX_MODULES + 1))
+    );
+    assert!(cennznut.encode().is_empty());
 }
 
 #[test]
 fn it_fails_to_encode_with_too_many_methods() {
-    let mut methods: Vec<(MethodName, Method)> = Vec::default();
+    let mut methods = MethodSet::new();
     for x in 0..MAX_METHODS + 1 {
         let method = Method::new(&x.to_string());
-        methods.push((method.name.clone(), method));
+        methods.push(method);
     }
     let module = Module::new("registered_module").methods(methods);
     let modules = make_modules(&module);
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
     let cennznut = CENNZnutV0 { modules, contracts };
-    assert_eq!(cennznut.encode(), []);
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::TooManyMethods {
+            module: 0,
+            count: MAX_METHODS + 1
+        })
+    );
+    assert!(cennznut.encode().is_empty());
 }
 
 #[test]
@@ -911,33 +1062,75 @@ fn it_fails_to_encode_with_too_many_contracts() {
     let methods = make_methods(&method);
     let module = Module::new("registered_module").methods(methods);
     let modules = make_modules(&module);
-    let mut contracts = Vec::<(ContractAddress, Contract)>::default();
+    let mut contracts = ContractSet::new();
     for x in 0..MAX_CONTRACTS + 1 {
         let mut address = [0; 32];
         address[0] = x as u8 & 0xff;
         address[1] = (x >> 8) as u8 & 0xff;
         let contract = Contract::new(&address);
-        contracts.push((contract.address, contract.clone()));
+        contracts.push(contract.clone());
     }
     let cennznut = CENNZnutV0 { modules, contracts };
-    assert_eq!(cennznut.encode(), []);
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::TooManyContracts(MAX_CONTRACTS + 1))
+    );
+    assert!(cennznut.encode().is_empty());
 }
 
 #[test]
 fn it_fails_to_encode_when_cennznut_is_too_large() {
     // 33 bytes per method, 33 + 33 * Method bytes per module
     // if 64 methods, per 64 modules, total bytes > 137,000
-    let mut methods: Vec<(MethodName, Method)> = Vec::default();
-    let mut modules: Vec<(ModuleName, Module)> = Vec::default();
+    let mut methods = MethodSet::new();
+    let mut modules = ModuleSet::new();
     for x in 0..64 + 1 {
         let method = Method::new(&x.to_string());
-        methods.push((method.name.clone(), method));
+        methods.push(method);
     }
     for x in 0..64 + 1 {
         let module = Module::new(&x.to_string()).methods(methods.clone());
-        modules.push((module.name.clone(), module));
+        modules.push(module);
     }
-    let contracts = Vec::<(ContractAddress, Contract)>::default();
+    let contracts = ContractSet::new();
+    let cennznut = CENNZnutV0 { modules, contracts };
+    assert!(match cennznut.try_encode() {
+        Err(EncodeError::TooLarge(length)) => length > MAX_CENNZNUT_BYTES,
+        _ => false,
+    });
+    assert!(cennznut.encode().is_empty());
+}
+
+#[test]
+fn it_reports_the_module_which_fails_to_encode() {
+    let method = Method::new("registered_method");
+    let methods = make_methods(&method);
+    let module = Module::new("registered_module").methods(methods.clone());
+    let bad_method = Method::new("bad_method").constraints(vec![0x55; 257]);
+    let bad_module = Module::new("bad_module").methods(vec![method, bad_method].into());
+
+    let mut modules = make_modules(&module);
+    modules.push(bad_module);
+    let contracts = ContractSet::new();
+    let cennznut = CENNZnut::V0(CENNZnutV0 { modules, contracts });
+
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::ConstraintsTooLarge {
+            module: 1,
+            method: 1
+        })
+    );
+    assert!(cennznut.encode().is_empty());
+}
+
+#[test]
+fn it_try_encodes_the_same_as_encode() {
+    let modules = module_for_contracts();
+    let contract = Contract::new(&[0x5a_u8; 32]).block_cooldown(10);
+    let contracts = make_contracts(&contract);
+
     let cennznut = CENNZnutV0 { modules, contracts };
-    assert_eq!(cennznut.encode(), []);
+
+    assert_eq!(cennznut.try_encode(), Ok(cennznut.encode()));
 }
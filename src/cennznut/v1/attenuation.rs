@@ -0,0 +1,755 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V1 Attenuation
+//!
+//! Checks a delegated CENNZnut grants no more than its parent
+//!
+
+use super::contract::Contract;
+use super::method::Method;
+use super::module::Module;
+use super::CENNZnutV1;
+use crate::cennznut::{requested_names, ContractAddress, CONTRACT_WILDCARD};
+use crate::constraints::is_conjunction;
+use crate::pattern::representative_names;
+use crate::{AttenuationErr, RateLimit};
+use alloc::vec::Vec;
+use pact::contract::Contract as PactContract;
+
+impl CENNZnutV1 {
+    /// Checks every call permitted by this CENNZnut is also permitted by `parent`.
+    /// Calls denied by either CENNZnut are not permitted.
+    /// Names granted explicitly by either CENNZnut, the wildcard, and names standing for those
+    /// matched by each combination of patterns, are resolved through
+    /// `get_module`/`get_method`/`get_contract` so pattern and wildcard precedence is honoured.
+    ///
+    /// # Errors
+    ///
+    /// Will return error identifying the first grant which exceeds the `parent`
+    pub fn is_subset_of(&self, parent: &Self) -> Result<(), AttenuationErr> {
+        let module_names = representative_names(
+            self.modules
+                .iter()
+                .chain(parent.modules.iter())
+                .map(|(name, module)| (name.as_str(), module.pattern)),
+        );
+        for module_name in &module_names {
+            if self.is_module_denied(module_name) {
+                continue;
+            }
+            if let Some(module) = self.get_module(module_name) {
+                let parent_module = parent
+                    .get_module(module_name)
+                    .filter(|_| !parent.is_module_denied(module_name))
+                    .ok_or_else(|| AttenuationErr::ModuleNotGranted {
+                        module: module_name.into(),
+                    })?;
+                module_is_subset_of(self, parent, module_name, module, parent_module)?;
+            }
+        }
+
+        let addresses = requested_names(
+            self.contracts
+                .iter()
+                .chain(parent.contracts.iter())
+                .map(|(address, _)| *address),
+            CONTRACT_WILDCARD,
+        );
+        for address in addresses {
+            if self.is_contract_denied(address) {
+                continue;
+            }
+            if let Some(contract) = self.get_contract(address) {
+                let parent_contract = parent
+                    .get_contract(address)
+                    .filter(|_| !parent.is_contract_denied(address))
+                    .ok_or(AttenuationErr::ContractNotGranted { contract: address })?;
+                contract_is_subset_of(address, contract, parent_contract)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks every method permitted by `module` of `nut` is also permitted by `parent` of
+/// `parent_nut`.
+/// Method deny entries are taken from every module entry applying to the module.
+fn module_is_subset_of(
+    nut: &CENNZnutV1,
+    parent_nut: &CENNZnutV1,
+    module_name: &str,
+    module: &Module,
+    parent: &Module,
+) -> Result<(), AttenuationErr> {
+    if !cooldown_at_least(module.block_cooldown, parent.block_cooldown) {
+        return Err(AttenuationErr::ModuleCooldownShortened {
+            module: module_name.into(),
+        });
+    }
+    if !validity_within(
+        (module.valid_from, module.valid_until),
+        (parent.valid_from, parent.valid_until),
+    ) {
+        return Err(AttenuationErr::ModuleValidityWidened {
+            module: module_name.into(),
+        });
+    }
+    if !rate_limit_at_least(module.rate_limit, parent.rate_limit) {
+        return Err(AttenuationErr::ModuleRateLimitLoosened {
+            module: module_name.into(),
+        });
+    }
+    if !constraints_at_least(module.constraints.as_ref(), parent.constraints.as_ref()) {
+        return Err(AttenuationErr::ModuleConstraintsLoosened {
+            module: module_name.into(),
+        });
+    }
+    let method_names = representative_names(
+        nut.matching_modules(module_name)
+            .chain(parent_nut.matching_modules(module_name))
+            .flat_map(|module| module.methods.iter())
+            .map(|(name, method)| (name.as_str(), method.pattern)),
+    );
+    for method_name in &method_names {
+        if nut.is_method_denied(module_name, method_name) {
+            continue;
+        }
+        if let Some(method) = module.get_method(method_name) {
+            let parent_method = parent
+                .get_method(method_name)
+                .filter(|_| !parent_nut.is_method_denied(module_name, method_name))
+                .ok_or_else(|| AttenuationErr::MethodNotGranted {
+                    module: module_name.into(),
+                    method: method_name.into(),
+                })?;
+            method_is_subset_of(module_name, method_name, method, parent_method)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks `method` is permitted by `parent` with equal or stricter cooldowns, validity windows,
+/// usage quotas, rate limits and constraints
+fn method_is_subset_of(
+    module_name: &str,
+    method_name: &str,
+    method: &Method,
+    parent: &Method,
+) -> Result<(), AttenuationErr> {
+    if !cooldown_at_least(method.block_cooldown, parent.block_cooldown) {
+        return Err(AttenuationErr::MethodCooldownShortened {
+            module: module_name.into(),
+            method: method_name.into(),
+        });
+    }
+    if !validity_within(
+        (method.valid_from, method.valid_until),
+        (parent.valid_from, parent.valid_until),
+    ) {
+        return Err(AttenuationErr::MethodValidityWidened {
+            module: module_name.into(),
+            method: method_name.into(),
+        });
+    }
+    if !uses_at_most(method.max_uses, parent.max_uses) {
+        return Err(AttenuationErr::MethodUsesIncreased {
+            module: module_name.into(),
+            method: method_name.into(),
+        });
+    }
+    if !rate_limit_at_least(method.rate_limit, parent.rate_limit) {
+        return Err(AttenuationErr::MethodRateLimitLoosened {
+            module: module_name.into(),
+            method: method_name.into(),
+        });
+    }
+    if !constraints_at_least(method.constraints.as_ref(), parent.constraints.as_ref()) {
+        return Err(AttenuationErr::ConstraintsLoosened {
+            module: module_name.into(),
+            method: method_name.into(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks `contract` is permitted by `parent` with equal or stricter cooldowns, validity windows,
+/// usage quotas, rate limits, selectors and constraints
+fn contract_is_subset_of(
+    address: ContractAddress,
+    contract: &Contract,
+    parent: &Contract,
+) -> Result<(), AttenuationErr> {
+    if !cooldown_at_least(contract.block_cooldown, parent.block_cooldown) {
+        return Err(AttenuationErr::ContractCooldownShortened { contract: address });
+    }
+    if !validity_within(
+        (contract.valid_from, contract.valid_until),
+        (parent.valid_from, parent.valid_until),
+    ) {
+        return Err(AttenuationErr::ContractValidityWidened { contract: address });
+    }
+    if !uses_at_most(contract.max_uses, parent.max_uses) {
+        return Err(AttenuationErr::ContractUsesIncreased { contract: address });
+    }
+    if !rate_limit_at_least(contract.rate_limit, parent.rate_limit) {
+        return Err(AttenuationErr::ContractRateLimitLoosened { contract: address });
+    }
+    let selectors_narrowed = match (&contract.selectors, &parent.selectors) {
+        (_, None) => true,
+        (Some(selectors), Some(parent_selectors)) => selectors
+            .iter()
+            .all(|selector| parent_selectors.contains(selector)),
+        (None, Some(_)) => false,
+    };
+    if !selectors_narrowed {
+        return Err(AttenuationErr::ContractSelectorsWidened { contract: address });
+    }
+    if !constraints_at_least(contract.constraints.as_ref(), parent.constraints.as_ref()) {
+        return Err(AttenuationErr::ContractConstraintsLoosened { contract: address });
+    }
+    Ok(())
+}
+
+/// Returns whether the `child` block cooldown is at least as long as the `parent`'s
+fn cooldown_at_least(child: Option<u32>, parent: Option<u32>) -> bool {
+    match (child, parent) {
+        (_, None) => true,
+        (Some(child), Some(parent)) => child >= parent,
+        (None, Some(_)) => false,
+    }
+}
+
+/// Returns whether the `child` usage quota is no larger than the `parent`'s
+fn uses_at_most(child: Option<u32>, parent: Option<u32>) -> bool {
+    match (child, parent) {
+        (_, None) => true,
+        (Some(child), Some(parent)) => child <= parent,
+        (None, Some(_)) => false,
+    }
+}
+
+/// Returns whether the `child` rate limit is at least as strict as the `parent`'s, permitting
+/// no more calls within a window at least as long
+fn rate_limit_at_least(child: Option<RateLimit>, parent: Option<RateLimit>) -> bool {
+    match (child, parent) {
+        (_, None) => true,
+        (Some(child), Some(parent)) => child.calls <= parent.calls && child.blocks >= parent.blocks,
+        (None, Some(_)) => false,
+    }
+}
+
+/// Returns whether the `child` validity window of first and last blocks lies within the
+/// `parent`'s
+fn validity_within(child: (Option<u32>, Option<u32>), parent: (Option<u32>, Option<u32>)) -> bool {
+    let from_within = match (child.0, parent.0) {
+        (_, None) => true,
+        (Some(child), Some(parent)) => child >= parent,
+        (None, Some(_)) => false,
+    };
+    let until_within = match (child.1, parent.1) {
+        (_, None) => true,
+        (Some(child), Some(parent)) => child <= parent,
+        (None, Some(_)) => false,
+    };
+    from_within && until_within
+}
+
+/// Returns whether the `child` constraints are at least as strict as the `parent`'s.
+/// This is conservative: the child must carry the parent's constraints exactly, or, when both
+/// are conjunctions of comparisons, extend them with additional clauses over an extended data
+/// table.
+fn constraints_at_least(child: Option<&Vec<u8>>, parent: Option<&Vec<u8>>) -> bool {
+    let parent = match parent {
+        Some(parent) if !parent.is_empty() => parent,
+        _ => return true,
+    };
+    let child = match child {
+        Some(child) if !child.is_empty() => child,
+        _ => return false,
+    };
+    if child == parent {
+        return true;
+    }
+    match (PactContract::decode(child), PactContract::decode(parent)) {
+        (Ok(child), Ok(parent)) => {
+            is_conjunction(&child.bytecode)
+                && is_conjunction(&parent.bytecode)
+                && child
+                    .data_table
+                    .as_ref()
+                    .starts_with(parent.data_table.as_ref())
+                && child.bytecode.starts_with(&parent.bytecode)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::tests::{make_cennznut, make_module};
+    use super::super::{contract::Contract, method::Method, module::Module};
+    use crate::cennznut::v0::tests::make_pact;
+    use crate::cennznut::CONTRACT_WILDCARD;
+    use crate::AttenuationErr;
+    use pact::interpreter::OpCode;
+    use pact::types::{Numeric, PactType};
+
+    #[test]
+    fn it_is_a_subset_of_itself() {
+        let cennznut = make_cennznut(
+            vec![make_module("module_test", vec![Method::new("method_test")])],
+            vec![Contract::new(&[0x12_u8; 32])],
+        );
+        assert_eq!(cennznut.is_subset_of(&cennznut), Ok(()));
+    }
+
+    #[test]
+    fn it_is_a_subset_of_a_wildcard() {
+        let parent = make_cennznut(
+            vec![make_module("*", vec![Method::new("*")])],
+            vec![Contract::wildcard()],
+        );
+        let child = make_cennznut(
+            vec![make_module("module_test", vec![Method::new("method_test")])],
+            vec![Contract::new(&[0x12_u8; 32])],
+        );
+        assert_eq!(child.is_subset_of(&parent), Ok(()));
+        assert_eq!(
+            parent.is_subset_of(&child),
+            Err(AttenuationErr::ModuleNotGranted {
+                module: "*".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn it_checks_wildcards_against_explicit_parent_grants() {
+        // The child wildcard also grants `module_test`, which the parent restricts
+        let parent = make_cennznut(
+            vec![
+                make_module("*", vec![Method::new("*")]),
+                make_module("module_test", vec![Method::new("method_test")]),
+            ],
+            vec![],
+        );
+        let child = make_cennznut(vec![make_module("*", vec![Method::new("*")])], vec![]);
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::MethodNotGranted {
+                module: "module_test".to_string(),
+                method: "*".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn it_checks_patterns_against_parent_patterns() {
+        let parent = make_cennznut(
+            vec![make_module(
+                "balances",
+                vec![Method::new("transfer*").pattern()],
+            )],
+            vec![],
+        );
+        let with_method =
+            |method: Method| make_cennznut(vec![make_module("balances", vec![method])], vec![]);
+
+        assert_eq!(
+            with_method(Method::new("transfer_all")).is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(
+            with_method(Method::new("transfer_*").pattern()).is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(
+            with_method(Method::new("trans*").pattern()).is_subset_of(&parent),
+            Err(AttenuationErr::MethodNotGranted {
+                module: "balances".to_string(),
+                method: "trans*".to_string(),
+            })
+        );
+        assert_eq!(
+            with_method(Method::new("*")).is_subset_of(&parent),
+            Err(AttenuationErr::MethodNotGranted {
+                module: "balances".to_string(),
+                method: "*".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn it_respects_parent_denials() {
+        let parent = make_cennznut(
+            vec![make_module(
+                "generic_asset",
+                vec![Method::new("*"), Method::new("burn").deny()],
+            )],
+            vec![Contract::wildcard(), Contract::new(&[0x12_u8; 32]).deny()],
+        );
+        let child = make_cennznut(
+            vec![make_module("generic_asset", vec![Method::new("*")])],
+            vec![],
+        );
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::MethodNotGranted {
+                module: "generic_asset".to_string(),
+                method: "burn".to_string(),
+            })
+        );
+
+        let child = make_cennznut(
+            vec![make_module(
+                "generic_asset",
+                vec![Method::new("*"), Method::new("burn").deny()],
+            )],
+            vec![Contract::wildcard()],
+        );
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::ContractNotGranted {
+                contract: [0x12_u8; 32]
+            })
+        );
+
+        let child = make_cennznut(
+            vec![make_module(
+                "generic_asset",
+                vec![Method::new("*"), Method::new("burn").deny()],
+            )],
+            vec![Contract::wildcard(), Contract::new(&[0x12_u8; 32]).deny()],
+        );
+        assert_eq!(child.is_subset_of(&parent), Ok(()));
+    }
+
+    #[test]
+    fn it_respects_method_denials_of_other_matching_modules() {
+        let parent = make_cennznut(
+            vec![
+                make_module("generic_asset", vec![Method::new("*")]),
+                make_module("*", vec![Method::new("burn").deny()]),
+            ],
+            vec![],
+        );
+        let child = make_cennznut(
+            vec![make_module("generic_asset", vec![Method::new("*")])],
+            vec![],
+        );
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::MethodNotGranted {
+                module: "generic_asset".to_string(),
+                method: "burn".to_string(),
+            })
+        );
+
+        let child = make_cennznut(
+            vec![
+                make_module("generic_asset", vec![Method::new("*")]),
+                make_module("generic_*", vec![Method::new("burn").deny()]).pattern(),
+            ],
+            vec![],
+        );
+        assert_eq!(child.is_subset_of(&parent), Ok(()));
+    }
+
+    #[test]
+    fn it_requires_contract_selectors_and_constraints_at_least_as_strict() {
+        let with_contract =
+            |contract: Contract| make_cennznut(vec![], vec![contract, Contract::wildcard()]);
+        let parent = with_contract(
+            Contract::new(&[0x12_u8; 32])
+                .selectors(vec![[1_u8; 4], [2_u8; 4]])
+                .constraints(vec![1, 2, 3]),
+        );
+
+        assert_eq!(
+            with_contract(
+                Contract::new(&[0x12_u8; 32])
+                    .selectors(vec![[2_u8; 4]])
+                    .constraints(vec![1, 2, 3])
+            )
+            .is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(
+            with_contract(Contract::new(&[0x12_u8; 32]).constraints(vec![1, 2, 3]))
+                .is_subset_of(&parent),
+            Err(AttenuationErr::ContractSelectorsWidened {
+                contract: [0x12_u8; 32]
+            })
+        );
+        assert_eq!(
+            with_contract(Contract::new(&[0x12_u8; 32]).selectors(vec![[1_u8; 4], [3_u8; 4]]))
+                .is_subset_of(&parent),
+            Err(AttenuationErr::ContractSelectorsWidened {
+                contract: [0x12_u8; 32]
+            })
+        );
+        assert_eq!(
+            with_contract(Contract::new(&[0x12_u8; 32]).selectors(vec![[1_u8; 4]]))
+                .is_subset_of(&parent),
+            Err(AttenuationErr::ContractConstraintsLoosened {
+                contract: [0x12_u8; 32]
+            })
+        );
+    }
+
+    #[test]
+    fn it_is_not_a_subset_with_larger_usage_quotas() {
+        let parent = make_cennznut(vec![], vec![Contract::wildcard().max_uses(5)]);
+
+        assert_eq!(
+            make_cennznut(vec![], vec![Contract::wildcard().max_uses(5)]).is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(
+            make_cennznut(vec![], vec![Contract::wildcard().max_uses(6)]).is_subset_of(&parent),
+            Err(AttenuationErr::ContractUsesIncreased {
+                contract: CONTRACT_WILDCARD,
+            })
+        );
+        assert_eq!(
+            make_cennznut(vec![], vec![Contract::wildcard()]).is_subset_of(&parent),
+            Err(AttenuationErr::ContractUsesIncreased {
+                contract: CONTRACT_WILDCARD,
+            })
+        );
+    }
+
+    #[test]
+    fn it_is_not_a_subset_with_looser_module_constraints() {
+        let constraints = make_pact(
+            vec![PactType::Numeric(Numeric(16_000))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        );
+        let parent = make_cennznut(
+            vec![make_module("module_test", vec![]).constraints(constraints.clone())],
+            vec![],
+        );
+
+        assert_eq!(
+            make_cennznut(
+                vec![make_module("module_test", vec![]).constraints(constraints)],
+                vec![]
+            )
+            .is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(
+            make_cennznut(vec![make_module("module_test", vec![])], vec![]).is_subset_of(&parent),
+            Err(AttenuationErr::ModuleConstraintsLoosened {
+                module: "module_test".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn it_requires_rate_limits_as_strict_as_the_parent() {
+        let with_module = |module: Module| make_cennznut(vec![module], vec![]);
+        let parent = with_module(make_module("module_test", vec![]).rate_limit(20, 100));
+        let loosened = Err(AttenuationErr::ModuleRateLimitLoosened {
+            module: "module_test".to_string(),
+        });
+
+        assert_eq!(
+            with_module(make_module("module_test", vec![]).rate_limit(10, 200))
+                .is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(
+            with_module(make_module("module_test", vec![]).rate_limit(21, 100))
+                .is_subset_of(&parent),
+            loosened
+        );
+        assert_eq!(
+            with_module(make_module("module_test", vec![]).rate_limit(20, 50))
+                .is_subset_of(&parent),
+            loosened
+        );
+        assert_eq!(
+            with_module(make_module("module_test", vec![])).is_subset_of(&parent),
+            loosened
+        );
+    }
+
+    #[test]
+    fn it_requires_validity_windows_within_the_parent() {
+        let with_method =
+            |method: Method| make_cennznut(vec![make_module("module_test", vec![method])], vec![]);
+        let parent = with_method(Method::new("method_test").valid_from(10).valid_until(20));
+        let widened = Err(AttenuationErr::MethodValidityWidened {
+            module: "module_test".to_string(),
+            method: "method_test".to_string(),
+        });
+
+        assert_eq!(
+            with_method(Method::new("method_test").valid_from(12).valid_until(20))
+                .is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(
+            with_method(Method::new("method_test").valid_from(9).valid_until(20))
+                .is_subset_of(&parent),
+            widened
+        );
+        assert_eq!(
+            with_method(Method::new("method_test").valid_from(10)).is_subset_of(&parent),
+            widened
+        );
+        assert_eq!(
+            parent.is_subset_of(&with_method(Method::new("method_test"))),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn it_is_not_a_subset_with_shorter_cooldowns() {
+        let parent = make_cennznut(
+            vec![make_module(
+                "module_test",
+                vec![Method::new("method_test").block_cooldown(10)],
+            )
+            .block_cooldown(10)],
+            vec![Contract::new(&[0x12_u8; 32]).block_cooldown(10)],
+        );
+        let child = make_cennznut(
+            vec![make_module(
+                "module_test",
+                vec![Method::new("method_test").block_cooldown(10)],
+            )],
+            vec![],
+        );
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::ModuleCooldownShortened {
+                module: "module_test".to_string()
+            })
+        );
+
+        let child = make_cennznut(
+            vec![make_module(
+                "module_test",
+                vec![Method::new("method_test").block_cooldown(9)],
+            )
+            .block_cooldown(11)],
+            vec![],
+        );
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::MethodCooldownShortened {
+                module: "module_test".to_string(),
+                method: "method_test".to_string(),
+            })
+        );
+
+        let child = make_cennznut(
+            vec![],
+            vec![Contract::new(&[0x12_u8; 32]).block_cooldown(9)],
+        );
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::ContractCooldownShortened {
+                contract: [0x12_u8; 32]
+            })
+        );
+    }
+
+    #[test]
+    fn it_is_not_a_subset_with_ungranted_contracts() {
+        let parent = make_cennznut(vec![], vec![Contract::new(&[0x12_u8; 32])]);
+        let child = make_cennznut(vec![], vec![Contract::new(&[0x34_u8; 32])]);
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::ContractNotGranted {
+                contract: [0x34_u8; 32]
+            })
+        );
+
+        let child = make_cennznut(vec![], vec![Contract::wildcard()]);
+        assert_eq!(
+            child.is_subset_of(&parent),
+            Err(AttenuationErr::ContractNotGranted {
+                contract: CONTRACT_WILDCARD
+            })
+        );
+    }
+
+    #[test]
+    fn it_requires_constraints_at_least_as_strict() {
+        let parent_constraints = make_pact(
+            vec![PactType::Numeric(Numeric(123))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        );
+        let stricter_constraints = make_pact(
+            vec![
+                PactType::Numeric(Numeric(123)),
+                PactType::Numeric(Numeric(456)),
+            ],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0, OpCode::EQ.into(), 0, 1, 1, 1],
+        );
+        let other_constraints = make_pact(
+            vec![PactType::Numeric(Numeric(456))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        );
+        let with_constraints = |constraints: Option<Vec<u8>>| {
+            let mut method = Method::new("method_test");
+            method.constraints = constraints;
+            make_cennznut(vec![make_module("module_test", vec![method])], vec![])
+        };
+        let parent = with_constraints(Some(parent_constraints.clone()));
+        let loosened = Err(AttenuationErr::ConstraintsLoosened {
+            module: "module_test".to_string(),
+            method: "method_test".to_string(),
+        });
+
+        assert_eq!(
+            with_constraints(Some(parent_constraints)).is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(
+            with_constraints(Some(stricter_constraints.clone())).is_subset_of(&parent),
+            Ok(())
+        );
+        assert_eq!(with_constraints(None).is_subset_of(&parent), loosened);
+        assert_eq!(
+            with_constraints(Some(other_constraints)).is_subset_of(&parent),
+            loosened
+        );
+        assert_eq!(
+            parent.is_subset_of(&with_constraints(Some(stricter_constraints))),
+            loosened
+        );
+    }
+
+    #[test]
+    fn it_requires_conjunctions_to_extend_constraints() {
+        let parent_constraints = make_pact(
+            vec![PactType::Numeric(Numeric(123))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        );
+        // Appending a disjunction would permit more than the parent's constraints
+        let disjunctive_constraints = make_pact(
+            vec![
+                PactType::Numeric(Numeric(123)),
+                PactType::Numeric(Numeric(456)),
+            ],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0, OpCode::OR.into(), 0, 1, 1, 1],
+        );
+        let with_constraints = |constraints: Vec<u8>| {
+            let method = Method::new("method_test").constraints(constraints);
+            make_cennznut(vec![make_module("module_test", vec![method])], vec![])
+        };
+
+        assert_eq!(
+            with_constraints(disjunctive_constraints)
+                .is_subset_of(&with_constraints(parent_constraints)),
+            Err(AttenuationErr::ConstraintsLoosened {
+                module: "module_test".to_string(),
+                method: "method_test".to_string(),
+            })
+        );
+    }
+}
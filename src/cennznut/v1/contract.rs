@@ -0,0 +1,491 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V1 Contracts
+//!
+//! Delegated smart contract permissioning of CENNZnut for use in CENNZnet
+//!
+
+use super::{encode_bytes, encode_count};
+use crate::cennznut::v0::contract::Contract as ContractV0;
+use crate::cennznut::{ContractAddress, ContractSelector, CONTRACT_WILDCARD};
+use crate::decode::{DecodeField, DecodeInput, DecodePath};
+use crate::downgrade::DowngradeError;
+use crate::{Constraint, DecodeError, EncodeError, RateLimit};
+use alloc::vec::Vec;
+use codec::{Decode, Encode, Input, Output};
+use core::convert::TryFrom;
+use pact::contract::Contract as PactContract;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+const BLOCK_COOLDOWN_MASK: u8 = 0x01;
+const DENY_MASK: u8 = 0x02;
+const SELECTORS_MASK: u8 = 0x04;
+const CONSTRAINTS_MASK: u8 = 0x08;
+const VALID_FROM_MASK: u8 = 0x10;
+const VALID_UNTIL_MASK: u8 = 0x20;
+const MAX_USES_MASK: u8 = 0x40;
+const RATE_LIMIT_MASK: u8 = 0x80;
+
+/// A CENNZnet permission domain contract
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct Contract {
+    pub address: ContractAddress,
+    /// Whether the entry denies the contracts it applies to, over any grant
+    #[cfg_attr(feature = "std", serde(default))]
+    pub deny: bool,
+    pub block_cooldown: Option<u32>,
+    /// The first block the entry may be used at
+    pub valid_from: Option<u32>,
+    /// The last block the entry may be used at
+    pub valid_until: Option<u32>,
+    /// The number of times the entry may be used
+    pub max_uses: Option<u32>,
+    /// The most calls the entry may be used for within a window of blocks
+    pub rate_limit: Option<RateLimit>,
+    /// The message selectors which may be called, or `None` to permit any selector
+    pub selectors: Option<Vec<ContractSelector>>,
+    /// Pact constraints over the call arguments
+    pub constraints: Option<Vec<u8>>,
+}
+
+impl Contract {
+    pub fn new(address: &ContractAddress) -> Self {
+        Self {
+            address: *address,
+            deny: false,
+            block_cooldown: None,
+            valid_from: None,
+            valid_until: None,
+            max_uses: None,
+            rate_limit: None,
+            selectors: None,
+            constraints: None,
+        }
+    }
+
+    pub fn wildcard() -> Self {
+        Self {
+            address: CONTRACT_WILDCARD,
+            deny: false,
+            block_cooldown: None,
+            valid_from: None,
+            valid_until: None,
+            max_uses: None,
+            rate_limit: None,
+            selectors: None,
+            constraints: None,
+        }
+    }
+
+    /// Makes the entry deny the contracts it applies to
+    pub fn deny(mut self) -> Self {
+        self.deny = true;
+        self
+    }
+
+    pub fn block_cooldown(mut self, block_cooldown: u32) -> Self {
+        self.block_cooldown = Some(block_cooldown);
+        self
+    }
+
+    pub fn valid_from(mut self, valid_from: u32) -> Self {
+        self.valid_from = Some(valid_from);
+        self
+    }
+
+    pub fn valid_until(mut self, valid_until: u32) -> Self {
+        self.valid_until = Some(valid_until);
+        self
+    }
+
+    pub fn max_uses(mut self, max_uses: u32) -> Self {
+        self.max_uses = Some(max_uses);
+        self
+    }
+
+    pub fn rate_limit(mut self, calls: u32, blocks: u32) -> Self {
+        self.rate_limit = Some(RateLimit::new(calls, blocks));
+        self
+    }
+
+    pub fn selectors(mut self, selectors: Vec<ContractSelector>) -> Self {
+        self.selectors = Some(selectors);
+        self
+    }
+
+    pub fn constraints(mut self, constraints: Vec<u8>) -> Self {
+        self.constraints = Some(constraints);
+        self
+    }
+
+    /// Encodes `constraint` as the Contract constraints
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the constraint has too many clauses to encode
+    pub fn constraint(mut self, constraint: &Constraint) -> Result<Self, EncodeError> {
+        let constraints = constraint.try_encode()?;
+        self.constraints = Some(constraints);
+        Ok(self)
+    }
+
+    /// Returns whether the Contract permits calling `selector`
+    pub fn permits_selector(&self, selector: &ContractSelector) -> bool {
+        match &self.selectors {
+            Some(selectors) => selectors.contains(selector),
+            None => true,
+        }
+    }
+
+    /// Returns the Pact contract, if it exists in the Contract
+    pub fn get_pact(&self) -> Option<PactContract> {
+        match &self.constraints {
+            // A decoded Contract has its constraints checked during decoding
+            Some(constraints) => PactContract::decode(constraints).ok(),
+            None => None,
+        }
+    }
+
+    /// Returns whether the Contract restricts the selectors or arguments of calls
+    pub fn restricts_calls(&self) -> bool {
+        self.selectors.is_some()
+            || self
+                .constraints
+                .as_ref()
+                .map_or(false, |constraints| !constraints.is_empty())
+    }
+
+    /// Encodes the Contract
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the rate limit window is empty,
+    /// or the selectors or constraints are longer than a `Compact<u32>` length
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        if matches!(self.rate_limit, Some(RateLimit { blocks: 0, .. })) {
+            return Err(EncodeError::EmptyContractRateLimitWindow { contract: 0 });
+        }
+        let constraints = match &self.constraints {
+            Some(constraints) if !constraints.is_empty() => Some(constraints),
+            _ => None,
+        };
+
+        let mut buf = Vec::<u8>::default();
+        let has_cooldown_byte: u8 = if self.block_cooldown.is_some() {
+            BLOCK_COOLDOWN_MASK
+        } else {
+            0x00_u8
+        };
+        let is_deny_byte: u8 = if self.deny { DENY_MASK } else { 0 };
+        let has_selectors_byte: u8 = if self.selectors.is_some() {
+            SELECTORS_MASK
+        } else {
+            0
+        };
+        let has_constraints_byte: u8 = if constraints.is_some() {
+            CONSTRAINTS_MASK
+        } else {
+            0
+        };
+        let has_valid_from_byte: u8 = if self.valid_from.is_some() {
+            VALID_FROM_MASK
+        } else {
+            0
+        };
+        let has_valid_until_byte: u8 = if self.valid_until.is_some() {
+            VALID_UNTIL_MASK
+        } else {
+            0
+        };
+        let has_max_uses_byte: u8 = if self.max_uses.is_some() {
+            MAX_USES_MASK
+        } else {
+            0
+        };
+        let has_rate_limit_byte: u8 = if self.rate_limit.is_some() {
+            RATE_LIMIT_MASK
+        } else {
+            0
+        };
+        buf.push_byte(
+            has_cooldown_byte
+                | is_deny_byte
+                | has_selectors_byte
+                | has_constraints_byte
+                | has_valid_from_byte
+                | has_valid_until_byte
+                | has_max_uses_byte
+                | has_rate_limit_byte,
+        );
+        buf.write(&self.address);
+
+        if let Some(cooldown) = self.block_cooldown {
+            buf.write(&cooldown.to_le_bytes());
+        }
+        if let Some(valid_from) = self.valid_from {
+            buf.write(&valid_from.to_le_bytes());
+        }
+        if let Some(valid_until) = self.valid_until {
+            buf.write(&valid_until.to_le_bytes());
+        }
+        if let Some(max_uses) = self.max_uses {
+            buf.write(&max_uses.to_le_bytes());
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            buf.write(&rate_limit.calls.to_le_bytes());
+            buf.write(&rate_limit.blocks.to_le_bytes());
+        }
+
+        if let Some(selectors) = &self.selectors {
+            encode_count(selectors.len(), &mut buf)?;
+            for selector in selectors {
+                buf.write(selector);
+            }
+        }
+
+        if let Some(constraints) = constraints {
+            encode_bytes(constraints, &mut buf)?;
+        }
+
+        Ok(buf)
+    }
+
+    /// Decodes a Contract, reporting the offset and field of any failure
+    pub(crate) fn decode_detailed<I: Input>(
+        input: &mut DecodeInput<I>,
+    ) -> Result<Self, DecodeError> {
+        let header_offset = input.offset();
+        let has_cooldown_byte: u8 = input.read_byte(DecodeField::ContractHeader)?;
+        input.check_header(
+            has_cooldown_byte,
+            BLOCK_COOLDOWN_MASK
+                | DENY_MASK
+                | SELECTORS_MASK
+                | CONSTRAINTS_MASK
+                | VALID_FROM_MASK
+                | VALID_UNTIL_MASK
+                | MAX_USES_MASK
+                | RATE_LIMIT_MASK,
+            header_offset,
+            DecodeField::ContractHeader,
+        )?;
+
+        let mut address = ContractAddress::default();
+        input.read(&mut address, DecodeField::ContractAddress)?;
+
+        let deny = (has_cooldown_byte & DENY_MASK) == DENY_MASK;
+
+        let block_cooldown = if (has_cooldown_byte & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK {
+            Some(input.read_u32(DecodeField::ContractCooldown)?)
+        } else {
+            None
+        };
+        let valid_from = if (has_cooldown_byte & VALID_FROM_MASK) == VALID_FROM_MASK {
+            Some(input.read_u32(DecodeField::ContractValidFrom)?)
+        } else {
+            None
+        };
+        let valid_until = if (has_cooldown_byte & VALID_UNTIL_MASK) == VALID_UNTIL_MASK {
+            Some(input.read_u32(DecodeField::ContractValidUntil)?)
+        } else {
+            None
+        };
+        let max_uses = if (has_cooldown_byte & MAX_USES_MASK) == MAX_USES_MASK {
+            Some(input.read_u32(DecodeField::ContractMaxUses)?)
+        } else {
+            None
+        };
+        let rate_limit = if (has_cooldown_byte & RATE_LIMIT_MASK) == RATE_LIMIT_MASK {
+            Some(input.read_rate_limit(DecodeField::ContractRateLimit)?)
+        } else {
+            None
+        };
+
+        let selectors = if (has_cooldown_byte & SELECTORS_MASK) == SELECTORS_MASK {
+            let selector_count = input.read_compact_u32(DecodeField::SelectorCount)?;
+            let mut selectors = Vec::<ContractSelector>::default();
+            for _ in 0..selector_count {
+                let mut selector = ContractSelector::default();
+                input.read(&mut selector, DecodeField::Selector)?;
+                selectors.push(selector);
+            }
+            Some(selectors)
+        } else {
+            None
+        };
+
+        let constraints = if (has_cooldown_byte & CONSTRAINTS_MASK) == CONSTRAINTS_MASK {
+            let constraints_offset = input.offset();
+            let constraints_buf = input.read_bytes(DecodeField::Constraints)?;
+            if input.is_strict() && constraints_buf.is_empty() {
+                return Err(DecodeError::EmptyConstraints {
+                    offset: constraints_offset,
+                    path: DecodePath::default(),
+                });
+            }
+            if PactContract::decode(&constraints_buf).is_err() {
+                return Err(DecodeError::InvalidConstraints {
+                    offset: constraints_offset,
+                    path: DecodePath::default(),
+                });
+            }
+            Some(constraints_buf)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            address,
+            deny,
+            block_cooldown,
+            valid_from,
+            valid_until,
+            max_uses,
+            rate_limit,
+            selectors,
+            constraints,
+        })
+    }
+}
+
+impl Encode for Contract {
+    fn encode_to<T: Output>(&self, buf: &mut T) {
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
+        }
+    }
+}
+
+impl Decode for Contract {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::decode_detailed(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
+impl From<ContractV0> for Contract {
+    fn from(contract: ContractV0) -> Self {
+        Self {
+            address: contract.address,
+            deny: false,
+            block_cooldown: contract.block_cooldown,
+            valid_from: None,
+            valid_until: None,
+            max_uses: None,
+            rate_limit: None,
+            selectors: None,
+            constraints: None,
+        }
+    }
+}
+
+impl TryFrom<Contract> for ContractV0 {
+    type Error = DowngradeError;
+    fn try_from(contract: Contract) -> Result<Self, Self::Error> {
+        if contract.deny {
+            return Err(DowngradeError::ContractDeny { contract: 0 });
+        }
+        if contract.selectors.is_some() {
+            return Err(DowngradeError::ContractSelectors { contract: 0 });
+        }
+        if contract.restricts_calls() {
+            return Err(DowngradeError::ContractConstraints { contract: 0 });
+        }
+        if contract.valid_from.is_some() || contract.valid_until.is_some() {
+            return Err(DowngradeError::ContractValidity { contract: 0 });
+        }
+        if contract.max_uses.is_some() {
+            return Err(DowngradeError::ContractMaxUses { contract: 0 });
+        }
+        if contract.rate_limit.is_some() {
+            return Err(DowngradeError::ContractRateLimit { contract: 0 });
+        }
+        Ok(Self {
+            address: contract.address,
+            block_cooldown: contract.block_cooldown,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Contract, BLOCK_COOLDOWN_MASK, CONSTRAINTS_MASK, SELECTORS_MASK};
+    use crate::decode::DecodeInput;
+    use crate::{Constraint, DecodeError, DecodePath};
+    use codec::{Decode, Encode};
+    use pact::interpreter::types::{Numeric, PactType};
+    use std::assert_eq;
+
+    #[test]
+    fn it_encodes_block_cooldown() {
+        let contract = Contract::new(&[0x5a_u8; 32]).block_cooldown(0x1337_b33f);
+
+        assert_eq!(
+            contract.encode(),
+            [
+                vec![BLOCK_COOLDOWN_MASK],
+                vec![0x5a_u8; 32],
+                vec![0x3f, 0xb3, 0x37, 0x13]
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn it_decodes_block_cooldown() {
+        let contract = Contract::wildcard().block_cooldown(0x1337_b33f);
+
+        assert_eq!(Contract::decode(&mut &contract.encode()[..]), Ok(contract));
+    }
+
+    #[test]
+    fn it_throws_error_on_short_address_length() {
+        let encoded = [0x00; 20];
+
+        assert_eq!(
+            Contract::decode(&mut &encoded[..]),
+            Err(codec::Error::from("expected 32 byte address")),
+        );
+    }
+
+    #[test]
+    fn it_encodes_selectors_and_constraints() {
+        let contract = Contract::new(&[0x5a_u8; 32])
+            .selectors(vec![[0xde, 0xad, 0xbe, 0xef], [0x01, 0x02, 0x03, 0x04]])
+            .constraint(&Constraint::arg(0).lte(PactType::Numeric(Numeric(100))))
+            .expect("it encodes");
+        let constraints = contract.constraints.clone().expect("it has constraints");
+
+        assert_eq!(
+            contract.encode(),
+            [
+                vec![SELECTORS_MASK | CONSTRAINTS_MASK],
+                vec![0x5a_u8; 32],
+                vec![2 << 2, 0xde, 0xad, 0xbe, 0xef, 0x01, 0x02, 0x03, 0x04],
+                vec![(constraints.len() as u8) << 2],
+                constraints,
+            ]
+            .concat()
+        );
+        assert_eq!(Contract::decode(&mut &contract.encode()[..]), Ok(contract));
+    }
+
+    #[test]
+    fn it_rejects_invalid_constraints() {
+        let encoded = [
+            vec![CONSTRAINTS_MASK],
+            vec![0x5a_u8; 32],
+            vec![1 << 2, 0xff],
+        ]
+        .concat();
+
+        assert_eq!(
+            Contract::decode_detailed(&mut DecodeInput::new(&mut &encoded[..])),
+            Err(DecodeError::InvalidConstraints {
+                offset: 33,
+                path: DecodePath::default(),
+            })
+        );
+    }
+}
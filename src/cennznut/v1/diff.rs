@@ -0,0 +1,758 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V1 Diff
+//!
+//! Permission changes between two `CENNZnuts`
+//!
+
+use super::contract::Contract;
+use super::method::Method;
+use super::module::Module;
+use super::CENNZnutV1;
+use crate::cennznut::{ContractAddress, CONTRACT_WILDCARD, WILDCARD};
+use crate::{PermissionChange, PermissionDiff};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+impl CENNZnutV1 {
+    /// Returns the permission changes from this CENNZnut to `other`,
+    /// independent of the order of their entries
+    pub fn diff(&self, other: &Self) -> PermissionDiff {
+        let mut changes = Vec::<PermissionChange>::default();
+
+        let modules = by_name(
+            self.modules
+                .iter()
+                .map(|(name, m)| ((name.as_str(), m.pattern, m.deny), m)),
+        );
+        let other_modules = by_name(
+            other
+                .modules
+                .iter()
+                .map(|(name, m)| ((name.as_str(), m.pattern, m.deny), m)),
+        );
+        for ((name, _, _), change) in merge(&modules, &other_modules) {
+            match change {
+                (Some(a), None) => changes.push(module_removed(name, a)),
+                (None, Some(b)) => changes.push(module_added(name, b)),
+                (Some(a), Some(b)) => diff_module(name, a, b, &mut changes),
+                (None, None) => {}
+            }
+        }
+
+        let contracts = by_key(
+            self.contracts
+                .iter()
+                .map(|(address, c)| ((*address, c.deny), c)),
+            |(address, _)| *address == CONTRACT_WILDCARD,
+        );
+        let other_contracts = by_key(
+            other
+                .contracts
+                .iter()
+                .map(|(address, c)| ((*address, c.deny), c)),
+            |(address, _)| *address == CONTRACT_WILDCARD,
+        );
+        for ((address, _), change) in merge(&contracts, &other_contracts) {
+            match change {
+                (Some(a), None) => changes.push(contract_removed(address, a)),
+                (None, Some(b)) => changes.push(contract_added(address, b)),
+                (Some(a), Some(b)) => diff_contract(address, a, b, &mut changes),
+                (None, None) => {}
+            }
+        }
+
+        PermissionDiff { changes }
+    }
+}
+
+/// Returns the change for a module entry only in the newer CENNZnut
+fn module_added(name: &str, module: &Module) -> PermissionChange {
+    let module_name = name.into();
+    if module.deny {
+        PermissionChange::ModuleDenied {
+            module: module_name,
+        }
+    } else {
+        PermissionChange::ModuleAdded {
+            module: module_name,
+        }
+    }
+}
+
+/// Returns the change for a module entry only in the older CENNZnut
+fn module_removed(name: &str, module: &Module) -> PermissionChange {
+    let module_name = name.into();
+    if module.deny {
+        PermissionChange::ModuleUndenied {
+            module: module_name,
+        }
+    } else {
+        PermissionChange::ModuleRemoved {
+            module: module_name,
+        }
+    }
+}
+
+/// Returns the change for a method entry only in the newer CENNZnut
+fn method_added(module_name: &str, name: &str, method: &Method) -> PermissionChange {
+    let (module, method_name) = (module_name.into(), name.into());
+    if method.deny {
+        PermissionChange::MethodDenied {
+            module,
+            method: method_name,
+        }
+    } else {
+        PermissionChange::MethodAdded {
+            module,
+            method: method_name,
+        }
+    }
+}
+
+/// Returns the change for a method entry only in the older CENNZnut
+fn method_removed(module_name: &str, name: &str, method: &Method) -> PermissionChange {
+    let (module, method_name) = (module_name.into(), name.into());
+    if method.deny {
+        PermissionChange::MethodUndenied {
+            module,
+            method: method_name,
+        }
+    } else {
+        PermissionChange::MethodRemoved {
+            module,
+            method: method_name,
+        }
+    }
+}
+
+/// Returns the change for a contract entry only in the newer CENNZnut
+fn contract_added(address: ContractAddress, contract: &Contract) -> PermissionChange {
+    if contract.deny {
+        PermissionChange::ContractDenied { contract: address }
+    } else {
+        PermissionChange::ContractAdded { contract: address }
+    }
+}
+
+/// Returns the change for a contract entry only in the older CENNZnut
+fn contract_removed(address: ContractAddress, contract: &Contract) -> PermissionChange {
+    if contract.deny {
+        PermissionChange::ContractUndenied { contract: address }
+    } else {
+        PermissionChange::ContractRemoved { contract: address }
+    }
+}
+
+fn diff_module(name: &str, a: &Module, b: &Module, changes: &mut Vec<PermissionChange>) {
+    if a.block_cooldown != b.block_cooldown {
+        changes.push(PermissionChange::ModuleCooldownChanged {
+            module: name.into(),
+            from: a.block_cooldown,
+            to: b.block_cooldown,
+        });
+    }
+    let (from, to) = ((a.valid_from, a.valid_until), (b.valid_from, b.valid_until));
+    if from != to {
+        changes.push(PermissionChange::ModuleValidityChanged {
+            module: name.into(),
+            from,
+            to,
+        });
+    }
+    if a.rate_limit != b.rate_limit {
+        changes.push(PermissionChange::ModuleRateLimitChanged {
+            module: name.into(),
+            from: a.rate_limit,
+            to: b.rate_limit,
+        });
+    }
+    let (from, to) = (
+        non_empty(a.constraints.as_ref()),
+        non_empty(b.constraints.as_ref()),
+    );
+    if from != to {
+        changes.push(PermissionChange::ModuleConstraintsChanged {
+            module: name.into(),
+            from,
+            to,
+        });
+    }
+    let methods = by_name(
+        a.methods
+            .iter()
+            .map(|(name, m)| ((name.as_str(), m.pattern, m.deny), m)),
+    );
+    let other_methods = by_name(
+        b.methods
+            .iter()
+            .map(|(name, m)| ((name.as_str(), m.pattern, m.deny), m)),
+    );
+    for ((method_name, _, _), change) in merge(&methods, &other_methods) {
+        match change {
+            (Some(a), None) => changes.push(method_removed(name, method_name, a)),
+            (None, Some(b)) => changes.push(method_added(name, method_name, b)),
+            (Some(a), Some(b)) => diff_method(name, method_name, a, b, changes),
+            (None, None) => {}
+        }
+    }
+}
+
+fn diff_method(
+    module_name: &str,
+    method_name: &str,
+    a: &Method,
+    b: &Method,
+    changes: &mut Vec<PermissionChange>,
+) {
+    if a.block_cooldown != b.block_cooldown {
+        changes.push(PermissionChange::MethodCooldownChanged {
+            module: module_name.into(),
+            method: method_name.into(),
+            from: a.block_cooldown,
+            to: b.block_cooldown,
+        });
+    }
+    let (from, to) = ((a.valid_from, a.valid_until), (b.valid_from, b.valid_until));
+    if from != to {
+        changes.push(PermissionChange::MethodValidityChanged {
+            module: module_name.into(),
+            method: method_name.into(),
+            from,
+            to,
+        });
+    }
+    if a.max_uses != b.max_uses {
+        changes.push(PermissionChange::MethodMaxUsesChanged {
+            module: module_name.into(),
+            method: method_name.into(),
+            from: a.max_uses,
+            to: b.max_uses,
+        });
+    }
+    if a.rate_limit != b.rate_limit {
+        changes.push(PermissionChange::MethodRateLimitChanged {
+            module: module_name.into(),
+            method: method_name.into(),
+            from: a.rate_limit,
+            to: b.rate_limit,
+        });
+    }
+    let (from, to) = (
+        non_empty(a.constraints.as_ref()),
+        non_empty(b.constraints.as_ref()),
+    );
+    if from != to {
+        changes.push(PermissionChange::ConstraintsChanged {
+            module: module_name.into(),
+            method: method_name.into(),
+            from,
+            to,
+        });
+    }
+}
+
+fn diff_contract(
+    address: ContractAddress,
+    a: &Contract,
+    b: &Contract,
+    changes: &mut Vec<PermissionChange>,
+) {
+    if a.block_cooldown != b.block_cooldown {
+        changes.push(PermissionChange::ContractCooldownChanged {
+            contract: address,
+            from: a.block_cooldown,
+            to: b.block_cooldown,
+        });
+    }
+    let (from, to) = ((a.valid_from, a.valid_until), (b.valid_from, b.valid_until));
+    if from != to {
+        changes.push(PermissionChange::ContractValidityChanged {
+            contract: address,
+            from,
+            to,
+        });
+    }
+    if a.max_uses != b.max_uses {
+        changes.push(PermissionChange::ContractMaxUsesChanged {
+            contract: address,
+            from: a.max_uses,
+            to: b.max_uses,
+        });
+    }
+    if a.rate_limit != b.rate_limit {
+        changes.push(PermissionChange::ContractRateLimitChanged {
+            contract: address,
+            from: a.rate_limit,
+            to: b.rate_limit,
+        });
+    }
+    if a.selectors != b.selectors {
+        changes.push(PermissionChange::ContractSelectorsChanged {
+            contract: address,
+            from: a.selectors.clone(),
+            to: b.selectors.clone(),
+        });
+    }
+    let (from, to) = (
+        non_empty(a.constraints.as_ref()),
+        non_empty(b.constraints.as_ref()),
+    );
+    if from != to {
+        changes.push(PermissionChange::ContractConstraintsChanged {
+            contract: address,
+            from,
+            to,
+        });
+    }
+}
+
+/// Returns the constraints, treating empty constraints as none as they are not encoded
+fn non_empty(constraints: Option<&Vec<u8>>) -> Option<Vec<u8>> {
+    constraints
+        .filter(|constraints| !constraints.is_empty())
+        .cloned()
+}
+
+/// Returns the `entries` ordered by key, keeping the entry of a repeated key which is matched
+/// during validation: the last of a repeated wildcard key, and the first of any other.
+fn by_key<K: Ord, V>(
+    entries: impl Iterator<Item = (K, V)>,
+    is_wildcard: impl Fn(&K) -> bool,
+) -> BTreeMap<K, V> {
+    let mut map = BTreeMap::new();
+    for (key, value) in entries {
+        if is_wildcard(&key) {
+            map.insert(key, value);
+        } else {
+            map.entry(key).or_insert(value);
+        }
+    }
+    map
+}
+
+/// The key of a module or method entry: its name, whether the name is a pattern and whether
+/// the entry is a denial. A literal name and a pattern, or a grant and a denial, of the same
+/// name are different permissions, so neither shadows the other.
+type NameKey<'a> = (&'a str, bool, bool);
+
+/// Returns the module or method `entries` ordered by key, as `by_key`
+fn by_name<'a, V>(entries: impl Iterator<Item = (NameKey<'a>, V)>) -> BTreeMap<NameKey<'a>, V> {
+    by_key(entries, |(name, _, _)| *name == WILDCARD)
+}
+
+/// The entries of each side for a key
+type Sides<'a, V> = (Option<&'a V>, Option<&'a V>);
+
+/// Returns the union of keys in `a` and `b`, in order, with the value of each side
+fn merge<'a, K: Ord + Copy, V>(
+    a: &'a BTreeMap<K, &'a V>,
+    b: &'a BTreeMap<K, &'a V>,
+) -> Vec<(K, Sides<'a, V>)> {
+    let mut keys: Vec<K> = a.keys().chain(b.keys()).copied().collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .map(|key| (key, (a.get(&key).copied(), b.get(&key).copied())))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::tests::{make_cennznut, make_module};
+    use super::super::{contract::Contract, method::Method, module::Module};
+    use crate::cennznut::v0::{method::Method as MethodV0, module::Module as ModuleV0};
+    use crate::{CENNZnut, CENNZnutV0, PermissionChange, PermissionDiff};
+
+    #[test]
+    fn it_ignores_entry_order() {
+        let a = make_cennznut(
+            vec![
+                make_module(
+                    "module_a",
+                    vec![Method::new("method_a"), Method::new("method_b")],
+                ),
+                make_module("module_b", vec![Method::new("method_a")]),
+            ],
+            vec![Contract::new(&[0x12_u8; 32]), Contract::wildcard()],
+        );
+        let b = make_cennznut(
+            vec![
+                make_module("module_b", vec![Method::new("method_a")]),
+                make_module(
+                    "module_a",
+                    vec![Method::new("method_b"), Method::new("method_a")],
+                ),
+            ],
+            vec![Contract::wildcard(), Contract::new(&[0x12_u8; 32])],
+        );
+
+        let diff = a.diff(&b);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "no permission changes");
+    }
+
+    #[test]
+    fn it_compares_the_last_of_repeated_wildcards() {
+        let a = make_cennznut(
+            vec![
+                make_module("*", vec![Method::new("method_a")]),
+                make_module("*", vec![Method::new("method_a")]),
+            ],
+            vec![Contract::wildcard(), Contract::wildcard()],
+        );
+        let b = make_cennznut(
+            vec![
+                make_module("*", vec![Method::new("method_a")]),
+                make_module("*", vec![Method::new("method_a").block_cooldown(5)]),
+            ],
+            vec![Contract::wildcard(), Contract::wildcard().block_cooldown(7)],
+        );
+
+        assert_eq!(
+            a.diff(&b).to_string(),
+            [
+                "~ method *::method_a block cooldown: none -> 5".to_string(),
+                format!("~ contract 0x{} block cooldown: none -> 7", "00".repeat(32)),
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_lists_changes() {
+        let a = make_cennznut(
+            vec![
+                make_module(
+                    "module_a",
+                    vec![
+                        Method::new("method_a").block_cooldown(5),
+                        Method::new("method_b").constraints(vec![1, 2, 3]),
+                    ],
+                ),
+                make_module("module_b", vec![Method::new("method_a")]),
+            ],
+            vec![Contract::new(&[0x12_u8; 32]), Contract::wildcard()],
+        );
+        let b = make_cennznut(
+            vec![
+                make_module(
+                    "module_a",
+                    vec![
+                        Method::new("method_b"),
+                        Method::new("method_c"),
+                        Method::new("method_a").block_cooldown(10),
+                    ],
+                )
+                .block_cooldown(3),
+                make_module("module_c", vec![Method::new("method_a")]),
+            ],
+            vec![Contract::new(&[0x12_u8; 32]).block_cooldown(7)],
+        );
+
+        assert_eq!(
+            a.diff(&b),
+            PermissionDiff {
+                changes: vec![
+                    PermissionChange::ModuleCooldownChanged {
+                        module: "module_a".to_string(),
+                        from: None,
+                        to: Some(3),
+                    },
+                    PermissionChange::MethodCooldownChanged {
+                        module: "module_a".to_string(),
+                        method: "method_a".to_string(),
+                        from: Some(5),
+                        to: Some(10),
+                    },
+                    PermissionChange::ConstraintsChanged {
+                        module: "module_a".to_string(),
+                        method: "method_b".to_string(),
+                        from: Some(vec![1, 2, 3]),
+                        to: None,
+                    },
+                    PermissionChange::MethodAdded {
+                        module: "module_a".to_string(),
+                        method: "method_c".to_string(),
+                    },
+                    PermissionChange::ModuleRemoved {
+                        module: "module_b".to_string(),
+                    },
+                    PermissionChange::ModuleAdded {
+                        module: "module_c".to_string(),
+                    },
+                    PermissionChange::ContractRemoved {
+                        contract: [0_u8; 32],
+                    },
+                    PermissionChange::ContractCooldownChanged {
+                        contract: [0x12_u8; 32],
+                        from: None,
+                        to: Some(7),
+                    },
+                ],
+            }
+        );
+        assert_eq!(
+            a.diff(&b).to_string(),
+            [
+                "~ module module_a block cooldown: none -> 3",
+                "~ method module_a::method_a block cooldown: 5 -> 10",
+                "~ method module_a::method_b constraints: 3 bytes -> none",
+                "+ method module_a::method_c",
+                "- module module_b",
+                "+ module module_c",
+                "- contract 0x0000000000000000000000000000000000000000000000000000000000000000",
+                "~ contract 0x1212121212121212121212121212121212121212121212121212121212121212 block cooldown: none -> 7",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_lists_contract_call_changes() {
+        let a = make_cennznut(
+            vec![],
+            vec![Contract::new(&[0x12_u8; 32]).constraints(vec![1, 2])],
+        );
+        let b = make_cennznut(
+            vec![],
+            vec![Contract::new(&[0x12_u8; 32]).selectors(vec![[0xde, 0xad, 0xbe, 0xef]])],
+        );
+
+        assert_eq!(
+            a.diff(&b).changes,
+            vec![
+                PermissionChange::ContractSelectorsChanged {
+                    contract: [0x12_u8; 32],
+                    from: None,
+                    to: Some(vec![[0xde, 0xad, 0xbe, 0xef]]),
+                },
+                PermissionChange::ContractConstraintsChanged {
+                    contract: [0x12_u8; 32],
+                    from: Some(vec![1, 2]),
+                    to: None,
+                },
+            ]
+        );
+        let address = "12".repeat(32);
+        assert_eq!(
+            b.diff(&a).to_string(),
+            format!(
+                "~ contract 0x{} selectors: [0xdeadbeef] -> any\n~ contract 0x{} constraints: none -> 2 bytes",
+                address, address
+            )
+        );
+    }
+
+    #[test]
+    fn it_lists_validity_changes() {
+        let a = make_cennznut(
+            vec![make_module("module_a", vec![Method::new("method_a")]).valid_from(5)],
+            vec![],
+        );
+        let b = make_cennznut(
+            vec![make_module(
+                "module_a",
+                vec![Method::new("method_a").valid_from(1).valid_until(9)],
+            )],
+            vec![],
+        );
+
+        assert_eq!(
+            a.diff(&b).to_string(),
+            [
+                "~ module module_a validity: 5.. -> any",
+                "~ method module_a::method_a validity: any -> 1..=9",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_lists_usage_quota_changes() {
+        let a = make_cennznut(
+            vec![make_module(
+                "module_a",
+                vec![Method::new("method_a").max_uses(3)],
+            )],
+            vec![],
+        );
+        let b = make_cennznut(
+            vec![make_module("module_a", vec![Method::new("method_a")])],
+            vec![],
+        );
+
+        assert_eq!(
+            a.diff(&b).to_string(),
+            "~ method module_a::method_a max uses: 3 -> unlimited"
+        );
+    }
+
+    #[test]
+    fn it_lists_rate_limit_changes() {
+        let a = make_cennznut(vec![], vec![Contract::wildcard()]);
+        let b = make_cennznut(vec![], vec![Contract::wildcard().rate_limit(20, 100)]);
+
+        assert_eq!(
+            a.diff(&b).to_string(),
+            format!(
+                "~ contract 0x{} rate limit: none -> 20 per 100 blocks",
+                "00".repeat(32)
+            )
+        );
+    }
+
+    #[test]
+    fn it_lists_new_denials() {
+        let a = make_cennznut(
+            vec![make_module("module_a", vec![Method::new("method_a")])],
+            vec![],
+        );
+        let b = make_cennznut(
+            vec![
+                make_module(
+                    "module_a",
+                    vec![Method::new("method_a"), Method::new("method_b").deny()],
+                ),
+                Module::new("module_b").deny(),
+            ],
+            vec![Contract::wildcard().deny()],
+        );
+
+        assert_eq!(
+            a.diff(&b).changes,
+            vec![
+                PermissionChange::MethodDenied {
+                    module: "module_a".to_string(),
+                    method: "method_b".to_string(),
+                },
+                PermissionChange::ModuleDenied {
+                    module: "module_b".to_string(),
+                },
+                PermissionChange::ContractDenied {
+                    contract: [0_u8; 32],
+                },
+            ]
+        );
+        assert_eq!(
+            b.diff(&a).to_string(),
+            [
+                "- deny method module_a::method_b",
+                "- deny module module_b",
+                &format!("- deny contract 0x{}", "00".repeat(32)),
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_lists_denials_of_granted_names() {
+        let a = make_cennznut(
+            vec![make_module("module_a", vec![Method::new("method_a")])],
+            vec![Contract::new(&[0x12_u8; 32])],
+        );
+        let b = make_cennznut(
+            vec![
+                make_module(
+                    "module_a",
+                    vec![Method::new("method_a"), Method::new("method_a").deny()],
+                ),
+                Module::new("module_a").deny(),
+            ],
+            vec![
+                Contract::new(&[0x12_u8; 32]),
+                Contract::new(&[0x12_u8; 32]).deny(),
+            ],
+        );
+
+        assert_eq!(
+            a.diff(&b).changes,
+            vec![
+                PermissionChange::MethodDenied {
+                    module: "module_a".to_string(),
+                    method: "method_a".to_string(),
+                },
+                PermissionChange::ModuleDenied {
+                    module: "module_a".to_string(),
+                },
+                PermissionChange::ContractDenied {
+                    contract: [0x12_u8; 32],
+                },
+            ]
+        );
+        assert_eq!(
+            b.diff(&a).to_string(),
+            [
+                "- deny method module_a::method_a".to_string(),
+                "- deny module module_a".to_string(),
+                format!("- deny contract 0x{}", "12".repeat(32)),
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_lists_grants_flipped_to_denials() {
+        let a = make_cennznut(
+            vec![make_module("module_a", vec![Method::new("method_a")])],
+            vec![Contract::new(&[0x12_u8; 32])],
+        );
+        let b = make_cennznut(
+            vec![make_module(
+                "module_a",
+                vec![Method::new("method_a").deny()],
+            )],
+            vec![Contract::new(&[0x12_u8; 32]).deny()],
+        );
+
+        assert_eq!(
+            a.diff(&b).changes,
+            vec![
+                PermissionChange::MethodRemoved {
+                    module: "module_a".to_string(),
+                    method: "method_a".to_string(),
+                },
+                PermissionChange::MethodDenied {
+                    module: "module_a".to_string(),
+                    method: "method_a".to_string(),
+                },
+                PermissionChange::ContractRemoved {
+                    contract: [0x12_u8; 32],
+                },
+                PermissionChange::ContractDenied {
+                    contract: [0x12_u8; 32],
+                },
+            ]
+        );
+        let address = "12".repeat(32);
+        assert_eq!(
+            b.diff(&a).to_string(),
+            [
+                "+ method module_a::method_a".to_string(),
+                "- deny method module_a::method_a".to_string(),
+                format!("+ contract 0x{}", address),
+                format!("- deny contract 0x{}", address),
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_diffs_across_versions() {
+        let method = MethodV0::new("method_test");
+        let module = ModuleV0::new("module_test").methods(vec![method].into());
+        let a = CENNZnut::V0(CENNZnutV0 {
+            modules: vec![module].into(),
+            contracts: vec![].into(),
+        });
+        let b = a.clone().upgrade_to_latest();
+
+        assert!(CENNZnut::diff(&a, &b).is_empty());
+        assert_eq!(
+            CENNZnut::diff(&a, &CENNZnut::V1(make_cennznut(vec![], vec![]))).changes,
+            vec![PermissionChange::ModuleRemoved {
+                module: "module_test".to_string()
+            }]
+        );
+    }
+}
@@ -0,0 +1,562 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V1 Method
+//!
+//! Delegated method permissioning of CENNZnut for use in CENNZnet
+//!
+
+use super::encode_bytes;
+use crate::cennznut::v0::method::Method as MethodV0;
+use crate::cennznut::MethodName;
+use crate::decode::{DecodeField, DecodeInput, DecodePath};
+use crate::downgrade::{is_nul_padded, DowngradeError};
+use crate::pattern::is_valid_pattern;
+use crate::{Constraint, DecodeError, EncodeError, RateLimit};
+use alloc::string::String;
+use alloc::vec::Vec;
+use codec::{Decode, Encode, Input, Output};
+use core::convert::TryFrom;
+use pact::contract::Contract as PactContract;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+const BLOCK_COOLDOWN_MASK: u8 = 0x01;
+const CONSTRAINTS_MASK: u8 = 0x02;
+const PATTERN_MASK: u8 = 0x04;
+const DENY_MASK: u8 = 0x08;
+const VALID_FROM_MASK: u8 = 0x10;
+const VALID_UNTIL_MASK: u8 = 0x20;
+const MAX_USES_MASK: u8 = 0x40;
+const RATE_LIMIT_MASK: u8 = 0x80;
+
+/// A CENNZnet permission domain module method
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct Method {
+    pub name: MethodName,
+    /// Whether the name is a pattern, its `*` matching any sequence of characters
+    #[cfg_attr(feature = "std", serde(default))]
+    pub pattern: bool,
+    /// Whether the entry denies the methods it applies to, over any grant
+    #[cfg_attr(feature = "std", serde(default))]
+    pub deny: bool,
+    pub block_cooldown: Option<u32>,
+    /// The first block the entry may be used at
+    pub valid_from: Option<u32>,
+    /// The last block the entry may be used at
+    pub valid_until: Option<u32>,
+    /// The number of times the entry may be used
+    pub max_uses: Option<u32>,
+    /// The most calls the entry may be used for within a window of blocks
+    pub rate_limit: Option<RateLimit>,
+    pub constraints: Option<Vec<u8>>,
+}
+
+impl Method {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            pattern: false,
+            deny: false,
+            block_cooldown: None,
+            valid_from: None,
+            valid_until: None,
+            max_uses: None,
+            rate_limit: None,
+            constraints: None,
+        }
+    }
+
+    /// Makes the name a pattern, its `*` matching any sequence of characters
+    pub fn pattern(mut self) -> Self {
+        self.pattern = true;
+        self
+    }
+
+    /// Makes the entry deny the methods it applies to
+    pub fn deny(mut self) -> Self {
+        self.deny = true;
+        self
+    }
+
+    pub fn block_cooldown(mut self, block_cooldown: u32) -> Self {
+        self.block_cooldown = Some(block_cooldown);
+        self
+    }
+
+    pub fn valid_from(mut self, valid_from: u32) -> Self {
+        self.valid_from = Some(valid_from);
+        self
+    }
+
+    pub fn valid_until(mut self, valid_until: u32) -> Self {
+        self.valid_until = Some(valid_until);
+        self
+    }
+
+    pub fn max_uses(mut self, max_uses: u32) -> Self {
+        self.max_uses = Some(max_uses);
+        self
+    }
+
+    pub fn rate_limit(mut self, calls: u32, blocks: u32) -> Self {
+        self.rate_limit = Some(RateLimit::new(calls, blocks));
+        self
+    }
+
+    pub fn constraints(mut self, constraints: Vec<u8>) -> Self {
+        self.constraints = Some(constraints);
+        self
+    }
+
+    /// Encodes `constraint` as the Method constraints
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the constraint has too many clauses to encode
+    pub fn constraint(mut self, constraint: &Constraint) -> Result<Self, EncodeError> {
+        let constraints = constraint.try_encode()?;
+        self.constraints = Some(constraints);
+        Ok(self)
+    }
+
+    /// Returns the Pact contract, if it exists in the Method
+    pub fn get_pact(&self) -> Option<PactContract> {
+        match &self.constraints {
+            Some(constraints) => match PactContract::decode(constraints) {
+                Ok(pact_contract) => Some(pact_contract),
+                // This error case can only occur after initializing a Method with bad constraints.
+                // A decoded Method will be checked during decoding.
+                Err(_) => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Encodes the Method
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the name is an invalid pattern, the rate limit window is empty,
+    /// or the name or constraints are longer than a `Compact<u32>` length
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        if self.pattern && !is_valid_pattern(&self.name) {
+            return Err(EncodeError::InvalidMethodPattern {
+                module: 0,
+                method: 0,
+            });
+        }
+        if matches!(self.rate_limit, Some(RateLimit { blocks: 0, .. })) {
+            return Err(EncodeError::EmptyMethodRateLimitWindow {
+                module: 0,
+                method: 0,
+            });
+        }
+        let constraints = match &self.constraints {
+            Some(constraints) if !constraints.is_empty() => Some(constraints),
+            _ => None,
+        };
+
+        let mut buf = Vec::<u8>::default();
+        let has_cooldown_byte: u8 = if self.block_cooldown.is_some() {
+            BLOCK_COOLDOWN_MASK
+        } else {
+            0
+        };
+        let has_constraints_byte: u8 = if constraints.is_some() {
+            CONSTRAINTS_MASK
+        } else {
+            0
+        };
+        let is_pattern_byte: u8 = if self.pattern { PATTERN_MASK } else { 0 };
+        let is_deny_byte: u8 = if self.deny { DENY_MASK } else { 0 };
+        let has_valid_from_byte: u8 = if self.valid_from.is_some() {
+            VALID_FROM_MASK
+        } else {
+            0
+        };
+        let has_valid_until_byte: u8 = if self.valid_until.is_some() {
+            VALID_UNTIL_MASK
+        } else {
+            0
+        };
+        let has_max_uses_byte: u8 = if self.max_uses.is_some() {
+            MAX_USES_MASK
+        } else {
+            0
+        };
+        let has_rate_limit_byte: u8 = if self.rate_limit.is_some() {
+            RATE_LIMIT_MASK
+        } else {
+            0
+        };
+        buf.push_byte(
+            has_cooldown_byte
+                | has_constraints_byte
+                | is_pattern_byte
+                | is_deny_byte
+                | has_valid_from_byte
+                | has_valid_until_byte
+                | has_max_uses_byte
+                | has_rate_limit_byte,
+        );
+
+        encode_bytes(self.name.as_bytes(), &mut buf)?;
+
+        if let Some(cooldown) = self.block_cooldown {
+            buf.write(&cooldown.to_le_bytes());
+        }
+        if let Some(valid_from) = self.valid_from {
+            buf.write(&valid_from.to_le_bytes());
+        }
+        if let Some(valid_until) = self.valid_until {
+            buf.write(&valid_until.to_le_bytes());
+        }
+        if let Some(max_uses) = self.max_uses {
+            buf.write(&max_uses.to_le_bytes());
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            buf.write(&rate_limit.calls.to_le_bytes());
+            buf.write(&rate_limit.blocks.to_le_bytes());
+        }
+
+        if let Some(constraints) = constraints {
+            encode_bytes(constraints, &mut buf)?;
+        }
+
+        Ok(buf)
+    }
+
+    /// Decodes a Method, reporting the offset and field of any failure
+    pub(crate) fn decode_detailed<I: Input>(
+        input: &mut DecodeInput<I>,
+    ) -> Result<Self, DecodeError> {
+        let header_offset = input.offset();
+        let block_cooldown_and_constraints = input.read_byte(DecodeField::MethodHeader)?;
+        input.check_header(
+            block_cooldown_and_constraints,
+            BLOCK_COOLDOWN_MASK
+                | CONSTRAINTS_MASK
+                | PATTERN_MASK
+                | DENY_MASK
+                | VALID_FROM_MASK
+                | VALID_UNTIL_MASK
+                | MAX_USES_MASK
+                | RATE_LIMIT_MASK,
+            header_offset,
+            DecodeField::MethodHeader,
+        )?;
+
+        let name_offset = input.offset();
+        let name_buf = input.read_bytes(DecodeField::MethodName)?;
+        input.check_name(&name_buf, name_offset, DecodeField::MethodName)?;
+        let name = String::from_utf8(name_buf).map_err(|_| DecodeError::InvalidUtf8Name {
+            offset: name_offset,
+            path: DecodePath::default(),
+            field: DecodeField::MethodName,
+        })?;
+        let pattern = (block_cooldown_and_constraints & PATTERN_MASK) == PATTERN_MASK;
+        if pattern && !is_valid_pattern(&name) {
+            return Err(DecodeError::InvalidPattern {
+                offset: name_offset,
+                path: DecodePath::default(),
+                field: DecodeField::MethodName,
+            });
+        }
+
+        let deny = (block_cooldown_and_constraints & DENY_MASK) == DENY_MASK;
+
+        let block_cooldown: Option<u32> =
+            if (block_cooldown_and_constraints & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK {
+                Some(input.read_u32(DecodeField::MethodCooldown)?)
+            } else {
+                None
+            };
+        let valid_from = if (block_cooldown_and_constraints & VALID_FROM_MASK) == VALID_FROM_MASK {
+            Some(input.read_u32(DecodeField::MethodValidFrom)?)
+        } else {
+            None
+        };
+        let valid_until = if (block_cooldown_and_constraints & VALID_UNTIL_MASK) == VALID_UNTIL_MASK
+        {
+            Some(input.read_u32(DecodeField::MethodValidUntil)?)
+        } else {
+            None
+        };
+        let max_uses = if (block_cooldown_and_constraints & MAX_USES_MASK) == MAX_USES_MASK {
+            Some(input.read_u32(DecodeField::MethodMaxUses)?)
+        } else {
+            None
+        };
+        let rate_limit = if (block_cooldown_and_constraints & RATE_LIMIT_MASK) == RATE_LIMIT_MASK {
+            Some(input.read_rate_limit(DecodeField::MethodRateLimit)?)
+        } else {
+            None
+        };
+
+        let constraints: Option<Vec<u8>> =
+            if (block_cooldown_and_constraints & CONSTRAINTS_MASK) == CONSTRAINTS_MASK {
+                let constraints_offset = input.offset();
+                let constraints_buf = input.read_bytes(DecodeField::Constraints)?;
+                if input.is_strict() && constraints_buf.is_empty() {
+                    return Err(DecodeError::EmptyConstraints {
+                        offset: constraints_offset,
+                        path: DecodePath::default(),
+                    });
+                }
+                if PactContract::decode(&constraints_buf).is_err() {
+                    return Err(DecodeError::InvalidConstraints {
+                        offset: constraints_offset,
+                        path: DecodePath::default(),
+                    });
+                };
+                Some(constraints_buf)
+            } else {
+                None
+            };
+
+        Ok(Self {
+            name,
+            pattern,
+            deny,
+            block_cooldown,
+            valid_from,
+            valid_until,
+            max_uses,
+            rate_limit,
+            constraints,
+        })
+    }
+}
+
+impl Encode for Method {
+    fn encode_to<T: Output>(&self, buf: &mut T) {
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
+        }
+    }
+}
+
+impl Decode for Method {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::decode_detailed(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
+impl From<MethodV0> for Method {
+    fn from(method: MethodV0) -> Self {
+        Self {
+            name: method.name,
+            pattern: false,
+            deny: false,
+            block_cooldown: method.block_cooldown,
+            valid_from: None,
+            valid_until: None,
+            max_uses: None,
+            rate_limit: None,
+            constraints: method.constraints,
+        }
+    }
+}
+
+impl TryFrom<Method> for MethodV0 {
+    type Error = DowngradeError;
+    fn try_from(method: Method) -> Result<Self, Self::Error> {
+        if method.pattern {
+            return Err(DowngradeError::MethodPattern {
+                module: 0,
+                method: 0,
+            });
+        }
+        if method.deny {
+            return Err(DowngradeError::MethodDeny {
+                module: 0,
+                method: 0,
+            });
+        }
+        if method.valid_from.is_some() || method.valid_until.is_some() {
+            return Err(DowngradeError::MethodValidity {
+                module: 0,
+                method: 0,
+            });
+        }
+        if method.max_uses.is_some() {
+            return Err(DowngradeError::MethodMaxUses {
+                module: 0,
+                method: 0,
+            });
+        }
+        if method.rate_limit.is_some() {
+            return Err(DowngradeError::MethodRateLimit {
+                module: 0,
+                method: 0,
+            });
+        }
+        if is_nul_padded(&method.name) {
+            return Err(DowngradeError::MethodNameNulPadded {
+                module: 0,
+                method: 0,
+            });
+        }
+        let downgraded = Self {
+            name: method.name,
+            block_cooldown: method.block_cooldown,
+            constraints: method.constraints,
+        };
+        downgraded.try_encode()?;
+        Ok(downgraded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Method, BLOCK_COOLDOWN_MASK, CONSTRAINTS_MASK};
+    use crate::decode::DecodeInput;
+    use crate::{DecodeError, DecodeField, DecodePath};
+    use codec::{Decode, Encode};
+    use std::assert_eq;
+
+    // Encoding Tests
+    #[test]
+    fn it_encodes() {
+        let method = Method::new("TestMethod");
+
+        let expected: Vec<u8> = [vec![0_u8, 10 << 2], b"TestMethod".to_vec()].concat();
+
+        assert_eq!(method.encode(), expected);
+    }
+
+    #[test]
+    fn it_encodes_names_longer_than_32_bytes() {
+        let name =
+            "I am Sam, I am Sam, Sam I am That Sam I am, That Sam I am I do not like that Sam I am";
+        let method = Method::new(name);
+
+        let expected: Vec<u8> = [
+            vec![0_u8],
+            vec![0b0101_0101, 0b0000_0001], // compact 85
+            name.as_bytes().to_vec(),
+        ]
+        .concat();
+
+        assert_eq!(name.len(), 85);
+        assert_eq!(method.encode(), expected);
+    }
+
+    #[test]
+    fn it_encodes_with_block_cooldown() {
+        let method = Method::new("TestMethod").block_cooldown(0x08040201);
+
+        let expected: Vec<u8> = [
+            vec![BLOCK_COOLDOWN_MASK, 10 << 2],
+            b"TestMethod".to_vec(),
+            vec![0x01, 0x02, 0x04, 0x08],
+        ]
+        .concat();
+
+        assert_eq!(method.encode(), expected);
+    }
+
+    #[test]
+    fn it_encodes_more_than_256_constraints_bytes() {
+        let method = Method::new("TestMethod").constraints(vec![0x55; 300]);
+
+        let expected: Vec<u8> = [
+            vec![CONSTRAINTS_MASK, 10 << 2],
+            b"TestMethod".to_vec(),
+            vec![0b1011_0001, 0b0000_0100], // compact 300
+            vec![0x55; 300],
+        ]
+        .concat();
+
+        assert_eq!(method.encode(), expected);
+    }
+
+    #[test]
+    fn it_does_not_encode_constraints_with_0_length() {
+        let method = Method::new("TestMethod").constraints(vec![]);
+
+        let expected: Vec<u8> = [vec![0_u8, 10 << 2], b"TestMethod".to_vec()].concat();
+
+        assert_eq!(method.encode(), expected);
+    }
+
+    // Decoding Tests
+    #[test]
+    fn it_decodes() {
+        let method = Method::new("TestMethod")
+            .block_cooldown(0x08040201)
+            .constraints(vec![0x00; 300]);
+
+        assert_eq!(Method::decode(&mut &method.encode()[..]), Ok(method));
+    }
+
+    #[test]
+    fn it_keeps_trailing_nul_bytes_in_names() {
+        let method = Method::new("TestMethod\0");
+
+        assert_eq!(
+            Method::decode(&mut &method.encode()[..]).unwrap().name,
+            "TestMethod\0"
+        );
+    }
+
+    #[test]
+    fn decode_fails_with_junk_bytes_in_the_name() {
+        let encoded: Vec<u8> = [vec![0_u8, 2 << 2], vec![0xf0, 0xf0]].concat();
+
+        assert_eq!(
+            Method::decode_detailed(&mut DecodeInput::new(&mut &encoded[..])),
+            Err(DecodeError::InvalidUtf8Name {
+                offset: 1,
+                path: DecodePath::default(),
+                field: DecodeField::MethodName,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_fails_with_non_canonical_name_length() {
+        let encoded: Vec<u8> = [vec![0_u8, 0b0010_1001, 0], b"TestMethod".to_vec()].concat();
+
+        assert_eq!(
+            Method::decode_detailed(&mut DecodeInput::new(&mut &encoded[..])),
+            Err(DecodeError::InvalidCompact {
+                offset: 1,
+                path: DecodePath::default(),
+                field: DecodeField::MethodName,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_fails_with_insufficient_bytes_for_name() {
+        let encoded: Vec<u8> = [vec![0_u8, 11 << 2], b"TestMethod".to_vec()].concat();
+
+        assert_eq!(
+            Method::decode_detailed(&mut DecodeInput::new(&mut &encoded[..])),
+            Err(DecodeError::Truncated {
+                offset: 2,
+                path: DecodePath::default(),
+                field: DecodeField::MethodName,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_fails_with_invalid_pact() {
+        let encoded: Vec<u8> = [
+            vec![CONSTRAINTS_MASK, 10 << 2],
+            b"TestMethod".to_vec(),
+            vec![33 << 2],
+            vec![0xff; 33],
+        ]
+        .concat();
+
+        assert_eq!(
+            Method::decode(&mut &encoded[..]),
+            Err(codec::Error::from("invalid constraints codec"))
+        );
+    }
+}
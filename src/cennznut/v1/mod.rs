@@ -0,0 +1,966 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V1
+//!
+//! Version 1 CENNZnut type.
+//! Names and constraints are `Compact<u32>` length prefixed and
+//! module, method and contract counts are `Compact<u32>` encoded.
+//!
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+mod attenuation;
+mod diff;
+mod trace;
+
+use alloc::vec::Vec;
+use codec::{Compact, Decode, Encode, Input, Output};
+use core::convert::TryFrom;
+use pact::interpreter::types::PactType;
+
+pub mod contract;
+pub mod method;
+pub mod module;
+pub mod set;
+
+use super::v0::contract::Contract as ContractV0;
+use super::v0::set::{ContractSet as ContractSetV0, ModuleSet as ModuleSetV0};
+use super::v0::{module::Module as ModuleV0, CENNZnutV0};
+use super::{ContractDomain, RuntimeDomain};
+use crate::cooldown::cooldown_elapsed;
+use crate::decode::{DecodeField, DecodeInput};
+use crate::downgrade::DowngradeError;
+use crate::pattern::{key_matches, resolve};
+use crate::rate_limit::within_rate_limit;
+use crate::trace::ConstraintsOutcome;
+use crate::usage::{remaining_uses, GrantPath, NutHash, UsageCounter};
+use crate::validation::{
+    check_unlimited, check_unmetered, check_validity, interpret_constraints,
+    interpret_contract_constraints, interpret_module_constraints,
+};
+use crate::{CallHistory, CooldownTracker, DecodeError, EncodeError, PartialDecode, ValidationErr};
+use contract::Contract;
+use method::Method;
+use module::Module;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use set::{ContractSet, ModuleSet};
+use trace::{constraints_outcome, ValidationObserver};
+
+use super::{ContractAddress, ContractSelector, CONTRACT_WILDCARD};
+
+pub const VERSION_BYTES: [u8; 2] = [1, 0];
+pub const MAX_CENNZNUT_BYTES: usize = u16::max_value() as usize;
+
+/// A CENNZnet permission domain struct for embedding in doughnuts
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct CENNZnutV1 {
+    pub modules: ModuleSet,
+    pub contracts: ContractSet,
+}
+
+impl CENNZnutV1 {
+    /// Returns the module, if it is granted by the CENNZnut
+    /// Defined modules have priority over pattern modules, which have priority over wildcard
+    /// modules
+    pub fn get_module(&self, module: &str) -> Option<&Module> {
+        self.get_module_entry(module).map(|(_, m)| m)
+    }
+
+    /// Returns the key and module, if it is granted by the CENNZnut
+    pub(crate) fn get_module_entry(&self, module: &str) -> Option<(&str, &Module)> {
+        resolve(
+            self.modules
+                .iter()
+                .filter(|(_, m)| !m.deny)
+                .map(|(name, m)| (name.as_str(), m.pattern, m)),
+            module,
+        )
+    }
+
+    /// Returns whether any deny entry of the CENNZnut applies to the module
+    pub fn is_module_denied(&self, module: &str) -> bool {
+        self.modules
+            .iter()
+            .any(|(name, m)| m.deny && key_matches(name, m.pattern, module))
+    }
+
+    /// Returns every module entry of the CENNZnut applying to the module, including deny entries
+    pub(crate) fn matching_modules<'a>(
+        &'a self,
+        module: &'a str,
+    ) -> impl Iterator<Item = &'a Module> + Clone + 'a {
+        self.modules
+            .iter()
+            .filter(move |(name, m)| key_matches(name, m.pattern, module))
+            .map(|(_, m)| m)
+    }
+
+    /// Returns whether any deny entry of the CENNZnut applies to the method.
+    /// Method deny entries of every module entry applying to the module are consulted, not only
+    /// those of the module entry which grants it.
+    pub fn is_method_denied(&self, module: &str, method: &str) -> bool {
+        self.matching_modules(module)
+            .any(|m| m.is_method_denied(method))
+    }
+
+    /// Returns the contract, if it is granted by the CENNZnut
+    /// Wildcard contracts (addr: 0) have lower priority than defined contracts
+    pub fn get_contract(&self, contract: ContractAddress) -> Option<&Contract> {
+        let mut outcome: Option<&Contract> = None;
+        for (address, c) in self.contracts.iter().filter(|(_, c)| !c.deny) {
+            if address == &contract {
+                outcome = Some(c);
+                break;
+            } else if address == &CONTRACT_WILDCARD {
+                outcome = Some(c);
+            }
+        }
+        outcome
+    }
+
+    /// Returns whether any deny entry of the CENNZnut applies to the contract
+    pub fn is_contract_denied(&self, contract: ContractAddress) -> bool {
+        self.contracts
+            .iter()
+            .any(|(address, c)| c.deny && (address == &contract || address == &CONTRACT_WILDCARD))
+    }
+
+    /// Encodes the CENNZnut, failing if it exceeds any encoding limits
+    ///
+    /// # Errors
+    ///
+    /// Will return error identifying the first module, method or contract
+    /// which cannot be encoded, or if the encoded CENNZnut is too large
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::<u8>::default();
+
+        buf.write(&VERSION_BYTES);
+
+        encode_count(self.modules.len(), &mut buf)
+            .map_err(|_| EncodeError::TooManyModules(self.modules.len()))?;
+        for (index, (_, module)) in self.modules.iter().enumerate() {
+            let module_buf = module.try_encode().map_err(|e| e.in_module(index))?;
+            buf.write(&module_buf);
+        }
+
+        encode_count(self.contracts.len(), &mut buf)
+            .map_err(|_| EncodeError::TooManyContracts(self.contracts.len()))?;
+        for (index, (_, contract)) in self.contracts.iter().enumerate() {
+            buf.write(&contract.try_encode().map_err(|e| e.in_contract(index))?);
+        }
+
+        // Avoid writing outside of the allocated domain buffer
+        if buf.len() > MAX_CENNZNUT_BYTES {
+            return Err(EncodeError::TooLarge(buf.len()));
+        }
+
+        Ok(buf)
+    }
+
+    /// Decodes a `CENNZnutV1` without the version, reporting the offset and path of any failure
+    pub(crate) fn partial_decode_detailed<I: Input>(
+        input: &mut DecodeInput<I>,
+    ) -> Result<Self, DecodeError> {
+        let module_count = input.read_compact_u32(DecodeField::ModuleCount)?;
+        let mut modules = ModuleSet::new();
+
+        for index in 0..module_count as usize {
+            let offset = input.offset();
+            let m = Module::decode_detailed(input).map_err(|e| e.in_module(index))?;
+            input
+                .check_order(
+                    modules.iter().next_back().map(|(key, _)| key),
+                    &m.name,
+                    offset,
+                    DecodeField::ModuleName,
+                )
+                .map_err(|e| e.in_module(index))?;
+            modules.push(m);
+        }
+
+        let contract_count = input.read_compact_u32(DecodeField::ContractCount)?;
+        let mut contracts = ContractSet::new();
+
+        for index in 0..contract_count as usize {
+            let offset = input.offset();
+            let c = Contract::decode_detailed(input).map_err(|e| e.in_contract(index))?;
+            input
+                .check_order(
+                    contracts.iter().next_back().map(|(key, _)| key),
+                    &c.address,
+                    offset,
+                    DecodeField::ContractAddress,
+                )
+                .map_err(|e| e.in_contract(index))?;
+            contracts.push(c);
+        }
+
+        Ok(Self { modules, contracts })
+    }
+
+    /// Returns the uses remaining of the `grant` usage quota, as counted by the `counter` for
+    /// the CENNZnut `nut_hash`, or `None` if the grant does not exist or is unlimited
+    pub fn remaining_uses<U: UsageCounter>(
+        &self,
+        nut_hash: &NutHash,
+        counter: &U,
+        grant: &GrantPath,
+    ) -> Option<u32> {
+        let max_uses = match grant {
+            GrantPath::Method { module, method } => self
+                .modules
+                .iter()
+                .find(|(name, m)| *name == module && !m.deny)
+                .and_then(|(_, m)| {
+                    m.methods
+                        .iter()
+                        .find(|(name, m)| *name == method && !m.deny)
+                })
+                .and_then(|(_, m)| m.max_uses),
+            GrantPath::Contract { address } => self
+                .contracts
+                .iter()
+                .find(|(a, c)| *a == address && !c.deny)
+                .and_then(|(_, c)| c.max_uses),
+        };
+        remaining_uses(max_uses, counter.uses(nut_hash, grant))
+    }
+}
+
+/// Returns the path of the grant of `method` within `module`
+fn method_grant(module: &Module, method: &Method) -> GrantPath {
+    GrantPath::Method {
+        module: module.name.clone(),
+        method: method.name.clone(),
+    }
+}
+
+/// Returns the path of the grant of `contract`
+fn contract_grant(contract: &Contract) -> GrantPath {
+    GrantPath::Contract {
+        address: contract.address,
+    }
+}
+
+/// Checks neither `module` nor `method` is bounded by a rate limit, nor `method` by a usage
+/// quota, for a call of `module_name::method_name` which is not counted against them
+fn check_untracked_method(
+    module_name: &str,
+    method_name: &str,
+    module: &Module,
+    method: &Method,
+) -> Result<(), ValidationErr<RuntimeDomain>> {
+    check_unlimited(module.rate_limit, || RuntimeDomain::Module {
+        module: module_name.into(),
+    })?;
+    let domain = || RuntimeDomain::Method {
+        module: module_name.into(),
+        method: method_name.into(),
+    };
+    check_unlimited(method.rate_limit, domain)?;
+    check_unmetered(method.max_uses, domain)
+}
+
+/// Checks `contract` is bounded by neither a rate limit nor a usage quota, for a call of
+/// `contract_address` which is not counted against them
+fn check_untracked_contract(
+    contract_address: ContractAddress,
+    contract: &Contract,
+) -> Result<(), ValidationErr<ContractDomain>> {
+    let domain = || ContractDomain::Contract {
+        address: contract_address,
+    };
+    check_unlimited(contract.rate_limit, domain)?;
+    check_unmetered(contract.max_uses, domain)
+}
+
+/// Encodes `count` as a `Compact<u32>`
+pub(crate) fn encode_count(count: usize, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+    let count = u32::try_from(count).map_err(|_| EncodeError::CountOverflow(count))?;
+    Compact(count).encode_to(buf);
+    Ok(())
+}
+
+/// Encodes `bytes` with a `Compact<u32>` length prefix
+pub(crate) fn encode_bytes(bytes: &[u8], buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+    encode_count(bytes.len(), buf)?;
+    buf.write(bytes);
+    Ok(())
+}
+
+impl Encode for CENNZnutV1 {
+    fn encode_to<T: Output>(&self, buf: &mut T) {
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
+        }
+    }
+}
+
+impl PartialDecode for CENNZnutV1 {
+    fn partial_decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::partial_decode_detailed(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
+impl Decode for CENNZnutV1 {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let version = u16::from_le_bytes([input.read_byte()?, input.read_byte()?]);
+        if version != 1 {
+            return Err(codec::Error::from("expected version : 1"));
+        }
+        Self::partial_decode(input)
+    }
+}
+
+impl From<CENNZnutV0> for CENNZnutV1 {
+    fn from(cennznut: CENNZnutV0) -> Self {
+        Self {
+            modules: cennznut.modules.into_iter().map(Module::from).collect(),
+            contracts: cennznut.contracts.into_iter().map(Contract::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<CENNZnutV1> for CENNZnutV0 {
+    type Error = DowngradeError;
+    fn try_from(cennznut: CENNZnutV1) -> Result<Self, Self::Error> {
+        let mut modules = ModuleSetV0::new();
+        for (index, module) in cennznut.modules.into_iter().enumerate() {
+            modules.push(ModuleV0::try_from(module).map_err(|e| e.in_module(index))?);
+        }
+        let mut contracts = ContractSetV0::new();
+        for (index, contract) in cennznut.contracts.into_iter().enumerate() {
+            contracts.push(ContractV0::try_from(contract).map_err(|e| e.in_contract(index))?);
+        }
+        let downgraded = Self { modules, contracts };
+        downgraded.try_encode()?;
+        Ok(downgraded)
+    }
+}
+
+impl CENNZnutV1 {
+    /// Validates a CENNZnut runtime module by:
+    /// (1) looking for `module_name` and `method_name`, denied by any applicable deny entry
+    /// (2) executing the Pact interpreter if module constraints exist, then if method
+    /// constraints exist
+    /// A module or method bounded by a validity window is only permitted through
+    /// `validate_module_at`, bounded by a rate limit only through
+    /// `validate_module_rate_limited`, and a method bounded by a usage quota only through
+    /// `validate_module_metered`.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_module(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        self.get_untracked_method(None, module_name, method_name, args, &mut ())?;
+        Ok(())
+    }
+
+    /// Validates a CENNZnut runtime module at `block_number` by:
+    /// (1) looking for `module_name` and `method_name`, denied by any applicable deny entry
+    /// (2) checking `block_number` is within the module and method validity windows
+    /// (3) executing the Pact interpreter if module constraints exist, then if method
+    /// constraints exist
+    /// (4) checking the module and method block cooldowns against the `tracker` history
+    /// (5) recording the call in the `tracker`
+    /// A module or method bounded by a rate limit is only permitted through
+    /// `validate_module_rate_limited`, and a method bounded by a usage quota only through
+    /// `validate_module_metered`.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_module_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        let (module, method) =
+            self.get_cooled_down_method(block_number, tracker, module_name, method_name, args)?;
+        check_untracked_method(module_name, method_name, module, method)?;
+        tracker.record_module_call(module_name, block_number);
+        tracker.record_method_call(module_name, method_name, block_number);
+        Ok(())
+    }
+
+    /// Validates a CENNZnut runtime module at `block_number` as `validate_module_at`, and by
+    /// checking the module and method rate limits against the calls in the `history`
+    /// before recording the call in it.
+    /// A method bounded by a usage quota is only permitted through `validate_module_metered`.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    pub fn validate_module_rate_limited<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        let (module, method) =
+            self.get_rate_limited_method(block_number, history, module_name, method_name, args)?;
+        check_unmetered(method.max_uses, || RuntimeDomain::Method {
+            module: module_name.into(),
+            method: method_name.into(),
+        })?;
+        history.record_module_call(module_name, block_number);
+        history.record_method_call(module_name, method_name, block_number);
+        history.record_grant_call(&method_grant(module, method), block_number);
+        Ok(())
+    }
+
+    /// Validates a CENNZnut runtime module at `block_number` as `validate_module_rate_limited`,
+    /// and by
+    /// (6) checking the method usage quota against the `counter` uses of the grant by the
+    /// CENNZnut `nut_hash`
+    /// (7) recording the use in the `counter`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `RuntimeDomain`
+    #[allow(clippy::too_many_arguments)]
+    pub fn validate_module_metered<H: CallHistory, U: UsageCounter>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        nut_hash: &NutHash,
+        counter: &mut U,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<RuntimeDomain>> {
+        let (module, method) =
+            self.get_rate_limited_method(block_number, history, module_name, method_name, args)?;
+        let grant = method_grant(module, method);
+        if remaining_uses(method.max_uses, counter.uses(nut_hash, &grant)) == Some(0) {
+            return Err(ValidationErr::QuotaExhausted(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            }));
+        }
+        history.record_module_call(module_name, block_number);
+        history.record_method_call(module_name, method_name, block_number);
+        history.record_grant_call(&grant, block_number);
+        if method.max_uses.is_some() {
+            counter.record_use(nut_hash, &grant);
+        }
+        Ok(())
+    }
+
+    /// Validates a CENNZnut smart contract by
+    /// (1) looking for `contract_address`, denied by any applicable deny entry
+    /// A contract restricting the selectors or arguments of calls is only permitted through
+    /// `validate_contract_with`, a contract bounded by a validity window only through
+    /// `validate_contract_at`, by a rate limit only through `validate_contract_rate_limited`,
+    /// and by a usage quota only through `validate_contract_metered`.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract(
+        &self,
+        contract_address: ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        let contract = self.get_called_contract(None, contract_address, None)?;
+        check_untracked_contract(contract_address, contract)?;
+        Ok(())
+    }
+
+    /// Validates a CENNZnut smart contract call of the message `selector` with `args` by
+    /// (1) looking for `contract_address`, denied by any applicable deny entry
+    /// (2) checking `selector` is in the contract selectors, if they exist
+    /// (3) executing the Pact interpreter if constraints exist
+    /// A contract bounded by a validity window is only permitted through
+    /// `validate_contract_at_with`, by a rate limit only through
+    /// `validate_contract_rate_limited_with`, and by a usage quota only through
+    /// `validate_contract_metered_with`.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_with(
+        &self,
+        contract_address: ContractAddress,
+        selector: &ContractSelector,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        let contract = self.get_called_contract(None, contract_address, Some((selector, args)))?;
+        check_untracked_contract(contract_address, contract)?;
+        Ok(())
+    }
+
+    /// Validates a CENNZnut smart contract at `block_number` by
+    /// (1) looking for `contract_address`, denied by any applicable deny entry, as
+    /// `validate_contract`
+    /// (2) checking `block_number` is within the contract validity window
+    /// (3) checking the contract block cooldown against the `tracker` history
+    /// (4) recording the call in the `tracker`
+    /// A contract bounded by a rate limit is only permitted through
+    /// `validate_contract_rate_limited`, and by a usage quota only through
+    /// `validate_contract_metered`.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        contract_address: ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        self.validate_contract_call_at(block_number, tracker, contract_address, None)
+    }
+
+    /// Validates a CENNZnut smart contract call of the message `selector` with `args` at
+    /// `block_number` as `validate_contract_at`, checking the selector and constraints as
+    /// `validate_contract_with`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_at_with<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        contract_address: ContractAddress,
+        selector: &ContractSelector,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        self.validate_contract_call_at(
+            block_number,
+            tracker,
+            contract_address,
+            Some((selector, args)),
+        )
+    }
+
+    /// Validates a CENNZnut smart contract at `block_number` as `validate_contract_at`, and by
+    /// checking the contract rate limit against the calls in the `history` before recording
+    /// the call in it.
+    /// A contract bounded by a usage quota is only permitted through `validate_contract_metered`.
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_rate_limited<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        contract_address: ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        self.validate_contract_call_rate_limited(block_number, history, contract_address, None)
+    }
+
+    /// Validates a CENNZnut smart contract call of the message `selector` with `args` at
+    /// `block_number` as `validate_contract_rate_limited`, checking the selector and
+    /// constraints as `validate_contract_with`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_rate_limited_with<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        contract_address: ContractAddress,
+        selector: &ContractSelector,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        self.validate_contract_call_rate_limited(
+            block_number,
+            history,
+            contract_address,
+            Some((selector, args)),
+        )
+    }
+
+    /// Validates a CENNZnut smart contract at `block_number` as
+    /// `validate_contract_rate_limited`, and by
+    /// (5) checking the contract usage quota against the `counter` uses of the grant by the
+    /// CENNZnut `nut_hash`
+    /// (6) recording the use in the `counter`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    pub fn validate_contract_metered<H: CallHistory, U: UsageCounter>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        nut_hash: &NutHash,
+        counter: &mut U,
+        contract_address: ContractAddress,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        self.validate_contract_call_metered(
+            block_number,
+            history,
+            nut_hash,
+            counter,
+            contract_address,
+            None,
+        )
+    }
+
+    /// Validates a CENNZnut smart contract call of the message `selector` with `args` at
+    /// `block_number` as `validate_contract_metered`, checking the selector and constraints
+    /// as `validate_contract_with`
+    ///
+    /// # Errors
+    ///
+    /// Will return error if validation fails with the type of error embedded in `ContractDomain`
+    #[allow(clippy::too_many_arguments)]
+    pub fn validate_contract_metered_with<H: CallHistory, U: UsageCounter>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        nut_hash: &NutHash,
+        counter: &mut U,
+        contract_address: ContractAddress,
+        selector: &ContractSelector,
+        args: &[PactType],
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        self.validate_contract_call_metered(
+            block_number,
+            history,
+            nut_hash,
+            counter,
+            contract_address,
+            Some((selector, args)),
+        )
+    }
+
+    /// Validates a CENNZnut smart contract `call` at `block_number` as `validate_contract_at`
+    fn validate_contract_call_at<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &mut T,
+        contract_address: ContractAddress,
+        call: Option<(&ContractSelector, &[PactType])>,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        let contract =
+            self.get_cooled_down_contract(block_number, tracker, contract_address, call)?;
+        check_untracked_contract(contract_address, contract)?;
+        tracker.record_contract_call(&contract_address, block_number);
+        Ok(())
+    }
+
+    /// Validates a CENNZnut smart contract `call` at `block_number` as
+    /// `validate_contract_rate_limited`
+    fn validate_contract_call_rate_limited<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        contract_address: ContractAddress,
+        call: Option<(&ContractSelector, &[PactType])>,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        let contract =
+            self.get_rate_limited_contract(block_number, history, contract_address, call)?;
+        check_unmetered(contract.max_uses, || ContractDomain::Contract {
+            address: contract_address,
+        })?;
+        history.record_contract_call(&contract_address, block_number);
+        history.record_grant_call(&contract_grant(contract), block_number);
+        Ok(())
+    }
+
+    /// Validates a CENNZnut smart contract `call` at `block_number` as
+    /// `validate_contract_metered`
+    fn validate_contract_call_metered<H: CallHistory, U: UsageCounter>(
+        &self,
+        block_number: u32,
+        history: &mut H,
+        nut_hash: &NutHash,
+        counter: &mut U,
+        contract_address: ContractAddress,
+        call: Option<(&ContractSelector, &[PactType])>,
+    ) -> Result<(), ValidationErr<ContractDomain>> {
+        let contract =
+            self.get_rate_limited_contract(block_number, history, contract_address, call)?;
+        let grant = contract_grant(contract);
+        if remaining_uses(contract.max_uses, counter.uses(nut_hash, &grant)) == Some(0) {
+            return Err(ValidationErr::QuotaExhausted(ContractDomain::Contract {
+                address: contract_address,
+            }));
+        }
+        history.record_contract_call(&contract_address, block_number);
+        history.record_grant_call(&grant, block_number);
+        if contract.max_uses.is_some() {
+            counter.record_use(nut_hash, &grant);
+        }
+        Ok(())
+    }
+
+    /// Returns the module and method permitting a call with `args` at `block_number`,
+    /// once their block cooldowns have elapsed and within their rate limits
+    fn get_rate_limited_method<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &H,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(&Module, &Method), ValidationErr<RuntimeDomain>> {
+        let (module, method) =
+            self.get_cooled_down_method(block_number, history, module_name, method_name, args)?;
+        if !within_rate_limit(
+            module.rate_limit,
+            |since| history.module_grant_calls_since(&module.name, since),
+            block_number,
+        ) {
+            return Err(ValidationErr::RateLimitExceeded(RuntimeDomain::Module {
+                module: module_name.into(),
+            }));
+        }
+        if !within_rate_limit(
+            method.rate_limit,
+            |since| history.grant_calls_since(&method_grant(module, method), since),
+            block_number,
+        ) {
+            return Err(ValidationErr::RateLimitExceeded(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            }));
+        }
+        Ok((module, method))
+    }
+
+    /// Returns the module and method permitting a call with `args` at `block_number`,
+    /// once their block cooldowns have elapsed
+    fn get_cooled_down_method<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &T,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(&Module, &Method), ValidationErr<RuntimeDomain>> {
+        let (module, method) =
+            self.get_permitted_method(Some(block_number), module_name, method_name, args)?;
+        if !cooldown_elapsed(
+            module.block_cooldown,
+            tracker.last_module_call(module_name),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Module {
+                module: module_name.into(),
+            }));
+        }
+        if !cooldown_elapsed(
+            method.block_cooldown,
+            tracker.last_method_call(module_name, method_name),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            }));
+        }
+        Ok((module, method))
+    }
+
+    /// Returns the contract permitting the `call` at `block_number`, once its block cooldown
+    /// has elapsed
+    fn get_cooled_down_contract<T: CooldownTracker>(
+        &self,
+        block_number: u32,
+        tracker: &T,
+        contract_address: ContractAddress,
+        call: Option<(&ContractSelector, &[PactType])>,
+    ) -> Result<&Contract, ValidationErr<ContractDomain>> {
+        let contract = self.get_called_contract(Some(block_number), contract_address, call)?;
+        if !cooldown_elapsed(
+            contract.block_cooldown,
+            tracker.last_contract_call(&contract_address),
+            block_number,
+        ) {
+            return Err(ValidationErr::CooldownNotElapsed(
+                ContractDomain::Contract {
+                    address: contract_address,
+                },
+            ));
+        }
+        Ok(contract)
+    }
+
+    /// Returns the module and method permitting a call with `args` at `block_number`
+    fn get_permitted_method(
+        &self,
+        block_number: Option<u32>,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> Result<(&Module, &Method), ValidationErr<RuntimeDomain>> {
+        self.get_observed_method(block_number, module_name, method_name, args, &mut ())
+    }
+
+    /// Returns the module and method permitting a call with `args` at `block_number` as
+    /// `get_observed_method`, where calls and uses are not counted against rate limits and
+    /// usage quotas
+    pub(crate) fn get_untracked_method<O: ValidationObserver>(
+        &self,
+        block_number: Option<u32>,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+        observer: &mut O,
+    ) -> Result<(&Module, &Method), ValidationErr<RuntimeDomain>> {
+        let (module, method) =
+            self.get_observed_method(block_number, module_name, method_name, args, observer)?;
+        check_untracked_method(module_name, method_name, module, method)?;
+        Ok((module, method))
+    }
+
+    /// Returns the module and method permitting a call with `args` at `block_number`,
+    /// reporting each step reached to the `observer`
+    pub(crate) fn get_observed_method<O: ValidationObserver>(
+        &self,
+        block_number: Option<u32>,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+        observer: &mut O,
+    ) -> Result<(&Module, &Method), ValidationErr<RuntimeDomain>> {
+        if self.is_module_denied(module_name) {
+            return Err(ValidationErr::Denied(RuntimeDomain::Module {
+                module: module_name.into(),
+            }));
+        }
+        let (module_key, module) = self.get_module_entry(module_name).ok_or_else(|| {
+            ValidationErr::NoPermission(RuntimeDomain::Module {
+                module: module_name.into(),
+            })
+        })?;
+        observer.module_matched(module_name, module_key, module);
+        check_validity(module.valid_from, module.valid_until, block_number, || {
+            RuntimeDomain::Module {
+                module: module_name.into(),
+            }
+        })?;
+        if self.is_method_denied(module_name, method_name) {
+            return Err(ValidationErr::Denied(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            }));
+        }
+        let (method_key, method) = module.get_method_entry(method_name).ok_or_else(|| {
+            ValidationErr::NoPermission(RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            })
+        })?;
+        observer.method_matched(method_name, method_key, method);
+        check_validity(method.valid_from, method.valid_until, block_number, || {
+            RuntimeDomain::Method {
+                module: module_name.into(),
+                method: method_name.into(),
+            }
+        })?;
+        if let Some(pact) = module.get_pact() {
+            let result = interpret_module_constraints(&pact, args, module_name, method_name);
+            observer.module_constraints(|| constraints_outcome(&pact, args, &result));
+            result?;
+        }
+        match method.get_pact() {
+            Some(pact) => {
+                let result = interpret_constraints(&pact, args, module_name, method_name);
+                observer.method_constraints(|| constraints_outcome(&pact, args, &result));
+                result?;
+            }
+            None => observer.method_constraints(|| ConstraintsOutcome::Unconstrained),
+        }
+        Ok((module, method))
+    }
+
+    /// Returns the contract permitting a call at `block_number`
+    fn get_permitted_contract(
+        &self,
+        block_number: Option<u32>,
+        contract_address: ContractAddress,
+    ) -> Result<&Contract, ValidationErr<ContractDomain>> {
+        let domain = || ContractDomain::Contract {
+            address: contract_address,
+        };
+        if self.is_contract_denied(contract_address) {
+            return Err(ValidationErr::Denied(domain()));
+        }
+        let contract = self
+            .get_contract(contract_address)
+            .ok_or_else(|| ValidationErr::NoPermission(domain()))?;
+        check_validity(
+            contract.valid_from,
+            contract.valid_until,
+            block_number,
+            domain,
+        )?;
+        Ok(contract)
+    }
+
+    /// Returns the contract permitting the `call` of a message selector with arguments at
+    /// `block_number`, or permitting any call, without restricted selectors or arguments, if
+    /// `call` is `None`
+    fn get_called_contract(
+        &self,
+        block_number: Option<u32>,
+        contract_address: ContractAddress,
+        call: Option<(&ContractSelector, &[PactType])>,
+    ) -> Result<&Contract, ValidationErr<ContractDomain>> {
+        let contract = self.get_permitted_contract(block_number, contract_address)?;
+        match call {
+            Some((selector, args)) => {
+                if !contract.permits_selector(selector) {
+                    return Err(ValidationErr::NoPermission(ContractDomain::Selector {
+                        address: contract_address,
+                        selector: *selector,
+                    }));
+                }
+                if let Some(pact) = contract.get_pact() {
+                    interpret_contract_constraints(&pact, args, contract_address, *selector)?;
+                }
+            }
+            None if contract.restricts_calls() => {
+                return Err(ValidationErr::NoPermission(ContractDomain::Contract {
+                    address: contract_address,
+                }));
+            }
+            None => {}
+        }
+        Ok(contract)
+    }
+
+    /// Returns the contract permitting the `call` at `block_number`, once its block cooldown
+    /// has elapsed and within its rate limit
+    fn get_rate_limited_contract<H: CallHistory>(
+        &self,
+        block_number: u32,
+        history: &H,
+        contract_address: ContractAddress,
+        call: Option<(&ContractSelector, &[PactType])>,
+    ) -> Result<&Contract, ValidationErr<ContractDomain>> {
+        let contract =
+            self.get_cooled_down_contract(block_number, history, contract_address, call)?;
+        if !within_rate_limit(
+            contract.rate_limit,
+            |since| history.grant_calls_since(&contract_grant(contract), since),
+            block_number,
+        ) {
+            return Err(ValidationErr::RateLimitExceeded(ContractDomain::Contract {
+                address: contract_address,
+            }));
+        }
+        Ok(contract)
+    }
+}
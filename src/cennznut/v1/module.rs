@@ -0,0 +1,506 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V1 Module
+//!
+//! Delegated runtime module permissioning of CENNZnut for use in CENNZnet
+//!
+
+use super::method::Method;
+use super::set::MethodSet;
+use super::{encode_bytes, encode_count};
+use crate::cennznut::v0::{
+    method::Method as MethodV0, module::Module as ModuleV0, set::MethodSet as MethodSetV0,
+};
+use crate::cennznut::ModuleName;
+use crate::decode::{DecodeField, DecodeInput, DecodePath};
+use crate::downgrade::{is_nul_padded, DowngradeError};
+use crate::pattern::{is_valid_pattern, key_matches, resolve};
+use crate::{Constraint, DecodeError, EncodeError, RateLimit};
+#[cfg(feature = "std")]
+use ::serde::{Deserialize, Serialize};
+use alloc::string::String;
+use alloc::vec::Vec;
+use codec::{Decode, Encode, Input, Output};
+use core::convert::TryFrom;
+use pact::contract::Contract as PactContract;
+const BLOCK_COOLDOWN_MASK: u8 = 0b0000_0001;
+const PATTERN_MASK: u8 = 0b0000_0010;
+const DENY_MASK: u8 = 0b0000_0100;
+const VALID_FROM_MASK: u8 = 0b0000_1000;
+const VALID_UNTIL_MASK: u8 = 0b0001_0000;
+const RATE_LIMIT_MASK: u8 = 0b0010_0000;
+const CONSTRAINTS_MASK: u8 = 0b0100_0000;
+
+/// A CENNZnet permission domain module
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct Module {
+    pub name: ModuleName,
+    /// Whether the name is a pattern, its `*` matching any sequence of characters
+    #[cfg_attr(feature = "std", serde(default))]
+    pub pattern: bool,
+    /// Whether the entry denies the modules it applies to, over any grant
+    #[cfg_attr(feature = "std", serde(default))]
+    pub deny: bool,
+    pub block_cooldown: Option<u32>,
+    /// The first block the entry may be used at
+    pub valid_from: Option<u32>,
+    /// The last block the entry may be used at
+    pub valid_until: Option<u32>,
+    /// The most calls the entry may be used for within a window of blocks
+    pub rate_limit: Option<RateLimit>,
+    /// Pact constraints over the arguments of every method call, checked before the method's own
+    pub constraints: Option<Vec<u8>>,
+    pub methods: MethodSet,
+}
+
+impl Module {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            pattern: false,
+            deny: false,
+            block_cooldown: None,
+            valid_from: None,
+            valid_until: None,
+            rate_limit: None,
+            constraints: None,
+            methods: MethodSet::new(),
+        }
+    }
+
+    /// Makes the name a pattern, its `*` matching any sequence of characters
+    pub fn pattern(mut self) -> Self {
+        self.pattern = true;
+        self
+    }
+
+    /// Makes the entry deny the modules it applies to, its methods are ignored
+    pub fn deny(mut self) -> Self {
+        self.deny = true;
+        self
+    }
+
+    pub fn block_cooldown(mut self, block_cooldown: u32) -> Self {
+        self.block_cooldown = Some(block_cooldown);
+        self
+    }
+
+    pub fn valid_from(mut self, valid_from: u32) -> Self {
+        self.valid_from = Some(valid_from);
+        self
+    }
+
+    pub fn valid_until(mut self, valid_until: u32) -> Self {
+        self.valid_until = Some(valid_until);
+        self
+    }
+
+    pub fn rate_limit(mut self, calls: u32, blocks: u32) -> Self {
+        self.rate_limit = Some(RateLimit::new(calls, blocks));
+        self
+    }
+
+    pub fn constraints(mut self, constraints: Vec<u8>) -> Self {
+        self.constraints = Some(constraints);
+        self
+    }
+
+    /// Encodes `constraint` as the Module constraints
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the constraint has too many clauses to encode
+    pub fn constraint(mut self, constraint: &Constraint) -> Result<Self, EncodeError> {
+        let constraints = constraint.try_encode()?;
+        self.constraints = Some(constraints);
+        Ok(self)
+    }
+
+    /// Returns the Pact contract, if it exists in the Module
+    pub fn get_pact(&self) -> Option<PactContract> {
+        match &self.constraints {
+            // A decoded Module has its constraints checked during decoding
+            Some(constraints) => PactContract::decode(constraints).ok(),
+            None => None,
+        }
+    }
+
+    pub fn methods(mut self, methods: MethodSet) -> Self {
+        self.methods = methods;
+        self
+    }
+
+    /// Returns the method, if it is granted by the Module
+    /// Defined methods have priority over pattern methods, which have priority over wildcard
+    /// methods
+    pub fn get_method(&self, method: &str) -> Option<&Method> {
+        self.get_method_entry(method).map(|(_, m)| m)
+    }
+
+    /// Returns the key and method, if it is granted by the Module
+    pub(crate) fn get_method_entry(&self, method: &str) -> Option<(&str, &Method)> {
+        resolve(
+            self.methods
+                .iter()
+                .filter(|(_, m)| !m.deny)
+                .map(|(name, m)| (name.as_str(), m.pattern, m)),
+            method,
+        )
+    }
+
+    /// Returns whether any deny entry of the Module applies to the method
+    pub fn is_method_denied(&self, method: &str) -> bool {
+        self.methods
+            .iter()
+            .any(|(name, m)| m.deny && key_matches(name, m.pattern, method))
+    }
+
+    /// Encodes the Module
+    ///
+    /// # Errors
+    ///
+    /// Will return error if the name is an invalid pattern, the rate limit window is empty,
+    /// or identifying the first method which cannot be encoded
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        if self.pattern && !is_valid_pattern(&self.name) {
+            return Err(EncodeError::InvalidModulePattern { module: 0 });
+        }
+        if matches!(self.rate_limit, Some(RateLimit { blocks: 0, .. })) {
+            return Err(EncodeError::EmptyModuleRateLimitWindow { module: 0 });
+        }
+        let constraints = match &self.constraints {
+            Some(constraints) if !constraints.is_empty() => Some(constraints),
+            _ => None,
+        };
+
+        let mut buf = Vec::<u8>::default();
+        let has_cooldown_byte: u8 = if self.block_cooldown.is_some() {
+            BLOCK_COOLDOWN_MASK
+        } else {
+            0
+        };
+        let is_pattern_byte: u8 = if self.pattern { PATTERN_MASK } else { 0 };
+        let is_deny_byte: u8 = if self.deny { DENY_MASK } else { 0 };
+        let has_valid_from_byte: u8 = if self.valid_from.is_some() {
+            VALID_FROM_MASK
+        } else {
+            0
+        };
+        let has_valid_until_byte: u8 = if self.valid_until.is_some() {
+            VALID_UNTIL_MASK
+        } else {
+            0
+        };
+        let has_rate_limit_byte: u8 = if self.rate_limit.is_some() {
+            RATE_LIMIT_MASK
+        } else {
+            0
+        };
+        let has_constraints_byte: u8 = if constraints.is_some() {
+            CONSTRAINTS_MASK
+        } else {
+            0
+        };
+        buf.push_byte(
+            has_cooldown_byte
+                | is_pattern_byte
+                | is_deny_byte
+                | has_valid_from_byte
+                | has_valid_until_byte
+                | has_rate_limit_byte
+                | has_constraints_byte,
+        );
+
+        encode_bytes(self.name.as_bytes(), &mut buf)?;
+
+        if let Some(cooldown) = self.block_cooldown {
+            buf.write(&cooldown.to_le_bytes());
+        }
+        if let Some(valid_from) = self.valid_from {
+            buf.write(&valid_from.to_le_bytes());
+        }
+        if let Some(valid_until) = self.valid_until {
+            buf.write(&valid_until.to_le_bytes());
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            buf.write(&rate_limit.calls.to_le_bytes());
+            buf.write(&rate_limit.blocks.to_le_bytes());
+        }
+        if let Some(constraints) = constraints {
+            encode_bytes(constraints, &mut buf)?;
+        }
+
+        encode_count(self.methods.len(), &mut buf).map_err(|_| EncodeError::TooManyMethods {
+            module: 0,
+            count: self.methods.len(),
+        })?;
+        for (index, (_, method)) in self.methods.iter().enumerate() {
+            let method_buf = method.try_encode().map_err(|e| e.in_method(index))?;
+            buf.write(&method_buf);
+        }
+
+        Ok(buf)
+    }
+
+    /// Decodes a Module, reporting the offset and field of any failure
+    pub(crate) fn decode_detailed<I: Input>(
+        input: &mut DecodeInput<I>,
+    ) -> Result<Self, DecodeError> {
+        let header_offset = input.offset();
+        let has_cooldown_byte = input.read_byte(DecodeField::ModuleHeader)?;
+        input.check_header(
+            has_cooldown_byte,
+            BLOCK_COOLDOWN_MASK
+                | PATTERN_MASK
+                | DENY_MASK
+                | VALID_FROM_MASK
+                | VALID_UNTIL_MASK
+                | RATE_LIMIT_MASK
+                | CONSTRAINTS_MASK,
+            header_offset,
+            DecodeField::ModuleHeader,
+        )?;
+
+        let name_offset = input.offset();
+        let name_buf = input.read_bytes(DecodeField::ModuleName)?;
+        input.check_name(&name_buf, name_offset, DecodeField::ModuleName)?;
+        let name = String::from_utf8(name_buf).map_err(|_| DecodeError::InvalidUtf8Name {
+            offset: name_offset,
+            path: DecodePath::default(),
+            field: DecodeField::ModuleName,
+        })?;
+        let pattern = (has_cooldown_byte & PATTERN_MASK) == PATTERN_MASK;
+        if pattern && !is_valid_pattern(&name) {
+            return Err(DecodeError::InvalidPattern {
+                offset: name_offset,
+                path: DecodePath::default(),
+                field: DecodeField::ModuleName,
+            });
+        }
+
+        let deny = (has_cooldown_byte & DENY_MASK) == DENY_MASK;
+
+        let block_cooldown = if (has_cooldown_byte & BLOCK_COOLDOWN_MASK) == BLOCK_COOLDOWN_MASK {
+            Some(input.read_u32(DecodeField::ModuleCooldown)?)
+        } else {
+            None
+        };
+        let valid_from = if (has_cooldown_byte & VALID_FROM_MASK) == VALID_FROM_MASK {
+            Some(input.read_u32(DecodeField::ModuleValidFrom)?)
+        } else {
+            None
+        };
+        let valid_until = if (has_cooldown_byte & VALID_UNTIL_MASK) == VALID_UNTIL_MASK {
+            Some(input.read_u32(DecodeField::ModuleValidUntil)?)
+        } else {
+            None
+        };
+        let rate_limit = if (has_cooldown_byte & RATE_LIMIT_MASK) == RATE_LIMIT_MASK {
+            Some(input.read_rate_limit(DecodeField::ModuleRateLimit)?)
+        } else {
+            None
+        };
+        let constraints = if (has_cooldown_byte & CONSTRAINTS_MASK) == CONSTRAINTS_MASK {
+            let constraints_offset = input.offset();
+            let constraints_buf = input.read_bytes(DecodeField::Constraints)?;
+            if input.is_strict() && constraints_buf.is_empty() {
+                return Err(DecodeError::EmptyConstraints {
+                    offset: constraints_offset,
+                    path: DecodePath::default(),
+                });
+            }
+            if PactContract::decode(&constraints_buf).is_err() {
+                return Err(DecodeError::InvalidConstraints {
+                    offset: constraints_offset,
+                    path: DecodePath::default(),
+                });
+            }
+            Some(constraints_buf)
+        } else {
+            None
+        };
+
+        let method_count = input.read_compact_u32(DecodeField::MethodCount)?;
+        let mut methods = MethodSet::new();
+
+        for index in 0..method_count as usize {
+            let offset = input.offset();
+            let m = Method::decode_detailed(input).map_err(|e| e.in_method(index))?;
+            input
+                .check_order(
+                    methods.iter().next_back().map(|(key, _)| key),
+                    &m.name,
+                    offset,
+                    DecodeField::MethodName,
+                )
+                .map_err(|e| e.in_method(index))?;
+            methods.push(m);
+        }
+
+        Ok(Self {
+            name,
+            pattern,
+            deny,
+            block_cooldown,
+            valid_from,
+            valid_until,
+            rate_limit,
+            constraints,
+            methods,
+        })
+    }
+}
+
+impl Encode for Module {
+    fn encode_to<T: Output>(&self, buf: &mut T) {
+        if let Ok(encoded) = self.try_encode() {
+            buf.write(&encoded);
+        }
+    }
+}
+
+impl Decode for Module {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::decode_detailed(&mut DecodeInput::new(input)).map_err(Into::into)
+    }
+}
+
+impl From<ModuleV0> for Module {
+    fn from(module: ModuleV0) -> Self {
+        Self {
+            name: module.name,
+            pattern: false,
+            deny: false,
+            block_cooldown: module.block_cooldown,
+            valid_from: None,
+            valid_until: None,
+            rate_limit: None,
+            constraints: None,
+            methods: module.methods.into_iter().map(Method::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<Module> for ModuleV0 {
+    type Error = DowngradeError;
+    fn try_from(module: Module) -> Result<Self, Self::Error> {
+        if module.pattern {
+            return Err(DowngradeError::ModulePattern { module: 0 });
+        }
+        if module.deny {
+            return Err(DowngradeError::ModuleDeny { module: 0 });
+        }
+        if module.valid_from.is_some() || module.valid_until.is_some() {
+            return Err(DowngradeError::ModuleValidity { module: 0 });
+        }
+        if module.rate_limit.is_some() {
+            return Err(DowngradeError::ModuleRateLimit { module: 0 });
+        }
+        if module
+            .constraints
+            .as_ref()
+            .map_or(false, |constraints| !constraints.is_empty())
+        {
+            return Err(DowngradeError::ModuleConstraints { module: 0 });
+        }
+        if is_nul_padded(&module.name) {
+            return Err(DowngradeError::ModuleNameNulPadded { module: 0 });
+        }
+        let mut methods = MethodSetV0::new();
+        for (index, method) in module.methods.into_iter().enumerate() {
+            methods.push(MethodV0::try_from(method).map_err(|e| e.in_method(index))?);
+        }
+        let downgraded = Self {
+            name: module.name,
+            block_cooldown: module.block_cooldown,
+            methods,
+        };
+        downgraded.try_encode()?;
+        Ok(downgraded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Method, Module, BLOCK_COOLDOWN_MASK};
+    use codec::{Decode, Encode};
+    use std::assert_eq;
+
+    macro_rules! methods {
+        ($($name:expr),*) => {
+            vec![
+                $( Method::new($name), )*
+            ].into()
+        }
+    }
+
+    // Encoding Tests
+    #[test]
+    fn it_encodes() {
+        let module = Module::new("TestModule").methods(methods!("TestMethod"));
+
+        let expected: Vec<u8> = [
+            vec![0_u8, 10 << 2],
+            b"TestModule".to_vec(),
+            vec![1 << 2],
+            Method::new("TestMethod").encode(),
+        ]
+        .concat();
+
+        assert_eq!(module.encode(), expected);
+    }
+
+    #[test]
+    fn it_encodes_without_methods() {
+        let module = Module::new("TestModule");
+
+        let expected: Vec<u8> = [vec![0_u8, 10 << 2], b"TestModule".to_vec(), vec![0]].concat();
+
+        assert_eq!(module.encode(), expected);
+    }
+
+    #[test]
+    fn it_encodes_with_block_cooldown() {
+        let module = Module::new("TestModule")
+            .methods(methods!("TestMethod"))
+            .block_cooldown(0x10204080);
+
+        let expected: Vec<u8> = [
+            vec![BLOCK_COOLDOWN_MASK, 10 << 2],
+            b"TestModule".to_vec(),
+            vec![0x80, 0x40, 0x20, 0x10],
+            vec![1 << 2],
+            Method::new("TestMethod").encode(),
+        ]
+        .concat();
+
+        assert_eq!(module.encode(), expected);
+    }
+
+    // Decoding Tests
+    #[test]
+    fn it_decodes_with_many_methods() {
+        let module = Module::new("I don't like green eggs and ham, I don't like you Sam I am")
+            .block_cooldown(0x10204080)
+            .methods(methods!("I", "do", "not", "like", "them", "Sam", "I am"));
+
+        assert_eq!(Module::decode(&mut &module.encode()[..]), Ok(module));
+    }
+
+    #[test]
+    fn decode_fails_with_insufficient_bytes_for_block_cooldown() {
+        let encoded: Vec<u8> = [
+            vec![BLOCK_COOLDOWN_MASK, 10 << 2],
+            b"TestModule".to_vec(),
+            vec![0x01, 0x02],
+        ]
+        .concat();
+
+        assert_eq!(
+            Module::decode(&mut &encoded[..]),
+            Err(codec::Error::from("Not enough data to fill buffer"))
+        );
+    }
+}
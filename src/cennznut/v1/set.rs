@@ -0,0 +1,40 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V1 Sets
+//!
+//! Permission domain entries keyed by their own name or address
+//!
+
+use super::contract::Contract;
+use super::method::Method;
+use super::module::Module;
+use crate::cennznut::v0::set::{DomainSet, Keyed};
+use crate::cennznut::{ContractAddress, MethodName, ModuleName};
+
+impl Keyed for Module {
+    type Key = ModuleName;
+    fn key(&self) -> &ModuleName {
+        &self.name
+    }
+}
+
+impl Keyed for Method {
+    type Key = MethodName;
+    fn key(&self) -> &MethodName {
+        &self.name
+    }
+}
+
+impl Keyed for Contract {
+    type Key = ContractAddress;
+    fn key(&self) -> &ContractAddress {
+        &self.address
+    }
+}
+
+/// The modules of a `CENNZnutV1`, keyed by `Module::name`
+pub type ModuleSet = DomainSet<Module>;
+/// The methods of a `Module`, keyed by `Method::name`
+pub type MethodSet = DomainSet<Method>;
+/// The contracts of a `CENNZnutV1`, keyed by `Contract::address`
+pub type ContractSet = DomainSet<Contract>;
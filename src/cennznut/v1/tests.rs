@@ -0,0 +1,1859 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! CENNZnut - V1 Integration Tests
+//!
+
+#![cfg(test)]
+
+use super::contract::Contract;
+use super::encode_count;
+use super::method::Method;
+use super::module::Module;
+use super::set::{ContractSet, MethodSet, ModuleSet};
+use crate::cennznut::v0::{
+    contract::Contract as ContractV0, method::Method as MethodV0, module::Module as ModuleV0,
+    tests::make_pact,
+};
+use crate::cennznut::{v1::MAX_CENNZNUT_BYTES, ContractDomain, RuntimeDomain, CONTRACT_WILDCARD};
+use crate::{
+    CENNZnut, CENNZnutV0, CENNZnutV1, Constraint, CooldownTracker, DecodeError, DecodeField,
+    DecodePath, DowngradeError, EncodeError, GrantPath, InMemoryCallHistory,
+    InMemoryCooldownTracker, InMemoryUsageCounter, RateLimit, TryFrom, ValidationErr,
+};
+
+use codec::{Decode, Encode};
+use pact::interpreter::OpCode;
+use pact::types::{Numeric, PactType, StringLike};
+use std::vec::Vec;
+
+fn make_methods(method: &Method) -> MethodSet {
+    let mut methods = MethodSet::new();
+    methods.push(method.clone());
+    methods
+}
+
+fn make_modules(module: &Module) -> ModuleSet {
+    let mut modules = ModuleSet::new();
+    modules.push(module.clone());
+    modules
+}
+
+fn make_contracts(contract: &Contract) -> ContractSet {
+    let mut contracts = ContractSet::new();
+    contracts.push(contract.clone());
+    contracts
+}
+
+pub(crate) fn make_module(name: &str, methods: Vec<Method>) -> Module {
+    Module::new(name).methods(methods.into())
+}
+
+pub(crate) fn make_cennznut(modules: Vec<Module>, contracts: Vec<Contract>) -> CENNZnutV1 {
+    CENNZnutV1 {
+        modules: modules.into(),
+        contracts: contracts.into(),
+    }
+}
+
+#[test]
+fn it_works_encode() {
+    let method = Method::new("method_test");
+    let methods = make_methods(&method);
+
+    let module = Module::new("module_test").methods(methods);
+    let modules = make_modules(&module);
+
+    let contract = Contract::new(&[0x5a_u8; 32]);
+    let contracts = make_contracts(&contract);
+
+    let cennznut = CENNZnutV1 { modules, contracts };
+    let encoded = cennznut.encode();
+
+    let expected_version = vec![1, 0];
+    let expected_modules = [
+        vec![1 << 2, 0, 11 << 2],
+        b"module_test".to_vec(),
+        vec![1 << 2, 0, 11 << 2],
+        b"method_test".to_vec(),
+    ]
+    .concat();
+    let expected_contracts = [vec![1 << 2, 0x00], vec![0x5a_u8; 32]].concat();
+    assert_eq!(
+        encoded,
+        [expected_version, expected_modules, expected_contracts].concat()
+    );
+    assert_eq!(cennznut.try_encode(), Ok(encoded));
+}
+
+#[test]
+fn it_works_encode_without_modules() {
+    let contract = Contract::new(&[0x5a_u8; 32]);
+    let contracts = make_contracts(&contract);
+
+    let cennznut = CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts,
+    };
+
+    assert_eq!(
+        cennznut.try_encode(),
+        Ok([vec![1, 0, 0, 1 << 2, 0x00], vec![0x5a_u8; 32]].concat())
+    );
+}
+
+#[test]
+fn it_works_decode_with_long_names_and_large_constraints() {
+    let name = "a_module_name_which_is_longer_than_32_bytes";
+    let method = Method::new("a_method_name_which_is_longer_than_32_bytes")
+        .block_cooldown(123)
+        .constraints(make_pact(
+            vec![PactType::StringLike(StringLike(&[0x61_u8; 300]))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        ));
+    let methods = make_methods(&method);
+    let module = Module::new(name).block_cooldown(86_400).methods(methods);
+    let modules = make_modules(&module);
+    let contract = Contract::wildcard().block_cooldown(5);
+    let contracts = make_contracts(&contract);
+
+    let cennznut = CENNZnutV1 { modules, contracts };
+    let encoded = cennznut.encode();
+
+    assert_eq!(CENNZnutV1::decode(&mut &encoded[..]), Ok(cennznut));
+}
+
+#[test]
+fn it_fails_decode_with_invalid_version() {
+    let encoded: Vec<u8> = vec![0, 0, 0, 0];
+    assert_eq!(
+        CENNZnutV1::decode(&mut &encoded[..]),
+        Err(codec::Error::from("expected version : 1"))
+    );
+}
+
+#[test]
+fn it_fails_to_encode_when_too_large() {
+    let method = Method::new("method_test").constraints(vec![0; MAX_CENNZNUT_BYTES]);
+    let methods = make_methods(&method);
+    let module = Module::new("module_test").methods(methods);
+    let modules = make_modules(&module);
+
+    let cennznut = CENNZnutV1 {
+        modules,
+        contracts: ContractSet::new(),
+    };
+
+    assert!(matches!(
+        cennznut.try_encode(),
+        Err(EncodeError::TooLarge(_))
+    ));
+    assert_eq!(cennznut.encode(), Vec::<u8>::default());
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn it_fails_to_encode_counts_larger_than_a_compact() {
+    let count = u32::max_value() as usize + 1;
+    assert_eq!(
+        encode_count(count, &mut Vec::new()),
+        Err(EncodeError::CountOverflow(count))
+    );
+}
+
+#[test]
+fn it_decodes_through_cennznut() {
+    let method = Method::new("method_test");
+    let methods = make_methods(&method);
+    let module = Module::new("module_test").methods(methods);
+    let modules = make_modules(&module);
+
+    let cennznut = CENNZnutV1 {
+        modules,
+        contracts: ContractSet::new(),
+    };
+    let encoded = cennznut.encode();
+
+    let decoded = CENNZnut::decode(&mut &encoded[..]).expect("it decodes");
+    assert_eq!(decoded.encode(), encoded);
+    assert_eq!(CENNZnutV1::try_from(decoded), Ok(cennznut));
+}
+
+#[test]
+fn it_reports_invalid_compact_counts() {
+    // A single byte count encoded in the two byte compact mode
+    let encoded: Vec<u8> = vec![1, 0, 0b0000_0101, 0];
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded),
+        Err(DecodeError::InvalidCompact {
+            offset: 2,
+            path: DecodePath::default(),
+            field: DecodeField::ModuleCount,
+        })
+    );
+}
+
+#[test]
+fn it_reports_truncated_method_names() {
+    let encoded: Vec<u8> = [
+        vec![1, 0, 1 << 2, 0, 4 << 2],
+        b"test".to_vec(),
+        vec![1 << 2, 0, 11 << 2],
+        b"method".to_vec(),
+    ]
+    .concat();
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded),
+        Err(DecodeError::Truncated {
+            offset: 12,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(0),
+                contract: None,
+            },
+            field: DecodeField::MethodName,
+        })
+    );
+}
+
+#[test]
+fn it_validates() {
+    let method = Method::new("method_test").constraints(make_pact(
+        vec![PactType::Numeric(Numeric(100))],
+        vec![OpCode::EQ.into(), 0, 0, 1, 0],
+    ));
+    let methods = make_methods(&method);
+    let module = Module::new("module_test").methods(methods);
+    let modules = make_modules(&module);
+    let contract = Contract::new(&[0x12_u8; 32]);
+    let contracts = make_contracts(&contract);
+
+    let cennznut = CENNZnut::V1(CENNZnutV1 { modules, contracts });
+    let args = [PactType::Numeric(Numeric(100))];
+
+    assert_eq!(
+        cennznut.validate_runtime_call(&module.name, &method.name, &args),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call(
+            &module.name,
+            &method.name,
+            &[PactType::Numeric(Numeric(101))]
+        ),
+        Err(ValidationErr::NoPermission(
+            RuntimeDomain::MethodArguments {
+                module: "module_test".into(),
+                method: "method_test".into(),
+            }
+        ))
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call(&module.name, "method_other", &args),
+        Err(ValidationErr::NoPermission(RuntimeDomain::Method {
+            module: "module_test".into(),
+            method: "method_other".into(),
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call("module_other", &method.name, &args),
+        Err(ValidationErr::NoPermission(RuntimeDomain::Module {
+            module: "module_other".into(),
+        }))
+    );
+    assert!(matches!(
+        cennznut.validate_runtime_call(&module.name, &method.name, &[]),
+        Err(ValidationErr::ConstraintsInterpretation(_))
+    ));
+    assert_eq!(cennznut.validate_contract_call(&contract.address), Ok(()));
+    assert_eq!(
+        cennznut.validate_contract_call(&[0x34_u8; 32]),
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x34_u8; 32],
+        }))
+    );
+}
+
+#[test]
+fn it_names_the_denied_call() {
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: ContractSet::new(),
+    });
+
+    assert_eq!(
+        cennznut
+            .validate_runtime_call("balances", "transfer", &[])
+            .unwrap_err()
+            .to_string(),
+        "CENNZnut does not grant permission for module balances"
+    );
+    assert_eq!(
+        cennznut
+            .validate_contract_call(&[0xab_u8; 32])
+            .unwrap_err()
+            .to_string(),
+        format!(
+            "CENNZnut does not grant permission for contract 0x{}",
+            "ab".repeat(32)
+        )
+    );
+}
+
+#[test]
+fn it_validates_cooldowns() {
+    let method = Method::new("method_test");
+    let methods = make_methods(&method);
+    let module = Module::new("module_test")
+        .block_cooldown(5)
+        .methods(methods);
+    let modules = make_modules(&module);
+
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules,
+        contracts: ContractSet::new(),
+    });
+    let mut tracker = InMemoryCooldownTracker::new();
+
+    assert_eq!(
+        cennznut.validate_runtime_call_at(10, &mut tracker, &module.name, &method.name, &[]),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call_at(14, &mut tracker, &module.name, &method.name, &[]),
+        Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Module {
+            module: "module_test".into(),
+        }))
+    );
+}
+
+fn make_v0() -> CENNZnutV0 {
+    let method = MethodV0::new("method_test")
+        .block_cooldown(123)
+        .constraints(make_pact(
+            vec![PactType::Numeric(Numeric(100))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        ));
+    let module = ModuleV0::new("module_test")
+        .block_cooldown(86_400)
+        .methods(vec![method].into());
+    let contract = ContractV0::new(&[0x12_u8; 32]).block_cooldown(5);
+    CENNZnutV0 {
+        modules: vec![module].into(),
+        contracts: vec![contract].into(),
+    }
+}
+
+#[test]
+fn it_upgrades_and_downgrades_losslessly() {
+    let method = Method::new("method_test")
+        .block_cooldown(123)
+        .constraints(make_pact(
+            vec![PactType::Numeric(Numeric(100))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        ));
+    let methods = make_methods(&method);
+    let module = Module::new("module_test")
+        .block_cooldown(86_400)
+        .methods(methods);
+    let modules = make_modules(&module);
+    let contract = Contract::new(&[0x12_u8; 32]).block_cooldown(5);
+    let contracts = make_contracts(&contract);
+    let expected = CENNZnutV1 { modules, contracts };
+
+    let upgraded = CENNZnutV1::from(make_v0());
+    assert_eq!(upgraded, expected);
+    assert_eq!(CENNZnutV0::try_from(upgraded), Ok(make_v0()));
+}
+
+#[test]
+fn it_upgrades_to_latest() {
+    let expected = CENNZnut::V1(CENNZnutV1::from(make_v0()));
+    assert_eq!(CENNZnut::V0(make_v0()).upgrade_to_latest(), expected);
+    assert_eq!(expected.clone().upgrade_to_latest(), expected);
+}
+
+#[test]
+fn it_fails_to_downgrade_long_names() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].name = "a_module_name_which_is_longer_than_32_bytes".to_string();
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::Unencodable(
+            EncodeError::ModuleNameTooLong { module: 0 }
+        ))
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    let method = Method::new("a_method_name_which_is_longer_than_32_bytes");
+    cennznut.modules[0].methods.push(method);
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::Unencodable(
+            EncodeError::MethodNameTooLong {
+                module: 0,
+                method: 1
+            }
+        ))
+    );
+}
+
+#[test]
+fn it_fails_to_downgrade_large_constraints() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].methods[0].constraints = Some(make_pact(
+        vec![PactType::StringLike(StringLike(&[0x61_u8; 300]))],
+        vec![OpCode::EQ.into(), 0, 0, 1, 0],
+    ));
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::Unencodable(
+            EncodeError::ConstraintsTooLarge {
+                module: 0,
+                method: 0
+            }
+        ))
+    );
+}
+
+#[test]
+fn it_fails_to_downgrade_nul_padded_names() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].methods[0].name = "method_test\0".to_string();
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::MethodNameNulPadded {
+            module: 0,
+            method: 0
+        })
+    );
+}
+
+#[test]
+fn it_fails_to_downgrade_without_modules() {
+    let cennznut = CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: ContractSet::new(),
+    };
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::Unencodable(EncodeError::NoModules))
+    );
+}
+
+#[test]
+fn it_gives_defined_methods_precedence_over_patterns() {
+    let module = Module::new("balances").methods(
+        vec![
+            Method::new("*").block_cooldown(1),
+            Method::new("transfer*").pattern().block_cooldown(2),
+            Method::new("transfer_*").pattern().block_cooldown(3),
+            Method::new("transfer_all"),
+            Method::new("*_approve").pattern().block_cooldown(4),
+        ]
+        .into(),
+    );
+
+    let cooldown = |method: &str| module.get_method(method).map(|m| m.block_cooldown);
+    assert_eq!(cooldown("transfer_all"), Some(None));
+    assert_eq!(cooldown("transfer_keep_alive"), Some(Some(3)));
+    assert_eq!(cooldown("transferable"), Some(Some(2)));
+    // Of equally specific patterns the first listed is matched
+    assert_eq!(cooldown("transfer_approve"), Some(Some(3)));
+    assert_eq!(cooldown("force_approve"), Some(Some(4)));
+    assert_eq!(cooldown("burn"), Some(Some(1)));
+    // A literal `*` in a pattern only matches through the pattern
+    assert_eq!(cooldown("transfer*"), Some(Some(2)));
+}
+
+#[test]
+fn it_gives_defined_modules_precedence_over_patterns() {
+    let module = |name: &str, cooldown: u32| {
+        Module::new(name)
+            .block_cooldown(cooldown)
+            .methods(make_methods(&Method::new("*")))
+    };
+    let cennznut = CENNZnutV1 {
+        modules: vec![
+            module("*", 1),
+            module("staking_*", 2).pattern(),
+            module("staking_rewards", 3),
+        ]
+        .into(),
+        contracts: ContractSet::new(),
+    };
+
+    let cooldown = |module: &str| cennznut.get_module(module).map(|m| m.block_cooldown);
+    assert_eq!(cooldown("staking_rewards"), Some(Some(3)));
+    assert_eq!(cooldown("staking_bonds"), Some(Some(2)));
+    assert_eq!(cooldown("staking"), Some(Some(1)));
+}
+
+#[test]
+fn it_encodes_and_decodes_patterns() {
+    let method = Method::new("transfer*").pattern();
+    let module = Module::new("*_assets")
+        .pattern()
+        .methods(make_methods(&method));
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    };
+
+    let encoded = cennznut.encode();
+    let expected: Vec<u8> = [
+        vec![1, 0, 1 << 2, 0b0000_0010, 8 << 2],
+        b"*_assets".to_vec(),
+        vec![1 << 2, 0x04, 9 << 2],
+        b"transfer*".to_vec(),
+        vec![0],
+    ]
+    .concat();
+    assert_eq!(encoded, expected);
+    assert_eq!(
+        CENNZnut::decode(&mut &encoded[..]),
+        Ok(CENNZnut::V1(cennznut))
+    );
+    assert_eq!(CENNZnut::decode_strict(&encoded).map(|_| ()), Ok(()));
+}
+
+#[test]
+fn it_fails_to_encode_and_decode_invalid_patterns() {
+    let module = Module::new("**")
+        .pattern()
+        .methods(make_methods(&Method::new("*")));
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    };
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::InvalidModulePattern { module: 0 })
+    );
+
+    let method = Method::new("transfer").pattern();
+    let module = Module::new("balances").methods(vec![Method::new("burn"), method].into());
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    };
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::InvalidMethodPattern {
+            module: 0,
+            method: 1
+        })
+    );
+
+    let encoded: Vec<u8> = [
+        vec![1, 0, 1 << 2, 0, 8 << 2],
+        b"balances".to_vec(),
+        vec![1 << 2, 0x04, 8 << 2],
+        b"transfer".to_vec(),
+        vec![0],
+    ]
+    .concat();
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded),
+        Err(DecodeError::InvalidPattern {
+            offset: 15,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(0),
+                contract: None,
+            },
+            field: DecodeField::MethodName,
+        })
+    );
+}
+
+#[test]
+fn it_fails_to_downgrade_patterns() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].pattern = true;
+    cennznut.modules[0].name = "module_*".to_string();
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ModulePattern { module: 0 })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].methods[0].pattern = true;
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::MethodPattern {
+            module: 0,
+            method: 0
+        })
+    );
+}
+
+#[test]
+fn it_gives_denials_precedence_over_grants() {
+    let module = Module::new("generic_asset")
+        .methods(vec![Method::new("*"), Method::new("burn").deny()].into());
+    let denied_module = Module::new("staking_*").pattern().deny();
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: vec![Module::new("staking_bond"), module, denied_module].into(),
+        contracts: vec![Contract::new(&[0x12_u8; 32]).deny(), Contract::wildcard()].into(),
+    });
+
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "transfer", &[]),
+        Ok(())
+    );
+    let denied = cennznut
+        .validate_runtime_call("generic_asset", "burn", &[])
+        .unwrap_err();
+    assert_eq!(
+        denied,
+        ValidationErr::Denied(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "burn".into(),
+        })
+    );
+    assert_eq!(
+        denied.to_string(),
+        "CENNZnut explicitly denies permission for method generic_asset::burn"
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call("staking_bond", "bond", &[]),
+        Err(ValidationErr::Denied(RuntimeDomain::Module {
+            module: "staking_bond".into(),
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call_at(
+            1,
+            &mut InMemoryCooldownTracker::new(),
+            "generic_asset",
+            "burn",
+            &[]
+        ),
+        Err(ValidationErr::Denied(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "burn".into(),
+        }))
+    );
+
+    assert_eq!(cennznut.validate_contract_call(&[0x34_u8; 32]), Ok(()));
+    assert_eq!(
+        cennznut.validate_contract_call(&[0x12_u8; 32]),
+        Err(ValidationErr::Denied(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+}
+
+#[test]
+fn it_applies_method_denials_of_every_matching_module() {
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: vec![
+            Module::new("generic_asset").methods(vec![Method::new("*")].into()),
+            Module::new("*").methods(vec![Method::new("burn").deny()].into()),
+        ]
+        .into(),
+        contracts: vec![].into(),
+    });
+
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "transfer", &[]),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "burn", &[]),
+        Err(ValidationErr::Denied(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "burn".into(),
+        }))
+    );
+    assert_eq!(
+        cennznut
+            .explain_runtime_call("generic_asset", "burn", &[])
+            .result,
+        Err(ValidationErr::Denied(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "burn".into(),
+        }))
+    );
+}
+
+#[test]
+fn it_encodes_and_decodes_denials() {
+    let method = Method::new("burn").deny();
+    let module = Module::new("generic_asset").methods(make_methods(&method));
+    let denied_module = Module::new("staking").deny();
+    let contract = Contract::new(&[0x12_u8; 32]).deny();
+    let cennznut = CENNZnutV1 {
+        modules: vec![module, denied_module].into(),
+        contracts: make_contracts(&contract),
+    };
+
+    let encoded = cennznut.encode();
+    let expected: Vec<u8> = [
+        vec![1, 0, 2 << 2, 0, 13 << 2],
+        b"generic_asset".to_vec(),
+        vec![1 << 2, 0x08, 4 << 2],
+        b"burn".to_vec(),
+        vec![0b0000_0100, 7 << 2],
+        b"staking".to_vec(),
+        vec![0, 1 << 2, 0x02],
+        vec![0x12_u8; 32],
+    ]
+    .concat();
+    assert_eq!(encoded, expected);
+    assert_eq!(
+        CENNZnut::decode(&mut &encoded[..]),
+        Ok(CENNZnut::V1(cennznut))
+    );
+    assert_eq!(CENNZnut::decode_strict(&encoded).map(|_| ()), Ok(()));
+}
+
+#[test]
+fn it_fails_to_downgrade_denials() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].deny = true;
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ModuleDeny { module: 0 })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].methods[0].deny = true;
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::MethodDeny {
+            module: 0,
+            method: 0
+        })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    let contract = Contract::wildcard().deny();
+    cennznut.contracts.push(contract);
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ContractDeny { contract: 1 })
+    );
+}
+
+#[test]
+fn it_validates_contract_calls_with_selectors_and_constraints() {
+    let restricted = Contract::new(&[0x12_u8; 32])
+        .selectors(vec![[0xde, 0xad, 0xbe, 0xef]])
+        .constraints(make_pact(
+            vec![PactType::Numeric(Numeric(100))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        ));
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: vec![restricted, Contract::wildcard()].into(),
+    });
+    let selector = [0xde, 0xad, 0xbe, 0xef];
+    let args = [PactType::Numeric(Numeric(100))];
+
+    assert_eq!(
+        cennznut.validate_contract_call_with(&[0x12_u8; 32], &selector, &args),
+        Ok(())
+    );
+    let denied = cennznut
+        .validate_contract_call_with(&[0x12_u8; 32], &[0_u8; 4], &args)
+        .unwrap_err();
+    assert_eq!(
+        denied,
+        ValidationErr::NoPermission(ContractDomain::Selector {
+            address: [0x12_u8; 32],
+            selector: [0_u8; 4],
+        })
+    );
+    assert_eq!(
+        denied.to_string(),
+        format!(
+            "CENNZnut does not grant permission for contract 0x{} selector 0x00000000",
+            "12".repeat(32)
+        )
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_with(
+            &[0x12_u8; 32],
+            &selector,
+            &[PactType::Numeric(Numeric(101))]
+        ),
+        Err(ValidationErr::NoPermission(
+            ContractDomain::SelectorArguments {
+                address: [0x12_u8; 32],
+                selector,
+            }
+        ))
+    );
+    // A restricted contract cannot be validated without the call
+    assert_eq!(
+        cennznut.validate_contract_call(&[0x12_u8; 32]),
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_with(&[0x34_u8; 32], &[0_u8; 4], &[]),
+        Ok(())
+    );
+}
+
+#[test]
+fn it_validates_restricted_contract_calls_at_blocks() {
+    let restricted = Contract::new(&[0x12_u8; 32])
+        .selectors(vec![[0xde, 0xad, 0xbe, 0xef]])
+        .constraints(make_pact(
+            vec![PactType::Numeric(Numeric(100))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        ))
+        .valid_from(5)
+        .block_cooldown(2);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: make_contracts(&restricted),
+    });
+    let selector = [0xde, 0xad, 0xbe, 0xef];
+    let args = [PactType::Numeric(Numeric(100))];
+    let mut tracker = InMemoryCooldownTracker::new();
+
+    // A contract bounded by a validity window cannot be validated without the block
+    assert_eq!(
+        cennznut.validate_contract_call_with(&[0x12_u8; 32], &selector, &args),
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at_with(4, &mut tracker, &[0x12_u8; 32], &selector, &args),
+        Err(ValidationErr::NotYetValid(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at_with(5, &mut tracker, &[0x12_u8; 32], &[0_u8; 4], &args),
+        Err(ValidationErr::NoPermission(ContractDomain::Selector {
+            address: [0x12_u8; 32],
+            selector: [0_u8; 4],
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at_with(
+            5,
+            &mut tracker,
+            &[0x12_u8; 32],
+            &selector,
+            &[PactType::Numeric(Numeric(101))]
+        ),
+        Err(ValidationErr::NoPermission(
+            ContractDomain::SelectorArguments {
+                address: [0x12_u8; 32],
+                selector,
+            }
+        ))
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at_with(5, &mut tracker, &[0x12_u8; 32], &selector, &args),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at_with(6, &mut tracker, &[0x12_u8; 32], &selector, &args),
+        Err(ValidationErr::CooldownNotElapsed(
+            ContractDomain::Contract {
+                address: [0x12_u8; 32],
+            }
+        ))
+    );
+    // A restricted contract cannot be validated without the call
+    assert_eq!(
+        cennznut.validate_contract_call_at(7, &mut tracker, &[0x12_u8; 32]),
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+}
+
+#[test]
+fn it_validates_restricted_contract_calls_within_rate_limits_and_quotas() {
+    let restricted = Contract::new(&[0x12_u8; 32])
+        .selectors(vec![[0xde, 0xad, 0xbe, 0xef]])
+        .constraints(make_pact(
+            vec![PactType::Numeric(Numeric(100))],
+            vec![OpCode::EQ.into(), 0, 0, 1, 0],
+        ))
+        .rate_limit(1, 5)
+        .max_uses(1);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: make_contracts(&restricted),
+    });
+    let selector = [0xde, 0xad, 0xbe, 0xef];
+    let args = [PactType::Numeric(Numeric(100))];
+    let mut history = InMemoryCallHistory::new();
+    let mut counter = InMemoryUsageCounter::new();
+    let nut_hash = [0x01_u8; 32];
+
+    // A contract bounded by a usage quota cannot be validated without counting uses
+    assert_eq!(
+        cennznut.validate_contract_call_rate_limited_with(
+            1,
+            &mut history,
+            &[0x12_u8; 32],
+            &selector,
+            &args
+        ),
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+    // Calls which fail the constraints are not counted
+    assert_eq!(
+        cennznut.validate_contract_call_metered_with(
+            1,
+            &mut history,
+            &nut_hash,
+            &mut counter,
+            &[0x12_u8; 32],
+            &selector,
+            &[PactType::Numeric(Numeric(101))]
+        ),
+        Err(ValidationErr::NoPermission(
+            ContractDomain::SelectorArguments {
+                address: [0x12_u8; 32],
+                selector,
+            }
+        ))
+    );
+    let mut validate_metered_at = |block_number: u32| {
+        cennznut.validate_contract_call_metered_with(
+            block_number,
+            &mut history,
+            &nut_hash,
+            &mut counter,
+            &[0x12_u8; 32],
+            &selector,
+            &args,
+        )
+    };
+    assert_eq!(validate_metered_at(1), Ok(()));
+    assert_eq!(
+        validate_metered_at(5),
+        Err(ValidationErr::RateLimitExceeded(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+    assert_eq!(
+        validate_metered_at(6),
+        Err(ValidationErr::QuotaExhausted(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+}
+
+#[test]
+fn it_fails_to_downgrade_contract_selectors_and_constraints() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.contracts[0].selectors = Some(vec![[0_u8; 4]]);
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ContractSelectors { contract: 0 })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.contracts[0].constraints = Some(make_pact(
+        vec![PactType::Numeric(Numeric(100))],
+        vec![OpCode::EQ.into(), 0, 0, 1, 0],
+    ));
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ContractConstraints { contract: 0 })
+    );
+}
+
+#[test]
+fn it_validates_validity_windows() {
+    let method = Method::new("transfer").valid_until(20);
+    let module = Module::new("generic_asset")
+        .valid_from(10)
+        .methods(make_methods(&method));
+    let contract = Contract::new(&[0x12_u8; 32]).valid_from(5).valid_until(5);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: make_contracts(&contract),
+    });
+    let mut tracker = InMemoryCooldownTracker::new();
+
+    assert_eq!(
+        cennznut.validate_runtime_call_at(9, &mut tracker, "generic_asset", "transfer", &[]),
+        Err(ValidationErr::NotYetValid(RuntimeDomain::Module {
+            module: "generic_asset".into(),
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call_at(10, &mut tracker, "generic_asset", "transfer", &[]),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call_at(20, &mut tracker, "generic_asset", "transfer", &[]),
+        Ok(())
+    );
+    let expired = cennznut
+        .validate_runtime_call_at(21, &mut tracker, "generic_asset", "transfer", &[])
+        .unwrap_err();
+    assert_eq!(
+        expired,
+        ValidationErr::Expired(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "transfer".into(),
+        })
+    );
+    assert_eq!(
+        expired.to_string(),
+        "CENNZnut method generic_asset::transfer has expired"
+    );
+    // A bounded grant cannot be validated without the block number
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "transfer", &[]),
+        Err(ValidationErr::NoPermission(RuntimeDomain::Module {
+            module: "generic_asset".into(),
+        }))
+    );
+
+    assert_eq!(
+        cennznut.validate_contract_call_at(4, &mut tracker, &[0x12_u8; 32]),
+        Err(ValidationErr::NotYetValid(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at(5, &mut tracker, &[0x12_u8; 32]),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at(6, &mut tracker, &[0x12_u8; 32]),
+        Err(ValidationErr::Expired(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+}
+
+#[test]
+fn it_encodes_and_decodes_validity_windows() {
+    let method = Method::new("burn").valid_until(0x0102_0304);
+    let module = Module::new("assets")
+        .valid_from(7)
+        .methods(make_methods(&method));
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    };
+
+    let encoded = cennznut.encode();
+    let expected: Vec<u8> = [
+        vec![1, 0, 1 << 2, 0b0000_1000, 6 << 2],
+        b"assets".to_vec(),
+        vec![7, 0, 0, 0],
+        vec![1 << 2, 0x20, 4 << 2],
+        b"burn".to_vec(),
+        vec![0x04, 0x03, 0x02, 0x01],
+        vec![0],
+    ]
+    .concat();
+    assert_eq!(encoded, expected);
+    assert_eq!(
+        CENNZnut::decode(&mut &encoded[..]),
+        Ok(CENNZnut::V1(cennznut))
+    );
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded[..13]),
+        Err(DecodeError::Truncated {
+            offset: 11,
+            path: DecodePath {
+                module: Some(0),
+                method: None,
+                contract: None,
+            },
+            field: DecodeField::ModuleValidFrom,
+        })
+    );
+}
+
+#[test]
+fn it_fails_to_downgrade_validity_windows() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].valid_from = Some(1);
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ModuleValidity { module: 0 })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].methods[0].valid_until = Some(1);
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::MethodValidity {
+            module: 0,
+            method: 0
+        })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.contracts[0].valid_until = Some(1);
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ContractValidity { contract: 0 })
+    );
+}
+
+#[test]
+fn it_validates_usage_quotas() {
+    let method = Method::new("transfer*").pattern().max_uses(2);
+    let module = Module::new("generic_asset").methods(make_methods(&method));
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    });
+    let mut tracker = InMemoryCallHistory::new();
+    let mut counter = InMemoryUsageCounter::new();
+    let nut_hash = [0x01_u8; 32];
+    let grant = GrantPath::Method {
+        module: "generic_asset".into(),
+        method: "transfer*".into(),
+    };
+
+    assert_eq!(
+        cennznut.remaining_uses(&nut_hash, &counter, &grant),
+        Some(2)
+    );
+    // Every method covered by the pattern counts towards its quota
+    for method_name in &["transfer", "transfer_all"] {
+        assert_eq!(
+            cennznut.validate_runtime_call_metered(
+                1,
+                &mut tracker,
+                &nut_hash,
+                &mut counter,
+                "generic_asset",
+                method_name,
+                &[]
+            ),
+            Ok(())
+        );
+    }
+    assert_eq!(
+        cennznut.remaining_uses(&nut_hash, &counter, &grant),
+        Some(0)
+    );
+    let exhausted = cennznut
+        .validate_runtime_call_metered(
+            2,
+            &mut tracker,
+            &nut_hash,
+            &mut counter,
+            "generic_asset",
+            "transfer",
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        exhausted,
+        ValidationErr::QuotaExhausted(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "transfer".into(),
+        })
+    );
+    assert_eq!(
+        exhausted.to_string(),
+        "CENNZnut method generic_asset::transfer usage quota is exhausted"
+    );
+    // Uses are counted per CENNZnut
+    assert_eq!(
+        cennznut.validate_runtime_call_metered(
+            2,
+            &mut tracker,
+            &[0x02_u8; 32],
+            &mut counter,
+            "generic_asset",
+            "transfer",
+            &[]
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn it_validates_contract_usage_quotas() {
+    let contract = Contract::wildcard().max_uses(1);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: make_contracts(&contract),
+    });
+    let mut tracker = InMemoryCallHistory::new();
+    let mut counter = InMemoryUsageCounter::new();
+    let nut_hash = [0x01_u8; 32];
+
+    // Every contract covered by the wildcard counts towards its quota
+    assert_eq!(
+        cennznut.validate_contract_call_metered(
+            1,
+            &mut tracker,
+            &nut_hash,
+            &mut counter,
+            &[0x12_u8; 32]
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_metered(
+            2,
+            &mut tracker,
+            &nut_hash,
+            &mut counter,
+            &[0x34_u8; 32]
+        ),
+        Err(ValidationErr::QuotaExhausted(ContractDomain::Contract {
+            address: [0x34_u8; 32],
+        }))
+    );
+    assert_eq!(
+        cennznut.remaining_uses(
+            &nut_hash,
+            &counter,
+            &GrantPath::Contract {
+                address: CONTRACT_WILDCARD
+            }
+        ),
+        Some(0)
+    );
+    assert_eq!(
+        cennznut.remaining_uses(
+            &nut_hash,
+            &counter,
+            &GrantPath::Contract {
+                address: [0x12_u8; 32]
+            }
+        ),
+        None
+    );
+}
+
+#[test]
+fn it_fails_to_validate_usage_quotas_without_counting_uses() {
+    let method = Method::new("transfer").max_uses(2);
+    let module = Module::new("generic_asset").methods(make_methods(&method));
+    let contract = Contract::wildcard().max_uses(1);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: make_contracts(&contract),
+    });
+    let method_err = || {
+        Err(ValidationErr::NoPermission(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "transfer".into(),
+        }))
+    };
+    let contract_err = || {
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    };
+
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "transfer", &[]),
+        method_err()
+    );
+    assert_eq!(
+        cennznut
+            .explain_runtime_call("generic_asset", "transfer", &[])
+            .result,
+        method_err()
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call_at(
+            1,
+            &mut InMemoryCooldownTracker::new(),
+            "generic_asset",
+            "transfer",
+            &[]
+        ),
+        method_err()
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call_rate_limited(
+            1,
+            &mut InMemoryCallHistory::new(),
+            "generic_asset",
+            "transfer",
+            &[]
+        ),
+        method_err()
+    );
+    assert_eq!(
+        cennznut.validate_contract_call(&[0x12_u8; 32]),
+        contract_err()
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_with(&[0x12_u8; 32], &[0xde, 0xad, 0xbe, 0xef], &[]),
+        contract_err()
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at(1, &mut InMemoryCooldownTracker::new(), &[0x12_u8; 32]),
+        contract_err()
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_rate_limited(
+            1,
+            &mut InMemoryCallHistory::new(),
+            &[0x12_u8; 32]
+        ),
+        contract_err()
+    );
+}
+
+#[test]
+fn it_does_not_record_uses_of_failed_calls() {
+    let method = Method::new("transfer").block_cooldown(5).max_uses(1);
+    let module = Module::new("generic_asset").methods(make_methods(&method));
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    });
+    let mut tracker = InMemoryCallHistory::new();
+    let mut counter = InMemoryUsageCounter::new();
+    let nut_hash = [0x01_u8; 32];
+
+    tracker.record_method_call("generic_asset", "transfer", 1);
+    assert_eq!(
+        cennznut.validate_runtime_call_metered(
+            2,
+            &mut tracker,
+            &nut_hash,
+            &mut counter,
+            "generic_asset",
+            "transfer",
+            &[]
+        ),
+        Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "transfer".into(),
+        }))
+    );
+    assert_eq!(
+        cennznut.remaining_uses(
+            &nut_hash,
+            &counter,
+            &GrantPath::Method {
+                module: "generic_asset".into(),
+                method: "transfer".into(),
+            }
+        ),
+        Some(1)
+    );
+}
+
+#[test]
+fn it_encodes_and_decodes_usage_quotas() {
+    let method = Method::new("burn").max_uses(3);
+    let module = Module::new("assets").methods(make_methods(&method));
+    let contract = Contract::new(&[0x12_u8; 32]).max_uses(0x0102_0304);
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: make_contracts(&contract),
+    };
+
+    let encoded = cennznut.encode();
+    let expected: Vec<u8> = [
+        vec![1, 0, 1 << 2, 0, 6 << 2],
+        b"assets".to_vec(),
+        vec![1 << 2, 0x40, 4 << 2],
+        b"burn".to_vec(),
+        vec![3, 0, 0, 0],
+        vec![1 << 2, 0x40],
+        vec![0x12_u8; 32],
+        vec![0x04, 0x03, 0x02, 0x01],
+    ]
+    .concat();
+    assert_eq!(encoded, expected);
+    assert_eq!(
+        CENNZnut::decode(&mut &encoded[..]),
+        Ok(CENNZnut::V1(cennznut))
+    );
+}
+
+#[test]
+fn it_fails_to_downgrade_usage_quotas() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].methods[0].max_uses = Some(1);
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::MethodMaxUses {
+            module: 0,
+            method: 0
+        })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.contracts[0].max_uses = Some(1);
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ContractMaxUses { contract: 0 })
+    );
+}
+
+#[test]
+fn it_validates_rate_limits() {
+    let method = Method::new("transfer").rate_limit(2, 10);
+    let module = Module::new("generic_asset")
+        .block_cooldown(2)
+        .methods(make_methods(&method));
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    });
+    let mut history = InMemoryCallHistory::new();
+    let mut validate_at = |block_number: u32| {
+        cennznut.validate_runtime_call_rate_limited(
+            block_number,
+            &mut history,
+            "generic_asset",
+            "transfer",
+            &[],
+        )
+    };
+
+    assert_eq!(validate_at(1), Ok(()));
+    // The cooldown still applies within the rate limit
+    assert_eq!(
+        validate_at(2),
+        Err(ValidationErr::CooldownNotElapsed(RuntimeDomain::Module {
+            module: "generic_asset".into(),
+        }))
+    );
+    assert_eq!(validate_at(5), Ok(()));
+    let exceeded = validate_at(10).unwrap_err();
+    assert_eq!(
+        exceeded,
+        ValidationErr::RateLimitExceeded(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "transfer".into(),
+        })
+    );
+    assert_eq!(
+        exceeded.to_string(),
+        "CENNZnut method generic_asset::transfer rate limit is exceeded"
+    );
+    // The call at block 1 has left the window of blocks 2 to 11
+    assert_eq!(validate_at(11), Ok(()));
+    assert!(validate_at(14).is_err());
+}
+
+#[test]
+fn it_validates_contract_rate_limits() {
+    let contract = Contract::new(&[0x12_u8; 32]).rate_limit(1, 5);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: make_contracts(&contract),
+    });
+    let mut history = InMemoryCallHistory::new();
+
+    assert_eq!(
+        cennznut.validate_contract_call_rate_limited(1, &mut history, &[0x12_u8; 32]),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_rate_limited(5, &mut history, &[0x12_u8; 32]),
+        Err(ValidationErr::RateLimitExceeded(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_rate_limited(6, &mut history, &[0x12_u8; 32]),
+        Ok(())
+    );
+}
+
+#[test]
+fn it_counts_rate_limits_per_grant() {
+    let method = Method::new("transfer*").pattern().rate_limit(2, 10);
+    let module = Module::new("generic_*")
+        .pattern()
+        .rate_limit(3, 10)
+        .methods(vec![method, Method::new("burn")].into());
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: make_contracts(&Contract::wildcard().rate_limit(1, 10)),
+    });
+    let mut history = InMemoryCallHistory::new();
+
+    // Every method covered by the pattern counts towards its rate limit
+    for method_name in &["transfer", "transfer_all"] {
+        assert_eq!(
+            cennznut.validate_runtime_call_rate_limited(
+                1,
+                &mut history,
+                "generic_asset",
+                method_name,
+                &[]
+            ),
+            Ok(())
+        );
+    }
+    assert_eq!(
+        cennznut.validate_runtime_call_rate_limited(
+            2,
+            &mut history,
+            "generic_asset",
+            "transfer_from",
+            &[]
+        ),
+        Err(ValidationErr::RateLimitExceeded(RuntimeDomain::Method {
+            module: "generic_asset".into(),
+            method: "transfer_from".into(),
+        }))
+    );
+    // Every module covered by the pattern counts towards its rate limit
+    assert_eq!(
+        cennznut.validate_runtime_call_rate_limited(2, &mut history, "generic_fee", "burn", &[]),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call_rate_limited(3, &mut history, "generic_asset", "burn", &[]),
+        Err(ValidationErr::RateLimitExceeded(RuntimeDomain::Module {
+            module: "generic_asset".into(),
+        }))
+    );
+
+    // Every contract covered by the wildcard counts towards its rate limit
+    assert_eq!(
+        cennznut.validate_contract_call_rate_limited(1, &mut history, &[0x12_u8; 32]),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_rate_limited(2, &mut history, &[0x34_u8; 32]),
+        Err(ValidationErr::RateLimitExceeded(ContractDomain::Contract {
+            address: [0x34_u8; 32],
+        }))
+    );
+}
+
+#[test]
+fn it_fails_to_validate_rate_limits_without_counting_calls() {
+    let module = Module::new("generic_asset")
+        .rate_limit(1, 10)
+        .methods(make_methods(&Method::new("transfer")));
+    let method = Method::new("transfer").rate_limit(1, 10);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: vec![
+            module,
+            Module::new("staking").methods(make_methods(&method)),
+        ]
+        .into(),
+        contracts: make_contracts(&Contract::wildcard().rate_limit(1, 10)),
+    });
+    let module_err = || {
+        Err(ValidationErr::NoPermission(RuntimeDomain::Module {
+            module: "generic_asset".into(),
+        }))
+    };
+    let method_err = || {
+        Err(ValidationErr::NoPermission(RuntimeDomain::Method {
+            module: "staking".into(),
+            method: "transfer".into(),
+        }))
+    };
+    let contract_err = || {
+        Err(ValidationErr::NoPermission(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    };
+
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "transfer", &[]),
+        module_err()
+    );
+    assert_eq!(
+        cennznut
+            .explain_runtime_call("generic_asset", "transfer", &[])
+            .result,
+        module_err()
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call("staking", "transfer", &[]),
+        method_err()
+    );
+    let mut tracker = InMemoryCooldownTracker::new();
+    assert_eq!(
+        cennznut.validate_runtime_call_at(1, &mut tracker, "generic_asset", "transfer", &[]),
+        module_err()
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call_at(1, &mut tracker, "staking", "transfer", &[]),
+        method_err()
+    );
+    assert_eq!(
+        cennznut.validate_contract_call(&[0x12_u8; 32]),
+        contract_err()
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_with(&[0x12_u8; 32], &[0xde, 0xad, 0xbe, 0xef], &[]),
+        contract_err()
+    );
+    assert_eq!(
+        cennznut.validate_contract_call_at(1, &mut tracker, &[0x12_u8; 32]),
+        contract_err()
+    );
+}
+
+#[test]
+fn it_encodes_and_decodes_rate_limits() {
+    let method = Method::new("burn").rate_limit(20, 100);
+    let module = Module::new("assets")
+        .rate_limit(1, 2)
+        .methods(make_methods(&method));
+    let contract = Contract::new(&[0x12_u8; 32]).rate_limit(3, 4);
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: make_contracts(&contract),
+    };
+
+    let encoded = cennznut.encode();
+    let expected: Vec<u8> = [
+        vec![1, 0, 1 << 2, 0b0010_0000, 6 << 2],
+        b"assets".to_vec(),
+        vec![1, 0, 0, 0, 2, 0, 0, 0],
+        vec![1 << 2, 0x80, 4 << 2],
+        b"burn".to_vec(),
+        vec![20, 0, 0, 0, 100, 0, 0, 0],
+        vec![1 << 2, 0x80],
+        vec![0x12_u8; 32],
+        vec![3, 0, 0, 0, 4, 0, 0, 0],
+    ]
+    .concat();
+    assert_eq!(encoded, expected);
+    assert_eq!(
+        CENNZnut::decode(&mut &encoded[..]),
+        Ok(CENNZnut::V1(cennznut))
+    );
+}
+
+#[test]
+fn it_fails_to_encode_and_decode_empty_rate_limit_windows() {
+    let module = Module::new("assets")
+        .rate_limit(1, 0)
+        .methods(make_methods(&Method::new("burn")));
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    };
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::EmptyModuleRateLimitWindow { module: 0 })
+    );
+
+    let method = Method::new("burn").rate_limit(1, 0);
+    let module = Module::new("assets").methods(vec![Method::new("mint"), method].into());
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    };
+    assert_eq!(
+        cennznut.try_encode(),
+        Err(EncodeError::EmptyMethodRateLimitWindow {
+            module: 0,
+            method: 1
+        })
+    );
+
+    let contracts = vec![
+        Contract::new(&[0x12_u8; 32]),
+        Contract::new(&[0x34_u8; 32]).rate_limit(1, 0),
+    ];
+    let cennznut = CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: contracts.into(),
+    };
+    let err = cennznut.try_encode().unwrap_err();
+    assert_eq!(
+        err,
+        EncodeError::EmptyContractRateLimitWindow { contract: 1 }
+    );
+    assert_eq!(err.to_string(), "contract 1 rate limit window is empty");
+
+    let encoded: Vec<u8> = [
+        vec![1, 0, 1 << 2, 0b0010_0000, 6 << 2],
+        b"assets".to_vec(),
+        vec![1, 0, 0, 0, 0, 0, 0, 0],
+        vec![1 << 2, 0, 4 << 2],
+        b"burn".to_vec(),
+        vec![0],
+    ]
+    .concat();
+    let err = CENNZnut::decode_detailed(&encoded).unwrap_err();
+    assert_eq!(
+        err,
+        DecodeError::EmptyRateLimitWindow {
+            offset: 15,
+            path: DecodePath {
+                module: Some(0),
+                method: None,
+                contract: None,
+            },
+            field: DecodeField::ModuleRateLimit,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "empty module rate limit window at byte 15 in module 0"
+    );
+}
+
+#[test]
+fn it_denies_every_call_with_a_rate_limit_of_no_calls() {
+    let contract = Contract::new(&[0x12_u8; 32]).rate_limit(0, 5);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: ModuleSet::new(),
+        contracts: make_contracts(&contract),
+    });
+    let mut history = InMemoryCallHistory::new();
+
+    assert_eq!(
+        cennznut.validate_contract_call_rate_limited(1, &mut history, &[0x12_u8; 32]),
+        Err(ValidationErr::RateLimitExceeded(ContractDomain::Contract {
+            address: [0x12_u8; 32],
+        }))
+    );
+}
+
+#[test]
+fn it_fails_to_downgrade_rate_limits() {
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].rate_limit = Some(RateLimit::new(1, 2));
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ModuleRateLimit { module: 0 })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].methods[0].rate_limit = Some(RateLimit::new(1, 2));
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::MethodRateLimit {
+            module: 0,
+            method: 0
+        })
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.contracts[0].rate_limit = Some(RateLimit::new(1, 2));
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ContractRateLimit { contract: 0 })
+    );
+}
+
+#[test]
+fn it_validates_module_constraints_before_method_constraints() {
+    let asset_id = Constraint::arg(0).eq(PactType::Numeric(Numeric(16_000)));
+    let amount = Constraint::arg(1).lte(PactType::Numeric(Numeric(100)));
+    let methods = vec![
+        Method::new("transfer")
+            .constraint(&amount)
+            .expect("it encodes"),
+        Method::new("*"),
+    ]
+    .into();
+    let module = Module::new("generic_asset")
+        .constraint(&asset_id)
+        .expect("it encodes")
+        .methods(methods);
+    let cennznut = CENNZnut::V1(CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    });
+    let args = |asset: u64, amount: u64| {
+        [
+            PactType::Numeric(Numeric(asset)),
+            PactType::Numeric(Numeric(amount)),
+        ]
+    };
+
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "transfer", &args(16_000, 100)),
+        Ok(())
+    );
+    let unsatisfied = cennznut
+        .validate_runtime_call("generic_asset", "transfer", &args(1, 100))
+        .unwrap_err();
+    assert_eq!(
+        unsatisfied,
+        ValidationErr::NoPermission(RuntimeDomain::ModuleArguments {
+            module: "generic_asset".into(),
+            method: "transfer".into(),
+        })
+    );
+    assert_eq!(
+        unsatisfied.to_string(),
+        "CENNZnut does not grant permission for method generic_asset::transfer arguments \
+         under the module constraints"
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "transfer", &args(16_000, 101)),
+        Err(ValidationErr::NoPermission(
+            RuntimeDomain::MethodArguments {
+                module: "generic_asset".into(),
+                method: "transfer".into(),
+            }
+        ))
+    );
+    // Unconstrained methods are still bound by the module constraints
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "burn", &args(16_000, 500)),
+        Ok(())
+    );
+    assert_eq!(
+        cennznut.validate_runtime_call("generic_asset", "burn", &args(1, 500)),
+        Err(ValidationErr::NoPermission(
+            RuntimeDomain::ModuleArguments {
+                module: "generic_asset".into(),
+                method: "burn".into(),
+            }
+        ))
+    );
+}
+
+#[test]
+fn it_encodes_and_decodes_module_constraints() {
+    let constraints = make_pact(
+        vec![PactType::Numeric(Numeric(16_000))],
+        vec![OpCode::EQ.into(), 0, 0, 1, 0],
+    );
+    let module = Module::new("assets").constraints(constraints.clone());
+    let cennznut = CENNZnutV1 {
+        modules: make_modules(&module),
+        contracts: ContractSet::new(),
+    };
+
+    let encoded = cennznut.encode();
+    let expected: Vec<u8> = [
+        vec![1, 0, 1 << 2, 0b0100_0000, 6 << 2],
+        b"assets".to_vec(),
+        vec![(constraints.len() as u8) << 2],
+        constraints,
+        vec![0, 0],
+    ]
+    .concat();
+    assert_eq!(encoded, expected);
+    assert_eq!(
+        CENNZnut::decode(&mut &encoded[..]),
+        Ok(CENNZnut::V1(cennznut))
+    );
+
+    let mut cennznut = CENNZnutV1::from(make_v0());
+    cennznut.modules[0].constraints = Some(make_pact(
+        vec![PactType::Numeric(Numeric(1))],
+        vec![OpCode::EQ.into(), 0, 0, 1, 0],
+    ));
+    assert_eq!(
+        CENNZnutV0::try_from(cennznut),
+        Err(DowngradeError::ModuleConstraints { module: 0 })
+    );
+}
@@ -0,0 +1,360 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - V1 Trace
+//!
+//! Explanation of a CENNZnut runtime call validation
+//!
+
+use super::{method::Method, module::Module, CENNZnutV1};
+use crate::constraints::first_unsatisfied_clause;
+use crate::trace::{ConstraintsOutcome, GrantMatch, MatchedGrant, ValidationTrace};
+use crate::ValidationErr;
+use pact::contract::Contract as PactContract;
+use pact::interpreter::types::PactType;
+
+/// Observes the steps of a runtime call validation as they are reached.
+/// Validation observes nothing, `explain_module` records the steps in a `ValidationTrace`.
+pub(crate) trait ValidationObserver {
+    fn module_matched(&mut self, module_name: &str, key: &str, module: &Module);
+    fn method_matched(&mut self, method_name: &str, key: &str, method: &Method);
+    fn module_constraints(&mut self, outcome: impl FnOnce() -> ConstraintsOutcome);
+    fn method_constraints(&mut self, outcome: impl FnOnce() -> ConstraintsOutcome);
+}
+
+impl ValidationObserver for () {
+    fn module_matched(&mut self, _: &str, _: &str, _: &Module) {}
+    fn method_matched(&mut self, _: &str, _: &str, _: &Method) {}
+    fn module_constraints(&mut self, _: impl FnOnce() -> ConstraintsOutcome) {}
+    fn method_constraints(&mut self, _: impl FnOnce() -> ConstraintsOutcome) {}
+}
+
+impl ValidationObserver for ValidationTrace {
+    fn module_matched(&mut self, module_name: &str, key: &str, module: &Module) {
+        self.module = Some(matched_grant(
+            module_name,
+            key,
+            module.pattern,
+            module.block_cooldown,
+        ));
+    }
+
+    fn method_matched(&mut self, method_name: &str, key: &str, method: &Method) {
+        self.method = Some(matched_grant(
+            method_name,
+            key,
+            method.pattern,
+            method.block_cooldown,
+        ));
+    }
+
+    fn module_constraints(&mut self, outcome: impl FnOnce() -> ConstraintsOutcome) {
+        self.module_constraints = Some(outcome());
+    }
+
+    fn method_constraints(&mut self, outcome: impl FnOnce() -> ConstraintsOutcome) {
+        self.constraints = Some(outcome());
+    }
+}
+
+/// Returns the grant matched by `requested` with the entry `key`
+fn matched_grant(
+    requested: &str,
+    key: &str,
+    pattern: bool,
+    block_cooldown: Option<u32>,
+) -> MatchedGrant {
+    MatchedGrant {
+        key: key.into(),
+        matched: if pattern {
+            GrantMatch::Pattern
+        } else if key == requested {
+            GrantMatch::Exact
+        } else {
+            GrantMatch::Wildcard
+        },
+        block_cooldown,
+    }
+}
+
+/// Returns the outcome of interpreting the `pact` constraints over `args` with `result`
+pub(crate) fn constraints_outcome<D: core::fmt::Display>(
+    pact: &PactContract,
+    args: &[PactType],
+    result: &Result<(), ValidationErr<D>>,
+) -> ConstraintsOutcome {
+    match result {
+        Ok(()) => ConstraintsOutcome::Satisfied,
+        Err(ValidationErr::ConstraintsInterpretation(_)) => {
+            ConstraintsOutcome::InterpretationFailed
+        }
+        Err(_) => ConstraintsOutcome::Unsatisfied {
+            clause: first_unsatisfied_clause(pact, args),
+        },
+    }
+}
+
+impl CENNZnutV1 {
+    /// Validates a CENNZnut runtime module call as `validate_module`,
+    /// recording the matched grants and constraints outcome
+    pub fn explain_module(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: &[PactType],
+    ) -> ValidationTrace {
+        let mut trace = ValidationTrace {
+            module: None,
+            method: None,
+            module_constraints: None,
+            constraints: None,
+            result: Ok(()),
+        };
+        let result = self
+            .get_untracked_method(None, module_name, method_name, args, &mut trace)
+            .map(|_| ());
+        trace.result = result;
+        trace
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{method::Method, module::Module, CENNZnutV1};
+    use crate::cennznut::v0::{method::Method as MethodV0, module::Module as ModuleV0};
+    use crate::trace::{ConstraintsOutcome, GrantMatch, MatchedGrant, ValidationTrace};
+    use crate::{CENNZnut, CENNZnutV0, Constraint, RuntimeDomain, ValidationErr};
+    use pact::types::{Numeric, PactType};
+
+    fn make_cennznut() -> CENNZnut {
+        let constraint = Constraint::arg(0)
+            .lte(PactType::Numeric(Numeric(100)))
+            .and(Constraint::arg(1).eq(PactType::Numeric(Numeric(7))));
+        let methods = vec![
+            Method::new("transfer")
+                .block_cooldown(5)
+                .constraint(&constraint)
+                .expect("it encodes"),
+            Method::new("*"),
+        ]
+        .into();
+        let module = Module::new("*").block_cooldown(10).methods(methods);
+        CENNZnut::V1(CENNZnutV1 {
+            modules: vec![module].into(),
+            contracts: vec![].into(),
+        })
+    }
+
+    fn args(amount: u64, asset: u64) -> [PactType<'static>; 2] {
+        [
+            PactType::Numeric(Numeric(amount)),
+            PactType::Numeric(Numeric(asset)),
+        ]
+    }
+
+    #[test]
+    fn it_explains_permitted_calls() {
+        let cennznut = make_cennznut();
+        let trace = cennznut.explain_runtime_call("balances", "transfer", &args(100, 7));
+
+        assert_eq!(
+            trace,
+            ValidationTrace {
+                module: Some(MatchedGrant {
+                    key: "*".to_string(),
+                    matched: GrantMatch::Wildcard,
+                    block_cooldown: Some(10),
+                }),
+                method: Some(MatchedGrant {
+                    key: "transfer".to_string(),
+                    matched: GrantMatch::Exact,
+                    block_cooldown: Some(5),
+                }),
+                module_constraints: None,
+                constraints: Some(ConstraintsOutcome::Satisfied),
+                result: Ok(()),
+            }
+        );
+        assert_eq!(
+            trace.to_string(),
+            "module: * (wildcard), block cooldown: 10\n\
+             method: transfer (exact), block cooldown: 5\n\
+             constraints: satisfied\n\
+             result: permitted"
+        );
+    }
+
+    #[test]
+    fn it_explains_the_unsatisfied_clause() {
+        let cennznut = make_cennznut();
+        let trace = cennznut.explain_runtime_call("balances", "transfer", &args(100, 8));
+
+        assert_eq!(
+            trace.constraints,
+            Some(ConstraintsOutcome::Unsatisfied { clause: Some(1) })
+        );
+        assert_eq!(
+            trace.result,
+            cennznut.validate_runtime_call("balances", "transfer", &args(100, 8))
+        );
+        assert_eq!(
+            cennznut
+                .explain_runtime_call("balances", "transfer", &args(101, 7))
+                .constraints,
+            Some(ConstraintsOutcome::Unsatisfied { clause: Some(0) })
+        );
+        assert_eq!(
+            cennznut
+                .explain_runtime_call("balances", "transfer", &[])
+                .constraints,
+            Some(ConstraintsOutcome::InterpretationFailed)
+        );
+    }
+
+    #[test]
+    fn it_explains_unconstrained_wildcard_methods() {
+        let trace = make_cennznut().explain_runtime_call("balances", "burn", &[]);
+
+        assert_eq!(
+            trace.method,
+            Some(MatchedGrant {
+                key: "*".to_string(),
+                matched: GrantMatch::Wildcard,
+                block_cooldown: None,
+            })
+        );
+        assert_eq!(trace.constraints, Some(ConstraintsOutcome::Unconstrained));
+        assert_eq!(trace.result, Ok(()));
+    }
+
+    #[test]
+    fn it_explains_pattern_methods() {
+        let method = Method::new("burn_*").pattern().block_cooldown(3);
+        let module = Module::new("balances").methods(vec![method].into());
+        let cennznut = CENNZnut::V1(CENNZnutV1 {
+            modules: vec![module].into(),
+            contracts: vec![].into(),
+        });
+        let trace = cennznut.explain_runtime_call("balances", "burn_all", &[]);
+
+        assert_eq!(
+            trace.method,
+            Some(MatchedGrant {
+                key: "burn_*".to_string(),
+                matched: GrantMatch::Pattern,
+                block_cooldown: Some(3),
+            })
+        );
+        assert_eq!(trace.result, Ok(()));
+    }
+
+    #[test]
+    fn it_explains_unsatisfied_module_constraints() {
+        let module = Module::new("balances")
+            .constraint(&Constraint::arg(1).eq(PactType::Numeric(Numeric(7))))
+            .expect("it encodes")
+            .methods(vec![Method::new("*")].into());
+        let cennznut = CENNZnut::V1(CENNZnutV1 {
+            modules: vec![module].into(),
+            contracts: vec![].into(),
+        });
+
+        let trace = cennznut.explain_runtime_call("balances", "transfer", &args(100, 8));
+        assert_eq!(
+            trace.module_constraints,
+            Some(ConstraintsOutcome::Unsatisfied { clause: Some(0) })
+        );
+        assert_eq!(trace.constraints, None);
+        assert_eq!(
+            trace.result,
+            cennznut.validate_runtime_call("balances", "transfer", &args(100, 8))
+        );
+
+        let trace = cennznut.explain_runtime_call("balances", "transfer", &args(100, 7));
+        assert_eq!(
+            trace.to_string(),
+            "module: balances (exact), block cooldown: none\n\
+             method: * (wildcard), block cooldown: none\n\
+             module constraints: satisfied\n\
+             constraints: unconstrained\n\
+             result: permitted"
+        );
+    }
+
+    #[test]
+    fn it_explains_as_validation() {
+        let denials = Module::new("generic_asset").methods(
+            vec![
+                Method::new("*"),
+                Method::new("burn").deny(),
+                Method::new("mint").valid_until(10),
+            ]
+            .into(),
+        );
+        let constrained = Module::new("balances")
+            .constraint(&Constraint::arg(1).eq(PactType::Numeric(Numeric(7))))
+            .expect("it encodes")
+            .methods(vec![Method::new("transfer_*").pattern()].into());
+        let fixtures = vec![
+            make_cennznut(),
+            CENNZnut::V1(CENNZnutV1 {
+                modules: vec![denials, constrained, Module::new("staking").deny()].into(),
+                contracts: vec![].into(),
+            }),
+            CENNZnut::V0(CENNZnutV0 {
+                modules: vec![ModuleV0::new("balances").methods(vec![MethodV0::new("*")].into())]
+                    .into(),
+                contracts: vec![].into(),
+            }),
+        ];
+        let (permitted, too_large, wrong_asset) = (args(100, 7), args(101, 7), args(100, 8));
+        let calls: Vec<(&str, &str, &[PactType])> = vec![
+            ("balances", "transfer", &permitted),
+            ("balances", "transfer", &too_large),
+            ("balances", "transfer_all", &wrong_asset),
+            ("balances", "transfer_all", &[]),
+            ("generic_asset", "transfer", &[]),
+            ("generic_asset", "burn", &[]),
+            ("generic_asset", "mint", &[]),
+            ("staking", "bond", &[]),
+            ("system", "remark", &[]),
+        ];
+
+        for cennznut in &fixtures {
+            for (module, method, args) in &calls {
+                assert_eq!(
+                    cennznut.explain_runtime_call(module, method, args).result,
+                    cennznut.validate_runtime_call(module, method, args),
+                    "{}::{}",
+                    module,
+                    method
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_explains_denied_modules() {
+        let cennznut = CENNZnut::V1(CENNZnutV1 {
+            modules: vec![].into(),
+            contracts: vec![].into(),
+        });
+        let trace = cennznut.explain_runtime_call("balances", "transfer", &[]);
+
+        assert_eq!(
+            trace,
+            ValidationTrace {
+                module: None,
+                method: None,
+                module_constraints: None,
+                constraints: None,
+                result: Err(ValidationErr::NoPermission(RuntimeDomain::Module {
+                    module: "balances".into(),
+                })),
+            }
+        );
+        assert_eq!(
+            trace.to_string(),
+            "module: not granted\nresult: CENNZnut does not grant permission for module balances"
+        );
+    }
+}
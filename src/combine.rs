@@ -0,0 +1,53 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Combine
+//!
+//! Errors which may occur while combining `CENNZnuts`
+//!
+
+use crate::cennznut::{MethodName, ModuleName};
+use crate::EncodeError;
+use alloc::fmt::{self, Display, Formatter};
+
+/// Error which may occur while taking the intersection or union of `CENNZnuts`
+#[derive(Debug, Eq, PartialEq)]
+pub enum CombineError {
+    /// Both `CENNZnuts` constrain the method and the constraints cannot be combined
+    ConstraintsIncompatible {
+        module: ModuleName,
+        method: MethodName,
+    },
+    TooManyModules(usize),
+    TooManyMethods {
+        module: ModuleName,
+        count: usize,
+    },
+    TooManyContracts(usize),
+    /// The combined `CENNZnut` cannot be encoded, such as when it permits nothing or
+    /// a module without methods is required to shadow a wildcard module
+    Unencodable(EncodeError),
+}
+
+impl Display for CombineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConstraintsIncompatible { module, method } => write!(
+                f,
+                "method {}::{} constraints cannot be combined",
+                module, method
+            ),
+            Self::TooManyModules(count) => {
+                write!(f, "combined CENNZnut has too many modules: {}", count)
+            }
+            Self::TooManyMethods { module, count } => write!(
+                f,
+                "combined module {} has too many methods: {}",
+                module, count
+            ),
+            Self::TooManyContracts(count) => {
+                write!(f, "combined CENNZnut has too many contracts: {}", count)
+            }
+            Self::Unencodable(err) => write!(f, "combined CENNZnut cannot be encoded: {}", err),
+        }
+    }
+}
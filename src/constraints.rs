@@ -0,0 +1,294 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Constraints
+//!
+//! Construction and composition of Pact constraints
+//!
+
+use crate::EncodeError;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use pact::contract::{Contract as PactContract, DataTable};
+use pact::interpreter::{interpret, types::PactType, OpCode};
+
+/// The length of a bytecode clause: `[op, lhs source, lhs index, rhs source, rhs index]`
+const CLAUSE_LENGTH: usize = 5;
+/// The load source of a call argument index
+const INPUT_SOURCE: u8 = 0;
+/// The load source of a data table index
+const DATA_TABLE_SOURCE: u8 = 1;
+
+/// A comparison of a call argument with a value
+struct Clause<'a> {
+    op: u8,
+    arg: u8,
+    value: PactType<'a>,
+}
+
+/// Pact constraints on the arguments of a call, satisfied when all clauses are satisfied.
+///
+/// ```ignore
+/// let constraint = Constraint::arg(0)
+///     .eq(PactType::Numeric(Numeric(16_000)))
+///     .and(Constraint::arg(1).lte(PactType::Numeric(Numeric(1_000))));
+/// let method = Method::new("transfer").constraint(&constraint)?;
+/// ```
+pub struct Constraint<'a> {
+    clauses: Vec<Clause<'a>>,
+}
+
+/// A call argument to be compared, see `Constraint::arg`
+pub struct ConstraintArg {
+    index: u8,
+}
+
+impl<'a> Constraint<'a> {
+    /// Returns the call argument at `index` to be compared
+    pub fn arg(index: u8) -> ConstraintArg {
+        ConstraintArg { index }
+    }
+
+    /// Returns constraints satisfied when both these and `other` are satisfied
+    pub fn and(mut self, other: Self) -> Self {
+        self.clauses.extend(other.clauses);
+        self
+    }
+
+    /// Returns the Pact contract of the constraints
+    ///
+    /// # Errors
+    ///
+    /// Will return error if there are more clauses than data table indices
+    pub fn to_pact(&self) -> Result<PactContract<'a>, EncodeError> {
+        let mut bytecode = Vec::<u8>::with_capacity(self.clauses.len() * CLAUSE_LENGTH);
+        for (index, clause) in self.clauses.iter().enumerate() {
+            let index = u8::try_from(index).map_err(|_| EncodeError::ConstraintsTooLarge {
+                module: 0,
+                method: 0,
+            })?;
+            bytecode.push(clause.op);
+            bytecode.extend_from_slice(&[INPUT_SOURCE, clause.arg, DATA_TABLE_SOURCE, index]);
+        }
+        Ok(PactContract {
+            data_table: DataTable::new(
+                self.clauses
+                    .iter()
+                    .map(|clause| clause.value.clone())
+                    .collect(),
+            ),
+            bytecode,
+        })
+    }
+
+    /// Encodes the constraints as the bytes of a Pact contract
+    ///
+    /// # Errors
+    ///
+    /// Will return error if there are more clauses than data table indices
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut constraints = Vec::<u8>::default();
+        self.to_pact()?.encode(&mut constraints);
+        Ok(constraints)
+    }
+}
+
+impl ConstraintArg {
+    fn compare(self, op: u8, value: PactType) -> Constraint {
+        Constraint {
+            clauses: vec![Clause {
+                op,
+                arg: self.index,
+                value,
+            }],
+        }
+    }
+
+    /// The argument is equal to `value`
+    pub fn eq(self, value: PactType) -> Constraint {
+        self.compare(OpCode::EQ.into(), value)
+    }
+
+    /// The argument is greater than `value`
+    pub fn gt(self, value: PactType) -> Constraint {
+        self.compare(OpCode::GT.into(), value)
+    }
+
+    /// The argument is greater than or equal to `value`
+    pub fn gte(self, value: PactType) -> Constraint {
+        self.compare(OpCode::GTE.into(), value)
+    }
+
+    /// The argument is less than `value`
+    pub fn lt(self, value: PactType) -> Constraint {
+        self.compare(OpCode::LT.into(), value)
+    }
+
+    /// The argument is less than or equal to `value`
+    pub fn lte(self, value: PactType) -> Constraint {
+        self.compare(OpCode::LTE.into(), value)
+    }
+}
+
+/// Returns whether `bytecode` is made up only of comparison clauses,
+/// which the interpreter treats as a conjunction
+pub(crate) fn is_conjunction(bytecode: &[u8]) -> bool {
+    let comparisons: [u8; 5] = [
+        OpCode::EQ.into(),
+        OpCode::GT.into(),
+        OpCode::GTE.into(),
+        OpCode::LT.into(),
+        OpCode::LTE.into(),
+    ];
+    bytecode.len() % CLAUSE_LENGTH == 0
+        && bytecode
+            .chunks(CLAUSE_LENGTH)
+            .all(|clause| comparisons.contains(&clause[0]))
+}
+
+/// Returns the index of the first clause of `pact` which `args` do not satisfy.
+/// Returns `None` if all are satisfied or the bytecode is not a conjunction of comparisons.
+pub(crate) fn first_unsatisfied_clause(pact: &PactContract, args: &[PactType]) -> Option<usize> {
+    if !is_conjunction(&pact.bytecode) {
+        return None;
+    }
+    pact.bytecode.chunks(CLAUSE_LENGTH).position(|clause| {
+        interpret(args, pact.data_table.as_ref(), clause).map_or(false, |satisfied| !satisfied)
+    })
+}
+
+/// Combines constraints `a` and `b` into constraints which are satisfied only when both are.
+/// Returns `None` if either is not a conjunction of comparisons, or the combined data table is
+/// too large to index.
+pub(crate) fn conjoin(a: &[u8], b: &[u8]) -> Option<Vec<u8>> {
+    let a = PactContract::decode(a).ok()?;
+    let b = PactContract::decode(b).ok()?;
+    if !is_conjunction(&a.bytecode) || !is_conjunction(&b.bytecode) {
+        return None;
+    }
+
+    let offset = a.data_table.as_ref().len();
+    let data_table: Vec<_> = a
+        .data_table
+        .as_ref()
+        .iter()
+        .chain(b.data_table.as_ref().iter())
+        .cloned()
+        .collect();
+    if data_table.len() > usize::from(u8::max_value()) + 1 {
+        return None;
+    }
+
+    let mut bytecode = a.bytecode.clone();
+    for clause in b.bytecode.chunks(CLAUSE_LENGTH) {
+        bytecode.push(clause[0]);
+        for load in clause[1..].chunks(2) {
+            let index = if load[0] == DATA_TABLE_SOURCE {
+                u8::try_from(usize::from(load[1]) + offset).ok()?
+            } else {
+                load[1]
+            };
+            bytecode.push(load[0]);
+            bytecode.push(index);
+        }
+    }
+
+    let mut constraints = Vec::<u8>::default();
+    PactContract {
+        data_table: DataTable::new(data_table),
+        bytecode,
+    }
+    .encode(&mut constraints);
+    Some(constraints)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Constraint;
+    use crate::cennznut::{v0, v1};
+    use crate::EncodeError;
+    use pact::contract::{Contract as PactContract, DataTable};
+    use pact::interpreter::{interpret, OpCode};
+    use pact::types::{Numeric, PactType, StringLike};
+
+    #[test]
+    fn it_encodes_as_hand_assembled_bytecode() {
+        let pact = PactContract {
+            data_table: DataTable::new(vec![
+                PactType::Numeric(Numeric(123)),
+                PactType::StringLike(StringLike(b"test")),
+            ]),
+            bytecode: [OpCode::EQ.into(), 0, 0, 1, 0, OpCode::EQ.into(), 0, 1, 1, 1].to_vec(),
+        };
+        let mut expected: Vec<u8> = Vec::new();
+        pact.encode(&mut expected);
+
+        let constraint = Constraint::arg(0)
+            .eq(PactType::Numeric(Numeric(123)))
+            .and(Constraint::arg(1).eq(PactType::StringLike(StringLike(b"test"))));
+
+        assert_eq!(constraint.try_encode(), Ok(expected));
+    }
+
+    #[test]
+    fn it_interprets_comparisons() {
+        let constraint = Constraint::arg(0)
+            .gt(PactType::Numeric(Numeric(10)))
+            .and(Constraint::arg(0).lte(PactType::Numeric(Numeric(20))))
+            .and(Constraint::arg(1).gte(PactType::Numeric(Numeric(5))))
+            .and(Constraint::arg(1).lt(PactType::Numeric(Numeric(6))));
+        let pact = constraint.to_pact().expect("it builds");
+        let satisfied = |a: u64, b: u64| {
+            interpret(
+                &[PactType::Numeric(Numeric(a)), PactType::Numeric(Numeric(b))],
+                pact.data_table.as_ref(),
+                &pact.bytecode,
+            )
+        };
+
+        assert_eq!(satisfied(11, 5), Ok(true));
+        assert_eq!(satisfied(20, 5), Ok(true));
+        assert_eq!(satisfied(10, 5), Ok(false));
+        assert_eq!(satisfied(21, 5), Ok(false));
+        assert_eq!(satisfied(15, 6), Ok(false));
+    }
+
+    #[test]
+    fn it_sets_method_constraints() {
+        let constraint = Constraint::arg(0).eq(PactType::Numeric(Numeric(123)));
+        let method = v0::method::Method::new("method_test")
+            .constraint(&constraint)
+            .expect("it fits");
+
+        assert_eq!(method.constraints, constraint.try_encode().ok());
+    }
+
+    #[test]
+    fn it_checks_constraints_size() {
+        let value = [0x61_u8; 200];
+        let constraint = Constraint::arg(0)
+            .eq(PactType::StringLike(StringLike(&value)))
+            .and(Constraint::arg(1).eq(PactType::StringLike(StringLike(&value))));
+
+        assert!(matches!(
+            v0::method::Method::new("method_test").constraint(&constraint),
+            Err(EncodeError::ConstraintsTooLarge {
+                module: 0,
+                method: 0
+            })
+        ));
+        assert!(v1::method::Method::new("method_test")
+            .constraint(&constraint)
+            .is_ok());
+    }
+
+    #[test]
+    fn it_fails_with_too_many_clauses() {
+        let mut constraint = Constraint::arg(0).eq(PactType::Numeric(Numeric(0)));
+        for value in 1..=256 {
+            constraint = constraint.and(Constraint::arg(0).eq(PactType::Numeric(Numeric(value))));
+        }
+
+        assert!(constraint.to_pact().is_err());
+    }
+}
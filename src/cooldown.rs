@@ -0,0 +1,126 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Cooldown
+//!
+//! Call history used to enforce CENNZnut block cooldowns
+//!
+
+use crate::cennznut::{ContractAddress, MethodName, ModuleName};
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+
+/// Records the block number at which a CENNZnut permission was last used.
+/// Cooldowns are tracked per requested module, method and contract address.
+pub trait CooldownTracker {
+    /// Returns the block number `module_name` was last called at, if any
+    fn last_module_call(&self, module_name: &str) -> Option<u32>;
+    /// Returns the block number `module_name::method_name` was last called at, if any
+    fn last_method_call(&self, module_name: &str, method_name: &str) -> Option<u32>;
+    /// Returns the block number `contract_address` was last called at, if any
+    fn last_contract_call(&self, contract_address: &ContractAddress) -> Option<u32>;
+    /// Records a call to `module_name` at `block_number`
+    fn record_module_call(&mut self, module_name: &str, block_number: u32);
+    /// Records a call to `module_name::method_name` at `block_number`
+    fn record_method_call(&mut self, module_name: &str, method_name: &str, block_number: u32);
+    /// Records a call to `contract_address` at `block_number`
+    fn record_contract_call(&mut self, contract_address: &ContractAddress, block_number: u32);
+}
+
+/// An in-memory `CooldownTracker` for tests and off-chain simulation
+#[derive(Default)]
+pub struct InMemoryCooldownTracker {
+    modules: BTreeMap<ModuleName, u32>,
+    methods: BTreeMap<(ModuleName, MethodName), u32>,
+    contracts: BTreeMap<ContractAddress, u32>,
+}
+
+impl InMemoryCooldownTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CooldownTracker for InMemoryCooldownTracker {
+    fn last_module_call(&self, module_name: &str) -> Option<u32> {
+        self.modules.get(module_name).copied()
+    }
+
+    fn last_method_call(&self, module_name: &str, method_name: &str) -> Option<u32> {
+        self.methods
+            .get(&(module_name.to_string(), method_name.to_string()))
+            .copied()
+    }
+
+    fn last_contract_call(&self, contract_address: &ContractAddress) -> Option<u32> {
+        self.contracts.get(contract_address).copied()
+    }
+
+    fn record_module_call(&mut self, module_name: &str, block_number: u32) {
+        self.modules.insert(module_name.to_string(), block_number);
+    }
+
+    fn record_method_call(&mut self, module_name: &str, method_name: &str, block_number: u32) {
+        self.methods.insert(
+            (module_name.to_string(), method_name.to_string()),
+            block_number,
+        );
+    }
+
+    fn record_contract_call(&mut self, contract_address: &ContractAddress, block_number: u32) {
+        self.contracts.insert(*contract_address, block_number);
+    }
+}
+
+/// Returns whether `block_cooldown` has elapsed at `block_number` since `last_call`
+pub(crate) fn cooldown_elapsed(
+    block_cooldown: Option<u32>,
+    last_call: Option<u32>,
+    block_number: u32,
+) -> bool {
+    match (block_cooldown, last_call) {
+        (Some(cooldown), Some(last_call)) => last_call
+            .checked_add(cooldown)
+            .map_or(false, |available_at| block_number >= available_at),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cooldown_elapsed, CooldownTracker, InMemoryCooldownTracker};
+
+    #[test]
+    fn it_elapses_without_a_cooldown_or_history() {
+        assert!(cooldown_elapsed(None, Some(10), 10));
+        assert!(cooldown_elapsed(Some(5), None, 0));
+    }
+
+    #[test]
+    fn it_elapses_once_the_cooldown_has_passed() {
+        assert!(!cooldown_elapsed(Some(5), Some(10), 14));
+        assert!(cooldown_elapsed(Some(5), Some(10), 15));
+        assert!(!cooldown_elapsed(
+            Some(u32::max_value()),
+            Some(10),
+            u32::max_value()
+        ));
+    }
+
+    #[test]
+    fn it_records_calls() {
+        let mut tracker = InMemoryCooldownTracker::new();
+        tracker.record_module_call("module_test", 3);
+        tracker.record_method_call("module_test", "method_test", 4);
+        tracker.record_contract_call(&[0x12_u8; 32], 5);
+
+        assert_eq!(tracker.last_module_call("module_test"), Some(3));
+        assert_eq!(tracker.last_module_call("method_test"), None);
+        assert_eq!(
+            tracker.last_method_call("module_test", "method_test"),
+            Some(4)
+        );
+        assert_eq!(tracker.last_method_call("method_test", "module_test"), None);
+        assert_eq!(tracker.last_contract_call(&[0x12_u8; 32]), Some(5));
+        assert_eq!(tracker.last_contract_call(&[0x34_u8; 32]), None);
+    }
+}
@@ -0,0 +1,568 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Decode
+//!
+//! Errors which may occur while decoding a CENNZnut
+//!
+
+use crate::cennznut::v1::MAX_CENNZNUT_BYTES;
+use crate::RateLimit;
+use alloc::fmt::{self, Display, Formatter};
+use alloc::vec;
+use alloc::vec::Vec;
+use codec::Input;
+
+/// The position of a decoding error within the CENNZnut permission domains
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DecodePath {
+    pub module: Option<usize>,
+    pub method: Option<usize>,
+    pub contract: Option<usize>,
+}
+
+/// A field of the CENNZnut encoding
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeField {
+    Version,
+    ModuleCount,
+    ModuleHeader,
+    ModuleName,
+    ModuleCooldown,
+    MethodHeader,
+    MethodName,
+    MethodCooldown,
+    ConstraintsLength,
+    Constraints,
+    ContractCount,
+    ContractHeader,
+    ContractAddress,
+    ContractCooldown,
+    MethodCount,
+    SelectorCount,
+    Selector,
+    ModuleValidFrom,
+    ModuleValidUntil,
+    MethodValidFrom,
+    MethodValidUntil,
+    ContractValidFrom,
+    ContractValidUntil,
+    MethodMaxUses,
+    ContractMaxUses,
+    ModuleRateLimit,
+    MethodRateLimit,
+    ContractRateLimit,
+}
+
+/// Error which may occur while decoding a CENNZnut.
+/// `offset` is the position of the offending byte(s) in the decoded input.
+#[derive(Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    BadVersion {
+        offset: usize,
+        version: u16,
+    },
+    Truncated {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+    InvalidUtf8Name {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+    InvalidConstraints {
+        offset: usize,
+        path: DecodePath,
+    },
+    /// A name flagged as a pattern does not have a single `*` and other characters
+    InvalidPattern {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+    InvalidCompact {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+    TrailingBytes {
+        offset: usize,
+    },
+    /// A header sets bits with no meaning, only rejected by strict decoding
+    ReservedHeaderBits {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+    /// A name contains a NUL byte, only rejected by strict decoding
+    NulInName {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+    /// A set constraints flag with no constraints, only rejected by strict decoding
+    EmptyConstraints {
+        offset: usize,
+        path: DecodePath,
+    },
+    /// A module, method or contract repeats an earlier name or address,
+    /// only rejected by strict decoding
+    Duplicate {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+    /// A module, method or contract is not sorted after the earlier name or address,
+    /// only rejected by strict decoding
+    OutOfOrder {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+    /// A rate limit window of 0 blocks, which would never count a call
+    EmptyRateLimitWindow {
+        offset: usize,
+        path: DecodePath,
+        field: DecodeField,
+    },
+}
+
+impl DecodeError {
+    fn path_mut(&mut self) -> Option<&mut DecodePath> {
+        match self {
+            Self::Truncated { path, .. }
+            | Self::InvalidUtf8Name { path, .. }
+            | Self::InvalidConstraints { path, .. }
+            | Self::InvalidPattern { path, .. }
+            | Self::InvalidCompact { path, .. }
+            | Self::ReservedHeaderBits { path, .. }
+            | Self::NulInName { path, .. }
+            | Self::EmptyConstraints { path, .. }
+            | Self::Duplicate { path, .. }
+            | Self::OutOfOrder { path, .. }
+            | Self::EmptyRateLimitWindow { path, .. } => Some(path),
+            Self::BadVersion { .. } | Self::TrailingBytes { .. } => None,
+        }
+    }
+
+    /// Attributes the error to the module at `index`
+    pub(crate) fn in_module(mut self, index: usize) -> Self {
+        if let Some(path) = self.path_mut() {
+            path.module = Some(index);
+        }
+        self
+    }
+
+    /// Attributes the error to the method at `index`
+    pub(crate) fn in_method(mut self, index: usize) -> Self {
+        if let Some(path) = self.path_mut() {
+            path.method = Some(index);
+        }
+        self
+    }
+
+    /// Attributes the error to the contract at `index`
+    pub(crate) fn in_contract(mut self, index: usize) -> Self {
+        if let Some(path) = self.path_mut() {
+            path.contract = Some(index);
+        }
+        self
+    }
+}
+
+impl From<DecodeError> for codec::Error {
+    fn from(err: DecodeError) -> Self {
+        match err {
+            DecodeError::BadVersion { .. } => Self::from("unexpected version"),
+            DecodeError::Truncated { field, .. } => match field {
+                DecodeField::ModuleName => Self::from("expected 32 byte module name"),
+                DecodeField::MethodName => Self::from("expected 32 byte method name"),
+                DecodeField::ContractAddress => Self::from("expected 32 byte address"),
+                _ => Self::from("Not enough data to fill buffer"),
+            },
+            DecodeError::InvalidUtf8Name { field, .. } => match field {
+                DecodeField::MethodName => Self::from("method names should be utf8 encoded"),
+                _ => Self::from("module names should be utf8 encoded"),
+            },
+            DecodeError::InvalidConstraints { .. } => Self::from("invalid constraints codec"),
+            DecodeError::InvalidPattern { .. } => Self::from("invalid name pattern"),
+            DecodeError::InvalidCompact { .. } => Self::from("invalid compact length"),
+            DecodeError::TrailingBytes { .. } => Self::from("unexpected trailing bytes"),
+            DecodeError::ReservedHeaderBits { .. } => Self::from("reserved header bits are set"),
+            DecodeError::NulInName { .. } => Self::from("names should not contain NUL bytes"),
+            DecodeError::EmptyConstraints { .. } => Self::from("constraints should not be empty"),
+            DecodeError::Duplicate { field, .. } => match field {
+                DecodeField::MethodName => Self::from("duplicate method name"),
+                DecodeField::ContractAddress => Self::from("duplicate contract address"),
+                _ => Self::from("duplicate module name"),
+            },
+            DecodeError::OutOfOrder { field, .. } => match field {
+                DecodeField::MethodName => Self::from("method names should be sorted"),
+                DecodeField::ContractAddress => Self::from("contract addresses should be sorted"),
+                _ => Self::from("module names should be sorted"),
+            },
+            DecodeError::EmptyRateLimitWindow { .. } => {
+                Self::from("rate limit window should not be empty")
+            }
+        }
+    }
+}
+
+impl Display for DecodePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(module) = self.module {
+            write!(f, " in module {}", module)?;
+        }
+        if let Some(method) = self.method {
+            write!(f, " method {}", method)?;
+        }
+        if let Some(contract) = self.contract {
+            write!(f, " in contract {}", contract)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DecodeField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Version => write!(f, "version"),
+            Self::ModuleCount => write!(f, "module count"),
+            Self::ModuleHeader => write!(f, "module header"),
+            Self::ModuleName => write!(f, "module name"),
+            Self::ModuleCooldown => write!(f, "module block cooldown"),
+            Self::MethodHeader => write!(f, "method header"),
+            Self::MethodName => write!(f, "method name"),
+            Self::MethodCooldown => write!(f, "method block cooldown"),
+            Self::ConstraintsLength => write!(f, "constraints length"),
+            Self::Constraints => write!(f, "constraints"),
+            Self::ContractCount => write!(f, "contract count"),
+            Self::ContractHeader => write!(f, "contract header"),
+            Self::ContractAddress => write!(f, "contract address"),
+            Self::ContractCooldown => write!(f, "contract block cooldown"),
+            Self::MethodCount => write!(f, "method count"),
+            Self::SelectorCount => write!(f, "selector count"),
+            Self::Selector => write!(f, "selector"),
+            Self::ModuleValidFrom => write!(f, "module valid from block"),
+            Self::ModuleValidUntil => write!(f, "module valid until block"),
+            Self::MethodValidFrom => write!(f, "method valid from block"),
+            Self::MethodValidUntil => write!(f, "method valid until block"),
+            Self::ContractValidFrom => write!(f, "contract valid from block"),
+            Self::ContractValidUntil => write!(f, "contract valid until block"),
+            Self::MethodMaxUses => write!(f, "method max uses"),
+            Self::ContractMaxUses => write!(f, "contract max uses"),
+            Self::ModuleRateLimit => write!(f, "module rate limit"),
+            Self::MethodRateLimit => write!(f, "method rate limit"),
+            Self::ContractRateLimit => write!(f, "contract rate limit"),
+        }
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadVersion { offset, version } => {
+                write!(f, "unexpected version {} at byte {}", version, offset)
+            }
+            Self::Truncated {
+                offset,
+                path,
+                field,
+            } => write!(f, "expected {} at byte {}{}", field, offset, path),
+            Self::InvalidUtf8Name {
+                offset,
+                path,
+                field,
+            } => write!(
+                f,
+                "{} is not utf8 encoded at byte {}{}",
+                field, offset, path
+            ),
+            Self::InvalidConstraints { offset, path } => {
+                write!(f, "invalid constraints codec at byte {}{}", offset, path)
+            }
+            Self::InvalidPattern {
+                offset,
+                path,
+                field,
+            } => write!(f, "invalid {} pattern at byte {}{}", field, offset, path),
+            Self::InvalidCompact {
+                offset,
+                path,
+                field,
+            } => write!(f, "invalid compact {} at byte {}{}", field, offset, path),
+            Self::TrailingBytes { offset } => {
+                write!(f, "unexpected trailing bytes at byte {}", offset)
+            }
+            Self::ReservedHeaderBits {
+                offset,
+                path,
+                field,
+            } => write!(f, "reserved {} bits set at byte {}{}", field, offset, path),
+            Self::NulInName {
+                offset,
+                path,
+                field,
+            } => write!(f, "{} contains NUL at byte {}{}", field, offset, path),
+            Self::EmptyConstraints { offset, path } => {
+                write!(f, "empty constraints at byte {}{}", offset, path)
+            }
+            Self::Duplicate {
+                offset,
+                path,
+                field,
+            } => write!(f, "duplicate {} at byte {}{}", field, offset, path),
+            Self::OutOfOrder {
+                offset,
+                path,
+                field,
+            } => write!(f, "{} out of order at byte {}{}", field, offset, path),
+            Self::EmptyRateLimitWindow {
+                offset,
+                path,
+                field,
+            } => write!(f, "empty {} window at byte {}{}", field, offset, path),
+        }
+    }
+}
+
+/// A codec `Input` which tracks the byte offset of each read.
+/// A strict input additionally asks decoders to reject non-canonical encodings.
+pub(crate) struct DecodeInput<'a, I: Input> {
+    input: &'a mut I,
+    offset: usize,
+    strict: bool,
+}
+
+impl<'a, I: Input> DecodeInput<'a, I> {
+    pub fn new(input: &'a mut I) -> Self {
+        Self {
+            input,
+            offset: 0,
+            strict: false,
+        }
+    }
+
+    pub fn strict(input: &'a mut I) -> Self {
+        Self {
+            input,
+            offset: 0,
+            strict: true,
+        }
+    }
+
+    /// Returns whether non-canonical encodings should be rejected
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Checks that the `header` read at `offset` sets only the `known` bits
+    pub fn check_header(
+        &self,
+        header: u8,
+        known: u8,
+        offset: usize,
+        field: DecodeField,
+    ) -> Result<(), DecodeError> {
+        if self.strict && header & !known != 0 {
+            return Err(DecodeError::ReservedHeaderBits {
+                offset,
+                path: DecodePath::default(),
+                field,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the `name` read at `offset` has no NUL bytes other than trailing padding
+    pub fn check_name(
+        &self,
+        name: &[u8],
+        offset: usize,
+        field: DecodeField,
+    ) -> Result<(), DecodeError> {
+        if self.strict && name.iter().skip_while(|b| **b != 0).any(|b| *b != 0) {
+            return Err(DecodeError::NulInName {
+                offset,
+                path: DecodePath::default(),
+                field,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the entry `key` read at `offset` is sorted after the `previous` entry key,
+    /// so entries are unique and in canonical order
+    pub fn check_order<K: Ord + ?Sized>(
+        &self,
+        previous: Option<&K>,
+        key: &K,
+        offset: usize,
+        field: DecodeField,
+    ) -> Result<(), DecodeError> {
+        match previous {
+            Some(previous) if self.strict && previous == key => Err(DecodeError::Duplicate {
+                offset,
+                path: DecodePath::default(),
+                field,
+            }),
+            Some(previous) if self.strict && previous > key => Err(DecodeError::OutOfOrder {
+                offset,
+                path: DecodePath::default(),
+                field,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the number of bytes read so far
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn read_byte(&mut self, field: DecodeField) -> Result<u8, DecodeError> {
+        let byte = self.input.read_byte().map_err(|_| self.truncated(field))?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    pub fn read(&mut self, into: &mut [u8], field: DecodeField) -> Result<(), DecodeError> {
+        self.input.read(into).map_err(|_| self.truncated(field))?;
+        self.offset += into.len();
+        Ok(())
+    }
+
+    pub fn read_u32(&mut self, field: DecodeField) -> Result<u32, DecodeError> {
+        let mut buf = [0_u8; 4];
+        self.read(&mut buf, field)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Reads a rate limit, rejecting a window of 0 blocks
+    pub fn read_rate_limit(&mut self, field: DecodeField) -> Result<RateLimit, DecodeError> {
+        let calls = self.read_u32(field)?;
+        let blocks_offset = self.offset;
+        let blocks = self.read_u32(field)?;
+        if blocks == 0 {
+            return Err(DecodeError::EmptyRateLimitWindow {
+                offset: blocks_offset,
+                path: DecodePath::default(),
+                field,
+            });
+        }
+        Ok(RateLimit::new(calls, blocks))
+    }
+
+    /// Reads a canonically encoded `Compact<u32>`
+    pub fn read_compact_u32(&mut self, field: DecodeField) -> Result<u32, DecodeError> {
+        let offset = self.offset;
+        let invalid = || DecodeError::InvalidCompact {
+            offset,
+            path: DecodePath::default(),
+            field,
+        };
+        let prefix = self.read_byte(field)?;
+        match prefix & 0b11 {
+            0b00 => Ok(u32::from(prefix >> 2)),
+            0b01 => {
+                let value = u32::from(u16::from_le_bytes([prefix, self.read_byte(field)?]) >> 2);
+                if value > 0b0011_1111 {
+                    Ok(value)
+                } else {
+                    Err(invalid())
+                }
+            }
+            0b10 => {
+                let mut buf = [prefix, 0, 0, 0];
+                self.read(&mut buf[1..], field)?;
+                let value = u32::from_le_bytes(buf) >> 2;
+                if value > 0b0011_1111_1111_1111 {
+                    Ok(value)
+                } else {
+                    Err(invalid())
+                }
+            }
+            _ => {
+                if prefix >> 2 != 0 {
+                    return Err(invalid());
+                }
+                let value = self.read_u32(field)?;
+                if value > 0b0011_1111_1111_1111_1111_1111_1111_1111 {
+                    Ok(value)
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+
+    /// Reads a `Compact<u32>` length prefixed byte string
+    pub fn read_bytes(&mut self, field: DecodeField) -> Result<Vec<u8>, DecodeError> {
+        let length = self.read_compact_u32(field)? as usize;
+        // A length longer than any CENNZnut cannot be satisfied by the input
+        if length > MAX_CENNZNUT_BYTES {
+            return Err(self.truncated(field));
+        }
+        let mut buf = vec![0_u8; length];
+        self.read(&mut buf, field)?;
+        Ok(buf)
+    }
+
+    fn truncated(&self, field: DecodeField) -> DecodeError {
+        DecodeError::Truncated {
+            offset: self.offset,
+            path: DecodePath::default(),
+            field,
+        }
+    }
+}
+
+/// A reader over borrowed CENNZnut bytes which tracks the byte offset of each read.
+/// Errors match those of a `DecodeInput` over the same bytes.
+pub(crate) struct DecodeSlice<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> DecodeSlice<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    /// Returns the number of bytes read so far
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the bytes read since `offset`
+    pub fn read_since(&self, offset: usize) -> &'a [u8] {
+        &self.bytes[offset..self.offset]
+    }
+
+    pub fn read_byte(&mut self, field: DecodeField) -> Result<u8, DecodeError> {
+        Ok(self.take(1, field)?[0])
+    }
+
+    /// Returns the next `length` bytes without copying them
+    pub fn take(&mut self, length: usize, field: DecodeField) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .offset
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(DecodeError::Truncated {
+                offset: self.offset,
+                path: DecodePath::default(),
+                field,
+            })?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+
+    pub fn read_u32(&mut self, field: DecodeField) -> Result<u32, DecodeError> {
+        let mut buf = [0_u8; 4];
+        buf.copy_from_slice(self.take(4, field)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+}
@@ -0,0 +1,396 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Diff
+//!
+//! Permission changes between two `CENNZnuts`
+//!
+
+use crate::attenuation::write_hex;
+use crate::cennznut::{ContractAddress, ContractSelector, MethodName, ModuleName};
+use crate::RateLimit;
+use alloc::fmt::{self, Display, Formatter};
+use alloc::vec::Vec;
+
+/// The first and last blocks a grant may be used at, if bounded
+pub type Validity = (Option<u32>, Option<u32>);
+
+/// A change to a permission grant
+#[derive(Debug, Eq, PartialEq)]
+pub enum PermissionChange {
+    ModuleAdded {
+        module: ModuleName,
+    },
+    ModuleRemoved {
+        module: ModuleName,
+    },
+    ModuleDenied {
+        module: ModuleName,
+    },
+    ModuleUndenied {
+        module: ModuleName,
+    },
+    ModuleCooldownChanged {
+        module: ModuleName,
+        from: Option<u32>,
+        to: Option<u32>,
+    },
+    ModuleValidityChanged {
+        module: ModuleName,
+        from: Validity,
+        to: Validity,
+    },
+    ModuleRateLimitChanged {
+        module: ModuleName,
+        from: Option<RateLimit>,
+        to: Option<RateLimit>,
+    },
+    ModuleConstraintsChanged {
+        module: ModuleName,
+        from: Option<Vec<u8>>,
+        to: Option<Vec<u8>>,
+    },
+    MethodAdded {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodRemoved {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodDenied {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodUndenied {
+        module: ModuleName,
+        method: MethodName,
+    },
+    MethodCooldownChanged {
+        module: ModuleName,
+        method: MethodName,
+        from: Option<u32>,
+        to: Option<u32>,
+    },
+    MethodValidityChanged {
+        module: ModuleName,
+        method: MethodName,
+        from: Validity,
+        to: Validity,
+    },
+    MethodMaxUsesChanged {
+        module: ModuleName,
+        method: MethodName,
+        from: Option<u32>,
+        to: Option<u32>,
+    },
+    MethodRateLimitChanged {
+        module: ModuleName,
+        method: MethodName,
+        from: Option<RateLimit>,
+        to: Option<RateLimit>,
+    },
+    ConstraintsChanged {
+        module: ModuleName,
+        method: MethodName,
+        from: Option<Vec<u8>>,
+        to: Option<Vec<u8>>,
+    },
+    ContractAdded {
+        contract: ContractAddress,
+    },
+    ContractRemoved {
+        contract: ContractAddress,
+    },
+    ContractDenied {
+        contract: ContractAddress,
+    },
+    ContractUndenied {
+        contract: ContractAddress,
+    },
+    ContractCooldownChanged {
+        contract: ContractAddress,
+        from: Option<u32>,
+        to: Option<u32>,
+    },
+    ContractValidityChanged {
+        contract: ContractAddress,
+        from: Validity,
+        to: Validity,
+    },
+    ContractMaxUsesChanged {
+        contract: ContractAddress,
+        from: Option<u32>,
+        to: Option<u32>,
+    },
+    ContractRateLimitChanged {
+        contract: ContractAddress,
+        from: Option<RateLimit>,
+        to: Option<RateLimit>,
+    },
+    ContractSelectorsChanged {
+        contract: ContractAddress,
+        from: Option<Vec<ContractSelector>>,
+        to: Option<Vec<ContractSelector>>,
+    },
+    ContractConstraintsChanged {
+        contract: ContractAddress,
+        from: Option<Vec<u8>>,
+        to: Option<Vec<u8>>,
+    },
+}
+
+/// The permission changes from one CENNZnut to another.
+/// Modules and methods are ordered by name and contracts by address.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct PermissionDiff {
+    pub changes: Vec<PermissionChange>,
+}
+
+impl PermissionDiff {
+    /// Returns whether both `CENNZnuts` grant the same permissions
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Writes a block cooldown, or `none`
+fn write_cooldown(f: &mut Formatter<'_>, cooldown: Option<u32>) -> fmt::Result {
+    match cooldown {
+        Some(cooldown) => write!(f, "{}", cooldown),
+        None => write!(f, "none"),
+    }
+}
+
+/// Writes a usage quota, or `unlimited`
+fn write_max_uses(f: &mut Formatter<'_>, max_uses: Option<u32>) -> fmt::Result {
+    match max_uses {
+        Some(max_uses) => write!(f, "{}", max_uses),
+        None => write!(f, "unlimited"),
+    }
+}
+
+/// Writes a rate limit as calls per window of blocks, or `none`
+fn write_rate_limit(f: &mut Formatter<'_>, rate_limit: Option<RateLimit>) -> fmt::Result {
+    match rate_limit {
+        Some(rate_limit) => write!(f, "{} per {} blocks", rate_limit.calls, rate_limit.blocks),
+        None => write!(f, "none"),
+    }
+}
+
+/// Writes a validity window as an inclusive range of blocks, or `any`
+fn write_validity(f: &mut Formatter<'_>, validity: Validity) -> fmt::Result {
+    match validity {
+        (None, None) => write!(f, "any"),
+        (Some(from), None) => write!(f, "{}..", from),
+        (None, Some(until)) => write!(f, "..={}", until),
+        (Some(from), Some(until)) => write!(f, "{}..={}", from, until),
+    }
+}
+
+/// Writes a summary of constraints, or `none`
+fn write_constraints(f: &mut Formatter<'_>, constraints: Option<&Vec<u8>>) -> fmt::Result {
+    match constraints {
+        Some(constraints) => write!(f, "{} bytes", constraints.len()),
+        None => write!(f, "none"),
+    }
+}
+
+/// Writes a list of selectors, or `any`
+fn write_selectors(
+    f: &mut Formatter<'_>,
+    selectors: Option<&Vec<ContractSelector>>,
+) -> fmt::Result {
+    match selectors {
+        Some(selectors) => {
+            write!(f, "[")?;
+            for (index, selector) in selectors.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                write_hex(f, selector)?;
+            }
+            write!(f, "]")
+        }
+        None => write!(f, "any"),
+    }
+}
+
+impl Display for PermissionChange {
+    #[allow(clippy::too_many_lines)]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ModuleAdded { module } => write!(f, "+ module {}", module),
+            Self::ModuleRemoved { module } => write!(f, "- module {}", module),
+            Self::ModuleDenied { module } => write!(f, "+ deny module {}", module),
+            Self::ModuleUndenied { module } => write!(f, "- deny module {}", module),
+            Self::ModuleCooldownChanged { module, from, to } => {
+                write!(f, "~ module {} block cooldown: ", module)?;
+                write_cooldown(f, *from)?;
+                write!(f, " -> ")?;
+                write_cooldown(f, *to)
+            }
+            Self::ModuleValidityChanged { module, from, to } => {
+                write!(f, "~ module {} validity: ", module)?;
+                write_validity(f, *from)?;
+                write!(f, " -> ")?;
+                write_validity(f, *to)
+            }
+            Self::ModuleRateLimitChanged { module, from, to } => {
+                write!(f, "~ module {} rate limit: ", module)?;
+                write_rate_limit(f, *from)?;
+                write!(f, " -> ")?;
+                write_rate_limit(f, *to)
+            }
+            Self::ModuleConstraintsChanged { module, from, to } => {
+                write!(f, "~ module {} constraints: ", module)?;
+                write_constraints(f, from.as_ref())?;
+                write!(f, " -> ")?;
+                write_constraints(f, to.as_ref())
+            }
+            Self::MethodAdded { module, method } => write!(f, "+ method {}::{}", module, method),
+            Self::MethodRemoved { module, method } => {
+                write!(f, "- method {}::{}", module, method)
+            }
+            Self::MethodDenied { module, method } => {
+                write!(f, "+ deny method {}::{}", module, method)
+            }
+            Self::MethodUndenied { module, method } => {
+                write!(f, "- deny method {}::{}", module, method)
+            }
+            Self::MethodCooldownChanged {
+                module,
+                method,
+                from,
+                to,
+            } => {
+                write!(f, "~ method {}::{} block cooldown: ", module, method)?;
+                write_cooldown(f, *from)?;
+                write!(f, " -> ")?;
+                write_cooldown(f, *to)
+            }
+            Self::MethodValidityChanged {
+                module,
+                method,
+                from,
+                to,
+            } => {
+                write!(f, "~ method {}::{} validity: ", module, method)?;
+                write_validity(f, *from)?;
+                write!(f, " -> ")?;
+                write_validity(f, *to)
+            }
+            Self::MethodMaxUsesChanged {
+                module,
+                method,
+                from,
+                to,
+            } => {
+                write!(f, "~ method {}::{} max uses: ", module, method)?;
+                write_max_uses(f, *from)?;
+                write!(f, " -> ")?;
+                write_max_uses(f, *to)
+            }
+            Self::MethodRateLimitChanged {
+                module,
+                method,
+                from,
+                to,
+            } => {
+                write!(f, "~ method {}::{} rate limit: ", module, method)?;
+                write_rate_limit(f, *from)?;
+                write!(f, " -> ")?;
+                write_rate_limit(f, *to)
+            }
+            Self::ConstraintsChanged {
+                module,
+                method,
+                from,
+                to,
+            } => {
+                write!(f, "~ method {}::{} constraints: ", module, method)?;
+                write_constraints(f, from.as_ref())?;
+                write!(f, " -> ")?;
+                write_constraints(f, to.as_ref())
+            }
+            Self::ContractAdded { contract } => {
+                write!(f, "+ contract ")?;
+                write_hex(f, contract)
+            }
+            Self::ContractRemoved { contract } => {
+                write!(f, "- contract ")?;
+                write_hex(f, contract)
+            }
+            Self::ContractDenied { contract } => {
+                write!(f, "+ deny contract ")?;
+                write_hex(f, contract)
+            }
+            Self::ContractUndenied { contract } => {
+                write!(f, "- deny contract ")?;
+                write_hex(f, contract)
+            }
+            Self::ContractCooldownChanged { contract, from, to } => {
+                write!(f, "~ contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " block cooldown: ")?;
+                write_cooldown(f, *from)?;
+                write!(f, " -> ")?;
+                write_cooldown(f, *to)
+            }
+            Self::ContractValidityChanged { contract, from, to } => {
+                write!(f, "~ contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " validity: ")?;
+                write_validity(f, *from)?;
+                write!(f, " -> ")?;
+                write_validity(f, *to)
+            }
+            Self::ContractMaxUsesChanged { contract, from, to } => {
+                write!(f, "~ contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " max uses: ")?;
+                write_max_uses(f, *from)?;
+                write!(f, " -> ")?;
+                write_max_uses(f, *to)
+            }
+            Self::ContractRateLimitChanged { contract, from, to } => {
+                write!(f, "~ contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " rate limit: ")?;
+                write_rate_limit(f, *from)?;
+                write!(f, " -> ")?;
+                write_rate_limit(f, *to)
+            }
+            Self::ContractSelectorsChanged { contract, from, to } => {
+                write!(f, "~ contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " selectors: ")?;
+                write_selectors(f, from.as_ref())?;
+                write!(f, " -> ")?;
+                write_selectors(f, to.as_ref())
+            }
+            Self::ContractConstraintsChanged { contract, from, to } => {
+                write!(f, "~ contract ")?;
+                write_hex(f, contract)?;
+                write!(f, " constraints: ")?;
+                write_constraints(f, from.as_ref())?;
+                write!(f, " -> ")?;
+                write_constraints(f, to.as_ref())
+            }
+        }
+    }
+}
+
+impl Display for PermissionDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return write!(f, "no permission changes");
+        }
+        for (index, change) in self.changes.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
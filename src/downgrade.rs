@@ -0,0 +1,222 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Downgrade
+//!
+//! Errors which may occur while converting a CENNZnut to an earlier version
+//!
+
+use crate::EncodeError;
+use alloc::fmt::{self, Display, Formatter};
+
+/// Error which may occur while downgrading a CENNZnut.
+/// Module, method and contract indices are positions within the downgraded CENNZnut,
+/// they are `0` when downgrading a standalone `Module`, `Method` or `Contract`.
+#[derive(Debug, Eq, PartialEq)]
+pub enum DowngradeError {
+    /// The CENNZnut exceeds an encoding limit of the earlier version
+    Unencodable(EncodeError),
+    /// A module name starting or ending with nul bytes would be trimmed by zero padding
+    ModuleNameNulPadded { module: usize },
+    /// A method name starting or ending with nul bytes would be trimmed by zero padding
+    MethodNameNulPadded { module: usize, method: usize },
+    /// A module name pattern has no equivalent in the earlier version
+    ModulePattern { module: usize },
+    /// A method name pattern has no equivalent in the earlier version
+    MethodPattern { module: usize, method: usize },
+    /// A module deny entry has no equivalent in the earlier version
+    ModuleDeny { module: usize },
+    /// A method deny entry has no equivalent in the earlier version
+    MethodDeny { module: usize, method: usize },
+    /// A contract deny entry has no equivalent in the earlier version
+    ContractDeny { contract: usize },
+    /// A contract selector allow-list has no equivalent in the earlier version
+    ContractSelectors { contract: usize },
+    /// Contract argument constraints have no equivalent in the earlier version
+    ContractConstraints { contract: usize },
+    /// A module validity window has no equivalent in the earlier version
+    ModuleValidity { module: usize },
+    /// A method validity window has no equivalent in the earlier version
+    MethodValidity { module: usize, method: usize },
+    /// A contract validity window has no equivalent in the earlier version
+    ContractValidity { contract: usize },
+    /// A method usage quota has no equivalent in the earlier version
+    MethodMaxUses { module: usize, method: usize },
+    /// A contract usage quota has no equivalent in the earlier version
+    ContractMaxUses { contract: usize },
+    /// A module rate limit has no equivalent in the earlier version
+    ModuleRateLimit { module: usize },
+    /// Module constraints have no equivalent in the earlier version
+    ModuleConstraints { module: usize },
+    /// A method rate limit has no equivalent in the earlier version
+    MethodRateLimit { module: usize, method: usize },
+    /// A contract rate limit has no equivalent in the earlier version
+    ContractRateLimit { contract: usize },
+}
+
+impl DowngradeError {
+    /// Attributes a module level error to the module at `index`
+    pub(crate) fn in_module(self, index: usize) -> Self {
+        match self {
+            Self::Unencodable(e) => Self::Unencodable(e.in_module(index)),
+            Self::ModuleNameNulPadded { .. } => Self::ModuleNameNulPadded { module: index },
+            Self::MethodNameNulPadded { method, .. } => Self::MethodNameNulPadded {
+                module: index,
+                method,
+            },
+            Self::ModulePattern { .. } => Self::ModulePattern { module: index },
+            Self::MethodPattern { method, .. } => Self::MethodPattern {
+                module: index,
+                method,
+            },
+            Self::ModuleDeny { .. } => Self::ModuleDeny { module: index },
+            Self::MethodDeny { method, .. } => Self::MethodDeny {
+                module: index,
+                method,
+            },
+            Self::ModuleValidity { .. } => Self::ModuleValidity { module: index },
+            Self::MethodValidity { method, .. } => Self::MethodValidity {
+                module: index,
+                method,
+            },
+            Self::MethodMaxUses { method, .. } => Self::MethodMaxUses {
+                module: index,
+                method,
+            },
+            Self::ModuleRateLimit { .. } => Self::ModuleRateLimit { module: index },
+            Self::ModuleConstraints { .. } => Self::ModuleConstraints { module: index },
+            Self::MethodRateLimit { method, .. } => Self::MethodRateLimit {
+                module: index,
+                method,
+            },
+            e => e,
+        }
+    }
+
+    /// Attributes a method level error to the method at `index`
+    pub(crate) fn in_method(self, index: usize) -> Self {
+        match self {
+            Self::Unencodable(e) => Self::Unencodable(e.in_method(index)),
+            Self::MethodNameNulPadded { module, .. } => Self::MethodNameNulPadded {
+                module,
+                method: index,
+            },
+            Self::MethodPattern { module, .. } => Self::MethodPattern {
+                module,
+                method: index,
+            },
+            Self::MethodDeny { module, .. } => Self::MethodDeny {
+                module,
+                method: index,
+            },
+            Self::MethodValidity { module, .. } => Self::MethodValidity {
+                module,
+                method: index,
+            },
+            Self::MethodMaxUses { module, .. } => Self::MethodMaxUses {
+                module,
+                method: index,
+            },
+            Self::MethodRateLimit { module, .. } => Self::MethodRateLimit {
+                module,
+                method: index,
+            },
+            e => e,
+        }
+    }
+
+    /// Attributes a contract level error to the contract at `index`
+    pub(crate) fn in_contract(self, index: usize) -> Self {
+        match self {
+            Self::ContractDeny { .. } => Self::ContractDeny { contract: index },
+            Self::ContractSelectors { .. } => Self::ContractSelectors { contract: index },
+            Self::ContractConstraints { .. } => Self::ContractConstraints { contract: index },
+            Self::ContractValidity { .. } => Self::ContractValidity { contract: index },
+            Self::ContractMaxUses { .. } => Self::ContractMaxUses { contract: index },
+            Self::ContractRateLimit { .. } => Self::ContractRateLimit { contract: index },
+            e => e,
+        }
+    }
+}
+
+impl From<EncodeError> for DowngradeError {
+    fn from(err: EncodeError) -> Self {
+        Self::Unencodable(err)
+    }
+}
+
+impl Display for DowngradeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unencodable(err) => write!(f, "{}", err),
+            Self::ModuleNameNulPadded { module } => {
+                write!(f, "module {} name would be trimmed of nul bytes", module)
+            }
+            Self::MethodNameNulPadded { module, method } => write!(
+                f,
+                "module {} method {} name would be trimmed of nul bytes",
+                module, method
+            ),
+            Self::ModulePattern { module } => {
+                write!(f, "module {} name pattern is not supported", module)
+            }
+            Self::MethodPattern { module, method } => write!(
+                f,
+                "module {} method {} name pattern is not supported",
+                module, method
+            ),
+            Self::ModuleDeny { module } => write!(f, "module {} deny is not supported", module),
+            Self::MethodDeny { module, method } => write!(
+                f,
+                "module {} method {} deny is not supported",
+                module, method
+            ),
+            Self::ContractDeny { contract } => {
+                write!(f, "contract {} deny is not supported", contract)
+            }
+            Self::ContractSelectors { contract } => {
+                write!(f, "contract {} selectors are not supported", contract)
+            }
+            Self::ContractConstraints { contract } => {
+                write!(f, "contract {} constraints are not supported", contract)
+            }
+            Self::ModuleValidity { module } => {
+                write!(f, "module {} validity window is not supported", module)
+            }
+            Self::MethodValidity { module, method } => write!(
+                f,
+                "module {} method {} validity window is not supported",
+                module, method
+            ),
+            Self::ContractValidity { contract } => {
+                write!(f, "contract {} validity window is not supported", contract)
+            }
+            Self::MethodMaxUses { module, method } => write!(
+                f,
+                "module {} method {} usage quota is not supported",
+                module, method
+            ),
+            Self::ContractMaxUses { contract } => {
+                write!(f, "contract {} usage quota is not supported", contract)
+            }
+            Self::ModuleRateLimit { module } => {
+                write!(f, "module {} rate limit is not supported", module)
+            }
+            Self::ModuleConstraints { module } => {
+                write!(f, "module {} constraints are not supported", module)
+            }
+            Self::MethodRateLimit { module, method } => write!(
+                f,
+                "module {} method {} rate limit is not supported",
+                module, method
+            ),
+            Self::ContractRateLimit { contract } => {
+                write!(f, "contract {} rate limit is not supported", contract)
+            }
+        }
+    }
+}
+
+/// Returns whether a zero padded `name` would decode to a different name
+pub(crate) fn is_nul_padded(name: &str) -> bool {
+    name.trim_matches(char::from(0)).len() != name.len()
+}
@@ -0,0 +1,184 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Encode
+//!
+//! Errors which may occur while encoding a CENNZnut
+//!
+
+use alloc::fmt::{self, Display, Formatter};
+
+/// Error which may occur while encoding a CENNZnut.
+/// Module, method and contract indices are positions within the encoded CENNZnut,
+/// they are `0` when encoding a standalone `Module`, `Method` or `Contract`.
+#[derive(Debug, Eq, PartialEq)]
+pub enum EncodeError {
+    NoModules,
+    TooManyModules(usize),
+    TooManyContracts(usize),
+    NoMethods {
+        module: usize,
+    },
+    TooManyMethods {
+        module: usize,
+        count: usize,
+    },
+    ModuleNameTooLong {
+        module: usize,
+    },
+    MethodNameTooLong {
+        module: usize,
+        method: usize,
+    },
+    ConstraintsTooLarge {
+        module: usize,
+        method: usize,
+    },
+    /// A module name flagged as a pattern does not have a single `*` and other characters
+    InvalidModulePattern {
+        module: usize,
+    },
+    /// A method name flagged as a pattern does not have a single `*` and other characters
+    InvalidMethodPattern {
+        module: usize,
+        method: usize,
+    },
+    /// A module rate limit window of 0 blocks, which would never count a call
+    EmptyModuleRateLimitWindow {
+        module: usize,
+    },
+    /// A method rate limit window of 0 blocks, which would never count a call
+    EmptyMethodRateLimitWindow {
+        module: usize,
+        method: usize,
+    },
+    /// A contract rate limit window of 0 blocks, which would never count a call
+    EmptyContractRateLimitWindow {
+        contract: usize,
+    },
+    /// A count or byte length is larger than a `Compact<u32>` can encode
+    CountOverflow(usize),
+    /// The encoded CENNZnut is longer than the maximum number of bytes
+    TooLarge(usize),
+}
+
+impl EncodeError {
+    /// Attributes a module level error to the module at `index`
+    pub(crate) fn in_module(self, index: usize) -> Self {
+        match self {
+            Self::NoMethods { .. } => Self::NoMethods { module: index },
+            Self::TooManyMethods { count, .. } => Self::TooManyMethods {
+                module: index,
+                count,
+            },
+            Self::ModuleNameTooLong { .. } => Self::ModuleNameTooLong { module: index },
+            Self::MethodNameTooLong { method, .. } => Self::MethodNameTooLong {
+                module: index,
+                method,
+            },
+            Self::ConstraintsTooLarge { method, .. } => Self::ConstraintsTooLarge {
+                module: index,
+                method,
+            },
+            Self::InvalidModulePattern { .. } => Self::InvalidModulePattern { module: index },
+            Self::InvalidMethodPattern { method, .. } => Self::InvalidMethodPattern {
+                module: index,
+                method,
+            },
+            Self::EmptyModuleRateLimitWindow { .. } => {
+                Self::EmptyModuleRateLimitWindow { module: index }
+            }
+            Self::EmptyMethodRateLimitWindow { method, .. } => Self::EmptyMethodRateLimitWindow {
+                module: index,
+                method,
+            },
+            e => e,
+        }
+    }
+
+    /// Attributes a method level error to the method at `index`
+    pub(crate) fn in_method(self, index: usize) -> Self {
+        match self {
+            Self::MethodNameTooLong { module, .. } => Self::MethodNameTooLong {
+                module,
+                method: index,
+            },
+            Self::ConstraintsTooLarge { module, .. } => Self::ConstraintsTooLarge {
+                module,
+                method: index,
+            },
+            Self::InvalidMethodPattern { module, .. } => Self::InvalidMethodPattern {
+                module,
+                method: index,
+            },
+            Self::EmptyMethodRateLimitWindow { module, .. } => Self::EmptyMethodRateLimitWindow {
+                module,
+                method: index,
+            },
+            e => e,
+        }
+    }
+
+    /// Attributes a contract level error to the contract at `index`
+    pub(crate) fn in_contract(self, index: usize) -> Self {
+        match self {
+            Self::EmptyContractRateLimitWindow { .. } => {
+                Self::EmptyContractRateLimitWindow { contract: index }
+            }
+            e => e,
+        }
+    }
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoModules => write!(f, "CENNZnut must contain at least 1 module"),
+            Self::TooManyModules(count) => write!(f, "CENNZnut has too many modules: {}", count),
+            Self::TooManyContracts(count) => {
+                write!(f, "CENNZnut has too many contracts: {}", count)
+            }
+            Self::NoMethods { module } => {
+                write!(f, "module {} must contain at least 1 method", module)
+            }
+            Self::TooManyMethods { module, count } => {
+                write!(f, "module {} has too many methods: {}", module, count)
+            }
+            Self::ModuleNameTooLong { module } => {
+                write!(f, "module {} name is longer than 32 bytes", module)
+            }
+            Self::MethodNameTooLong { module, method } => write!(
+                f,
+                "module {} method {} name is longer than 32 bytes",
+                module, method
+            ),
+            Self::ConstraintsTooLarge { module, method } => write!(
+                f,
+                "module {} method {} constraints are too large",
+                module, method
+            ),
+            Self::InvalidModulePattern { module } => {
+                write!(f, "module {} name is an invalid pattern", module)
+            }
+            Self::InvalidMethodPattern { module, method } => write!(
+                f,
+                "module {} method {} name is an invalid pattern",
+                module, method
+            ),
+            Self::EmptyModuleRateLimitWindow { module } => {
+                write!(f, "module {} rate limit window is empty", module)
+            }
+            Self::EmptyMethodRateLimitWindow { module, method } => write!(
+                f,
+                "module {} method {} rate limit window is empty",
+                module, method
+            ),
+            Self::EmptyContractRateLimitWindow { contract } => {
+                write!(f, "contract {} rate limit window is empty", contract)
+            }
+            Self::CountOverflow(count) => {
+                write!(f, "count or length is too large to encode: {}", count)
+            }
+            Self::TooLarge(length) => write!(f, "CENNZnut is too large: {} bytes", length),
+        }
+    }
+}
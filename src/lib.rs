@@ -18,16 +18,49 @@ extern crate std as alloc;
 use codec::Input;
 pub use core::convert::TryFrom;
 
+mod attenuation;
+mod call_schema;
+mod canonicalize;
 mod cennznut;
+mod combine;
+mod constraints;
+mod cooldown;
+mod decode;
+mod diff;
+mod downgrade;
+mod encode;
+mod pattern;
+mod policy;
+mod rate_limit;
+mod trace;
+mod usage;
 mod validation;
 
+pub use crate::attenuation::AttenuationErr;
+pub use crate::call_schema::{ArgType, CallDecodeError, CallSchema};
+pub use crate::canonicalize::CanonicalizeError;
 pub use crate::cennznut::ContractDomain;
 pub use crate::cennznut::RuntimeDomain;
 
 pub use crate::cennznut::v0;
+pub use crate::cennznut::v1;
 
+pub use crate::cennznut::v0::borrowed::CENNZnutRef;
+pub use crate::cennznut::v0::index::CENNZnutIndex;
 pub use crate::cennznut::v0::CENNZnutV0;
+pub use crate::cennznut::v1::CENNZnutV1;
 pub use crate::cennznut::CENNZnut;
+pub use crate::combine::CombineError;
+pub use crate::constraints::{Constraint, ConstraintArg};
+pub use crate::cooldown::{CooldownTracker, InMemoryCooldownTracker};
+pub use crate::decode::{DecodeError, DecodeField, DecodePath};
+pub use crate::diff::{PermissionChange, PermissionDiff, Validity};
+pub use crate::downgrade::DowngradeError;
+pub use crate::encode::EncodeError;
+pub use crate::policy::{PolicyError, PolicyErrorKind};
+pub use crate::rate_limit::{CallHistory, InMemoryCallHistory, RateLimit};
+pub use crate::trace::{ConstraintsOutcome, GrantMatch, MatchedGrant, ValidationTrace};
+pub use crate::usage::{GrantPath, InMemoryUsageCounter, NutHash, UsageCounter};
 pub use crate::validation::ValidationErr;
 
 mod test;
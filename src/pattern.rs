@@ -0,0 +1,139 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Pattern
+//!
+//! Matching of module and method names against name patterns
+//!
+
+use crate::cennznut::WILDCARD;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::iter::once;
+
+const PATTERN_WILDCARD: char = '*';
+
+/// Returns whether `pattern` is a valid name pattern.
+/// A pattern has a single `*` and at least one other character, as `*` alone is the wildcard.
+pub(crate) fn is_valid_pattern(pattern: &str) -> bool {
+    pattern.len() > 1 && pattern.matches(PATTERN_WILDCARD).count() == 1
+}
+
+/// Returns the characters before and after the `*`, if `pattern` is valid
+fn split(pattern: &str) -> Option<(&str, &str)> {
+    if !is_valid_pattern(pattern) {
+        return None;
+    }
+    pattern
+        .find(PATTERN_WILDCARD)
+        .map(|index| (&pattern[..index], &pattern[index + 1..]))
+}
+
+/// Returns whether `name` matches `pattern`, the `*` matching any sequence of characters.
+/// An invalid pattern matches no name.
+pub(crate) fn pattern_matches(pattern: &str, name: &str) -> bool {
+    match split(pattern) {
+        Some((prefix, suffix)) => {
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        }
+        None => false,
+    }
+}
+
+/// Returns whether the entry `key` applies to `name`, as an exact name, a pattern or the
+/// wildcard
+pub(crate) fn key_matches(key: &str, is_pattern: bool, name: &str) -> bool {
+    if is_pattern {
+        pattern_matches(key, name)
+    } else {
+        key == name || key == WILDCARD
+    }
+}
+
+/// Returns the key and entry granting `name` from `entries` of key, whether the key is a
+/// pattern, and entry.
+/// An exact entry takes precedence, then the longest matching pattern (the first listed of
+/// equally long patterns), then the wildcard entry.
+pub(crate) fn resolve<'a, T>(
+    entries: impl Iterator<Item = (&'a str, bool, &'a T)>,
+    name: &str,
+) -> Option<(&'a str, &'a T)> {
+    let mut pattern: Option<(&'a str, &'a T)> = None;
+    let mut wildcard: Option<(&'a str, &'a T)> = None;
+    for (key, is_pattern, entry) in entries {
+        if is_pattern {
+            let more_specific = pattern.map_or(true, |(matched, _)| key.len() > matched.len());
+            if more_specific && pattern_matches(key, name) {
+                pattern = Some((key, entry));
+            }
+        } else if key == name {
+            return Some((key, entry));
+        } else if key == WILDCARD {
+            wildcard = Some((key, entry));
+        }
+    }
+    pattern.or(wildcard)
+}
+
+/// Returns names which between them resolve to every combination of `entries` granting a name,
+/// given `entries` of key and whether the key is a pattern.
+/// Without patterns these are the keys followed by the wildcard.
+pub(crate) fn representative_names<'a>(
+    entries: impl Iterator<Item = (&'a str, bool)> + Clone,
+) -> Vec<String> {
+    let mut names = Vec::<String>::default();
+    let mut push = |name: String| {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    };
+    for (key, _) in entries.clone().filter(|(_, is_pattern)| !is_pattern) {
+        push(key.into());
+    }
+    push(WILDCARD.into());
+
+    let patterns: Vec<(&str, &str)> = entries
+        .clone()
+        .filter(|(_, is_pattern)| *is_pattern)
+        .filter_map(|(key, _)| split(key))
+        .collect();
+    if patterns.is_empty() {
+        return names;
+    }
+
+    let prefixes: Vec<&str> = once("").chain(patterns.iter().map(|(p, _)| *p)).collect();
+    let suffixes: Vec<&str> = once("").chain(patterns.iter().map(|(_, s)| *s)).collect();
+    // A character in no name or pattern stands for the characters matched by a `*`
+    let literals: Vec<&str> = entries
+        .filter(|(key, is_pattern)| !is_pattern && *key != WILDCARD)
+        .map(|(key, _)| key)
+        .collect();
+    let unused = |c: &char| {
+        !literals
+            .iter()
+            .chain(&prefixes)
+            .chain(&suffixes)
+            .any(|part| part.contains(*c))
+    };
+    let filler = once(PATTERN_WILDCARD)
+        .chain((1..=0x10_ffff).filter_map(core::char::from_u32))
+        .find(unused)
+        .unwrap_or(PATTERN_WILDCARD);
+
+    for prefix in &prefixes {
+        for suffix in &suffixes {
+            let mut name = String::from(*prefix);
+            name.push(filler);
+            name.push_str(suffix);
+            push(name);
+            // Shorter names where the prefix and suffix overlap
+            for overlap in 1..=prefix.len().min(suffix.len()) {
+                if suffix.is_char_boundary(overlap) && prefix.ends_with(&suffix[..overlap]) {
+                    push([*prefix, &suffix[overlap..]].concat());
+                }
+            }
+        }
+    }
+    names
+}
@@ -0,0 +1,75 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Policy
+//!
+//! Errors which may occur while parsing a CENNZnut policy
+//!
+
+use alloc::fmt::{self, Display, Formatter};
+
+/// The cause of a policy parsing error
+#[derive(Debug, Eq, PartialEq)]
+pub enum PolicyErrorKind {
+    UnexpectedEnd { expected: &'static str },
+    UnexpectedToken { expected: &'static str },
+    UnterminatedString,
+    InvalidCooldown,
+    InvalidAddress,
+    InvalidConstraints,
+    NameTooLong,
+    ConstraintsTooLarge,
+    NoMethods,
+    TooManyModules,
+    TooManyMethods,
+    TooManyContracts,
+}
+
+/// Error which may occur while parsing a CENNZnut policy.
+/// `line` and `column` are 1-based and count characters.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PolicyError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PolicyErrorKind,
+}
+
+impl Display for PolicyErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd { expected } => {
+                write!(f, "expected {}, found end of policy", expected)
+            }
+            Self::UnexpectedToken { expected } => write!(f, "expected {}", expected),
+            Self::UnterminatedString => write!(f, "unterminated string"),
+            Self::InvalidCooldown => write!(f, "block cooldown should be a u32"),
+            Self::InvalidAddress => {
+                write!(
+                    f,
+                    "contract address should be `*` or 0x prefixed 32 byte hex"
+                )
+            }
+            Self::InvalidConstraints => {
+                write!(
+                    f,
+                    "constraints should be 0x prefixed hex of a pact contract"
+                )
+            }
+            Self::NameTooLong => write!(f, "name is longer than 32 bytes"),
+            Self::ConstraintsTooLarge => write!(f, "constraints are too large"),
+            Self::NoMethods => write!(f, "module must contain at least 1 method"),
+            Self::TooManyModules => write!(f, "too many modules"),
+            Self::TooManyMethods => write!(f, "too many methods"),
+            Self::TooManyContracts => write!(f, "too many contracts"),
+        }
+    }
+}
+
+impl Display for PolicyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Rate Limit
+//!
+//! Call history used to enforce CENNZnut sliding window rate limits
+//!
+
+use crate::cennznut::{ContractAddress, MethodName, ModuleName};
+use crate::{CooldownTracker, GrantPath};
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// Permits at most `calls` calls within any `blocks` consecutive blocks.
+/// A limit of 0 `calls` denies every call, while a window of 0 `blocks` would never count
+/// a call and cannot be encoded.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    pub calls: u32,
+    pub blocks: u32,
+}
+
+impl RateLimit {
+    pub fn new(calls: u32, blocks: u32) -> Self {
+        Self { calls, blocks }
+    }
+
+    /// Returns the first block of the window ending at `block_number`
+    fn window_start(self, block_number: u32) -> u32 {
+        block_number.saturating_add(1).saturating_sub(self.blocks)
+    }
+}
+
+/// Records the block numbers at which CENNZnut permissions were used, extending the
+/// last call recorded by a `CooldownTracker` to every call within a rate limit window.
+/// Calls are tracked per grant which permitted them, so that every call permitted by a
+/// pattern or wildcard entry counts towards its rate limit.
+pub trait CallHistory: CooldownTracker {
+    /// Returns the number of calls permitted by the module entry `module` at or after
+    /// `since_block`, through any of its methods
+    fn module_grant_calls_since(&self, module: &str, since_block: u32) -> u32;
+    /// Returns the number of calls permitted by `grant` at or after `since_block`
+    fn grant_calls_since(&self, grant: &GrantPath, since_block: u32) -> u32;
+    /// Records a call permitted by `grant` at `block_number`
+    fn record_grant_call(&mut self, grant: &GrantPath, block_number: u32);
+}
+
+/// An in-memory `CallHistory` for tests and off-chain simulation
+#[derive(Default)]
+pub struct InMemoryCallHistory {
+    modules: BTreeMap<ModuleName, Vec<u32>>,
+    methods: BTreeMap<(ModuleName, MethodName), Vec<u32>>,
+    contracts: BTreeMap<ContractAddress, Vec<u32>>,
+    module_grants: BTreeMap<ModuleName, Vec<u32>>,
+    grants: BTreeMap<GrantPath, Vec<u32>>,
+}
+
+impl InMemoryCallHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Returns the latest of the `calls` blocks, if any
+fn last_call(calls: Option<&Vec<u32>>) -> Option<u32> {
+    calls.and_then(|calls| calls.iter().max().copied())
+}
+
+/// Returns the number of the `calls` blocks at or after `since_block`
+fn calls_since(calls: Option<&Vec<u32>>, since_block: u32) -> u32 {
+    let count = calls.map_or(0, |calls| {
+        calls.iter().filter(|block| **block >= since_block).count()
+    });
+    u32::try_from(count).unwrap_or(u32::max_value())
+}
+
+impl CooldownTracker for InMemoryCallHistory {
+    fn last_module_call(&self, module_name: &str) -> Option<u32> {
+        last_call(self.modules.get(module_name))
+    }
+
+    fn last_method_call(&self, module_name: &str, method_name: &str) -> Option<u32> {
+        last_call(
+            self.methods
+                .get(&(module_name.to_string(), method_name.to_string())),
+        )
+    }
+
+    fn last_contract_call(&self, contract_address: &ContractAddress) -> Option<u32> {
+        last_call(self.contracts.get(contract_address))
+    }
+
+    fn record_module_call(&mut self, module_name: &str, block_number: u32) {
+        self.modules
+            .entry(module_name.to_string())
+            .or_default()
+            .push(block_number);
+    }
+
+    fn record_method_call(&mut self, module_name: &str, method_name: &str, block_number: u32) {
+        self.methods
+            .entry((module_name.to_string(), method_name.to_string()))
+            .or_default()
+            .push(block_number);
+    }
+
+    fn record_contract_call(&mut self, contract_address: &ContractAddress, block_number: u32) {
+        self.contracts
+            .entry(*contract_address)
+            .or_default()
+            .push(block_number);
+    }
+}
+
+impl CallHistory for InMemoryCallHistory {
+    fn module_grant_calls_since(&self, module: &str, since_block: u32) -> u32 {
+        calls_since(self.module_grants.get(module), since_block)
+    }
+
+    fn grant_calls_since(&self, grant: &GrantPath, since_block: u32) -> u32 {
+        calls_since(self.grants.get(grant), since_block)
+    }
+
+    fn record_grant_call(&mut self, grant: &GrantPath, block_number: u32) {
+        if let GrantPath::Method { module, .. } = grant {
+            self.module_grants
+                .entry(module.clone())
+                .or_default()
+                .push(block_number);
+        }
+        self.grants
+            .entry(grant.clone())
+            .or_default()
+            .push(block_number);
+    }
+}
+
+/// Returns whether a call at `block_number` is within `rate_limit`, given the number of
+/// earlier `calls_since` the first block of the window ending at `block_number`
+pub(crate) fn within_rate_limit(
+    rate_limit: Option<RateLimit>,
+    calls_since: impl FnOnce(u32) -> u32,
+    block_number: u32,
+) -> bool {
+    match rate_limit {
+        Some(rate_limit) => calls_since(rate_limit.window_start(block_number)) < rate_limit.calls,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{within_rate_limit, CallHistory, InMemoryCallHistory, RateLimit};
+    use crate::{CooldownTracker, GrantPath};
+
+    #[test]
+    fn it_is_within_no_rate_limit() {
+        assert!(within_rate_limit(None, |_| u32::max_value(), 10));
+    }
+
+    #[test]
+    fn it_counts_calls_within_the_window() {
+        let rate_limit = Some(RateLimit::new(2, 10));
+
+        assert!(within_rate_limit(
+            rate_limit,
+            |since| if since == 11 { 1 } else { 2 },
+            20
+        ));
+        assert!(!within_rate_limit(rate_limit, |_| 2, 20));
+        // The window is clamped at the first block
+        assert!(within_rate_limit(
+            rate_limit,
+            |since| if since == 0 { 1 } else { 2 },
+            5
+        ));
+        assert!(!within_rate_limit(Some(RateLimit::new(0, 10)), |_| 0, 5));
+    }
+
+    #[test]
+    fn it_records_calls() {
+        let mut history = InMemoryCallHistory::new();
+        history.record_module_call("module_test", 3);
+        history.record_module_call("module_test", 7);
+        history.record_method_call("module_test", "method_test", 4);
+        history.record_contract_call(&[0x12_u8; 32], 5);
+
+        assert_eq!(history.last_module_call("module_test"), Some(7));
+        assert_eq!(
+            history.last_method_call("module_test", "method_test"),
+            Some(4)
+        );
+        assert_eq!(history.last_contract_call(&[0x12_u8; 32]), Some(5));
+        assert_eq!(history.last_contract_call(&[0x34_u8; 32]), None);
+    }
+
+    #[test]
+    fn it_counts_calls_per_grant() {
+        let mut history = InMemoryCallHistory::new();
+        let method_a = GrantPath::Method {
+            module: "module_*".into(),
+            method: "*".into(),
+        };
+        let method_b = GrantPath::Method {
+            module: "module_*".into(),
+            method: "method_b".into(),
+        };
+        let contract = GrantPath::Contract {
+            address: [0x12_u8; 32],
+        };
+        history.record_grant_call(&method_a, 3);
+        history.record_grant_call(&method_a, 7);
+        history.record_grant_call(&method_b, 4);
+        history.record_grant_call(&contract, 5);
+
+        assert_eq!(history.grant_calls_since(&method_a, 3), 2);
+        assert_eq!(history.grant_calls_since(&method_a, 4), 1);
+        assert_eq!(history.grant_calls_since(&method_b, 5), 0);
+        // Calls through every method of the module entry count towards it
+        assert_eq!(history.module_grant_calls_since("module_*", 4), 2);
+        assert_eq!(history.module_grant_calls_since("module_test", 0), 0);
+        assert_eq!(history.grant_calls_since(&contract, 5), 1);
+        assert_eq!(
+            history.grant_calls_since(
+                &GrantPath::Contract {
+                    address: [0x34_u8; 32]
+                },
+                0
+            ),
+            0
+        );
+        // Grant calls are not calls for cooldowns
+        assert_eq!(history.last_contract_call(&[0x12_u8; 32]), None);
+    }
+}
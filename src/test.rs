@@ -6,9 +6,12 @@
 #![warn(clippy::pedantic)]
 #![cfg(test)]
 
-use crate::CENNZnut;
+use crate::cennznut::v0::{
+    contract::Contract as ContractV0, method::Method as MethodV0, module::Module as ModuleV0,
+};
+use crate::{CENNZnut, CENNZnutV0, DecodeError, DecodeField, DecodePath};
 
-use codec::Decode;
+use codec::{Decode, Encode};
 use std::vec::Vec;
 
 #[test]
@@ -40,3 +43,529 @@ fn it_fails_decode_with_invalid_constraints() {
         Err(codec::Error::from("invalid constraints codec")),
     );
 }
+
+#[test]
+fn it_reports_the_offset_of_invalid_constraints() {
+    let encoded_cennznut: Vec<u8> = vec![
+        0, 0, 1, 64, 109, 111, 100, 117, 108, 101, 95, 116, 101, 115, 116, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 109, 101, 116, 104, 111, 100, 95, 116, 101, 115,
+        116, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let bad_type_id: Vec<u8> = vec![3, 0, 0b1000_0000, 0b0000_0001, 0b0000_0001];
+    let encoded_with_bad_type_id: Vec<u8> = [encoded_cennznut, bad_type_id].concat();
+
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded_with_bad_type_id),
+        Err(DecodeError::InvalidConstraints {
+            offset: 2 + 1 + 33 + 33 + 1,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(0),
+                contract: None,
+            },
+        }),
+    );
+}
+
+#[test]
+fn it_reports_bad_versions() {
+    let encoded: Vec<u8> = vec![2, 0, 0, 0];
+
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded),
+        Err(DecodeError::BadVersion {
+            offset: 0,
+            version: 2
+        }),
+    );
+    assert_eq!(
+        CENNZnut::decode(&mut &encoded[..]),
+        Err(codec::Error::from("unexpected version")),
+    );
+}
+
+#[test]
+fn it_reports_the_path_of_truncated_fields() {
+    // The module announces a block cooldown but only carries 2 of its 4 bytes,
+    // so the method name runs out of input.
+    let encoded: Vec<u8> = [
+        vec![0, 0, 0, 0b0000_0001],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0x01, 0x02],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+    ]
+    .concat();
+
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded),
+        Err(DecodeError::Truncated {
+            offset: 2 + 1 + 33 + 4 + 1,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(0),
+                contract: None,
+            },
+            field: DecodeField::MethodName,
+        }),
+    );
+}
+
+#[test]
+fn it_reports_invalid_utf8_names() {
+    let encoded: Vec<u8> = [
+        vec![0, 0, 0, 0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0xf0; 21],
+        vec![0],
+    ]
+    .concat();
+
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded),
+        Err(DecodeError::InvalidUtf8Name {
+            offset: 2 + 1 + 33 + 1,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(0),
+                contract: None,
+            },
+            field: DecodeField::MethodName,
+        }),
+    );
+}
+
+#[test]
+fn it_reports_truncated_contracts() {
+    let encoded: Vec<u8> = [
+        vec![0, 0, 0, 0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![2, 0],
+        vec![0x5a; 32],
+        vec![1],
+        vec![0x5a; 32],
+        vec![0x01, 0x02],
+    ]
+    .concat();
+
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded),
+        Err(DecodeError::Truncated {
+            offset: 2 + 1 + 33 + 33 + 1 + 33 + 33,
+            path: DecodePath {
+                module: None,
+                method: None,
+                contract: Some(1),
+            },
+            field: DecodeField::ContractCooldown,
+        }),
+    );
+}
+
+#[test]
+fn it_reports_trailing_bytes() {
+    let encoded: Vec<u8> = [
+        vec![0, 0, 0, 0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+    ]
+    .concat();
+    let encoded_with_trailing_bytes: Vec<u8> = [encoded.clone(), vec![0x55]].concat();
+
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded),
+        Ok(CENNZnut::decode(&mut &encoded[..]).unwrap()),
+    );
+    assert_eq!(
+        CENNZnut::decode_detailed(&encoded_with_trailing_bytes),
+        Err(DecodeError::TrailingBytes {
+            offset: encoded.len()
+        }),
+    );
+}
+
+fn canonical_v0() -> Vec<u8> {
+    [
+        vec![0, 0, 0, 0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+    ]
+    .concat()
+}
+
+#[test]
+fn it_strictly_decodes_canonical_encodings() {
+    let encoded = canonical_v0();
+
+    assert_eq!(
+        CENNZnut::decode_strict(&encoded),
+        CENNZnut::decode_detailed(&encoded),
+    );
+    assert!(CENNZnut::decode_strict(&encoded).is_ok());
+}
+
+#[test]
+fn it_strictly_rejects_trailing_bytes() {
+    let encoded = canonical_v0();
+    let encoded_with_trailing_bytes: Vec<u8> = [encoded.clone(), vec![0x55]].concat();
+
+    assert!(CENNZnut::decode(&mut &encoded_with_trailing_bytes[..]).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&encoded_with_trailing_bytes),
+        Err(DecodeError::TrailingBytes {
+            offset: encoded.len()
+        }),
+    );
+}
+
+#[test]
+fn it_strictly_rejects_duplicate_module_names() {
+    let module: Vec<u8> = [
+        vec![0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+    ]
+    .concat();
+    let encoded: Vec<u8> = [vec![0, 0, 1], module.clone(), module, vec![0]].concat();
+
+    assert!(CENNZnut::decode_detailed(&encoded).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&encoded),
+        Err(DecodeError::Duplicate {
+            offset: 2 + 1 + 33 + 33,
+            path: DecodePath {
+                module: Some(1),
+                method: None,
+                contract: None,
+            },
+            field: DecodeField::ModuleName,
+        }),
+    );
+}
+
+#[test]
+fn it_strictly_rejects_duplicate_method_names() {
+    let encoded: Vec<u8> = [
+        vec![0, 0, 0, 0b0000_0010],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+    ]
+    .concat();
+
+    assert!(CENNZnut::decode_detailed(&encoded).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&encoded),
+        Err(DecodeError::Duplicate {
+            offset: 2 + 1 + 33 + 33,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(1),
+                contract: None,
+            },
+            field: DecodeField::MethodName,
+        }),
+    );
+}
+
+#[test]
+fn it_strictly_rejects_duplicate_contract_addresses() {
+    let encoded: Vec<u8> = [
+        vec![0, 0, 0, 0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![2, 0],
+        vec![0x5a; 32],
+        vec![0],
+        vec![0x5a; 32],
+    ]
+    .concat();
+
+    assert!(CENNZnut::decode_detailed(&encoded).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&encoded),
+        Err(DecodeError::Duplicate {
+            offset: 2 + 1 + 33 + 33 + 1 + 33,
+            path: DecodePath {
+                module: None,
+                method: None,
+                contract: Some(1),
+            },
+            field: DecodeField::ContractAddress,
+        }),
+    );
+}
+
+#[test]
+fn it_strictly_rejects_out_of_order_module_names() {
+    let module = |name: &[u8]| -> Vec<u8> {
+        [
+            vec![0],
+            name.to_vec(),
+            vec![0; 24],
+            vec![0],
+            b"method_test".to_vec(),
+            vec![0; 21],
+        ]
+        .concat()
+    };
+    let encoded: Vec<u8> = [
+        vec![0, 0, 1],
+        module(b"module_b"),
+        module(b"module_a"),
+        vec![0],
+    ]
+    .concat();
+
+    assert!(CENNZnut::decode_detailed(&encoded).is_ok());
+    let out_of_order = CENNZnut::decode_strict(&encoded).unwrap_err();
+    assert_eq!(
+        out_of_order,
+        DecodeError::OutOfOrder {
+            offset: 2 + 1 + 33 + 33,
+            path: DecodePath {
+                module: Some(1),
+                method: None,
+                contract: None,
+            },
+            field: DecodeField::ModuleName,
+        },
+    );
+    assert_eq!(
+        out_of_order.to_string(),
+        "module name out of order at byte 69 in module 1"
+    );
+}
+
+#[test]
+fn it_strictly_rejects_out_of_order_method_names() {
+    let encoded: Vec<u8> = [
+        vec![0, 0, 0, 0b0000_0010],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_b".to_vec(),
+        vec![0; 24],
+        vec![0],
+        b"method_a".to_vec(),
+        vec![0; 24],
+        vec![0],
+    ]
+    .concat();
+
+    assert!(CENNZnut::decode_detailed(&encoded).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&encoded),
+        Err(DecodeError::OutOfOrder {
+            offset: 2 + 1 + 33 + 33,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(1),
+                contract: None,
+            },
+            field: DecodeField::MethodName,
+        }),
+    );
+}
+
+#[test]
+fn it_strictly_rejects_out_of_order_contract_addresses() {
+    let encoded: Vec<u8> = [
+        vec![0, 0, 0, 0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![2, 0],
+        vec![0x5a; 32],
+        vec![0],
+        vec![0x12; 32],
+    ]
+    .concat();
+
+    assert!(CENNZnut::decode_detailed(&encoded).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&encoded),
+        Err(DecodeError::OutOfOrder {
+            offset: 2 + 1 + 33 + 33 + 1 + 33,
+            path: DecodePath {
+                module: None,
+                method: None,
+                contract: Some(1),
+            },
+            field: DecodeField::ContractAddress,
+        }),
+    );
+}
+
+#[test]
+fn it_strictly_decodes_canonicalized_cennznuts() {
+    let module = |name: &str| {
+        ModuleV0::new(name)
+            .methods(vec![MethodV0::new("method_b"), MethodV0::new("method_a")].into())
+    };
+    let cennznut = CENNZnutV0 {
+        modules: vec![module("module_b"), module("module_a")].into(),
+        contracts: vec![ContractV0::new(&[0x5a; 32]), ContractV0::new(&[0x12; 32])].into(),
+    };
+    let encoded = CENNZnut::V0(cennznut.clone()).encode();
+    let canonical = CENNZnut::V0(cennznut.canonicalize().expect("it canonicalizes")).encode();
+
+    assert!(CENNZnut::decode_strict(&encoded).is_err());
+    assert!(CENNZnut::decode_strict(&canonical).is_ok());
+}
+
+#[test]
+fn it_strictly_rejects_nul_bytes_within_names() {
+    let interior_nul: Vec<u8> = [
+        vec![0, 0, 0, 0],
+        b"module\0test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+    ]
+    .concat();
+    let leading_nul: Vec<u8> = [
+        vec![0, 0, 0, 0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0, 0],
+        b"method_test".to_vec(),
+        vec![0; 20],
+        vec![0],
+    ]
+    .concat();
+
+    assert!(CENNZnut::decode_detailed(&interior_nul).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&interior_nul),
+        Err(DecodeError::NulInName {
+            offset: 2 + 1 + 1,
+            path: DecodePath {
+                module: Some(0),
+                method: None,
+                contract: None,
+            },
+            field: DecodeField::ModuleName,
+        }),
+    );
+    // Leading padding is trimmed when decoding, so re-encoding would move the name
+    assert!(CENNZnut::decode_detailed(&leading_nul).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&leading_nul),
+        Err(DecodeError::NulInName {
+            offset: 2 + 1 + 33 + 1,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(0),
+                contract: None,
+            },
+            field: DecodeField::MethodName,
+        }),
+    );
+}
+
+#[test]
+fn it_strictly_rejects_reserved_header_bits() {
+    let mut reserved_method_bits = canonical_v0();
+    reserved_method_bits[2 + 1 + 33] = 0b0000_0100;
+    let reserved_contract_bits: Vec<u8> = [
+        vec![0, 0, 0, 0],
+        b"module_test".to_vec(),
+        vec![0; 21],
+        vec![0],
+        b"method_test".to_vec(),
+        vec![0; 21],
+        vec![1, 0b1000_0000],
+        vec![0x5a; 32],
+    ]
+    .concat();
+
+    assert!(CENNZnut::decode_detailed(&reserved_method_bits).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&reserved_method_bits),
+        Err(DecodeError::ReservedHeaderBits {
+            offset: 2 + 1 + 33,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(0),
+                contract: None,
+            },
+            field: DecodeField::MethodHeader,
+        }),
+    );
+    assert!(CENNZnut::decode_detailed(&reserved_contract_bits).is_ok());
+    assert_eq!(
+        CENNZnut::decode_strict(&reserved_contract_bits),
+        Err(DecodeError::ReservedHeaderBits {
+            offset: 2 + 1 + 33 + 33 + 1,
+            path: DecodePath {
+                module: None,
+                method: None,
+                contract: Some(0),
+            },
+            field: DecodeField::ContractHeader,
+        }),
+    );
+}
+
+#[test]
+fn it_strictly_rejects_empty_constraints() {
+    // A V1 method sets the constraints flag with a zero length payload
+    let encoded: Vec<u8> = [
+        vec![1, 0],
+        vec![1 << 2],
+        vec![0, 11 << 2],
+        b"module_test".to_vec(),
+        vec![1 << 2],
+        vec![0b0000_0010, 11 << 2],
+        b"method_test".to_vec(),
+        vec![0],
+        vec![0],
+    ]
+    .concat();
+
+    assert_eq!(
+        CENNZnut::decode_strict(&encoded),
+        Err(DecodeError::EmptyConstraints {
+            offset: 2 + 1 + 2 + 11 + 1 + 2 + 11,
+            path: DecodePath {
+                module: Some(0),
+                method: Some(0),
+                contract: None,
+            },
+        }),
+    );
+}
@@ -0,0 +1,112 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Trace
+//!
+//! Explanation of a CENNZnut runtime call validation
+//!
+
+use crate::{RuntimeDomain, ValidationErr};
+use alloc::fmt::{self, Display, Formatter};
+use alloc::string::String;
+
+/// How a requested name matched a CENNZnut grant
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GrantMatch {
+    Exact,
+    /// A name pattern, preferred to the wildcard
+    Pattern,
+    Wildcard,
+}
+
+/// The module or method grant matched by a call
+#[derive(Debug, Eq, PartialEq)]
+pub struct MatchedGrant {
+    /// The key of the matched entry
+    pub key: String,
+    pub matched: GrantMatch,
+    /// The block cooldown in effect for the call
+    pub block_cooldown: Option<u32>,
+}
+
+/// The outcome of interpreting the matched method constraints
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConstraintsOutcome {
+    Unconstrained,
+    Satisfied,
+    /// `clause` is the index of the first unsatisfied clause, if it can be identified
+    Unsatisfied {
+        clause: Option<usize>,
+    },
+    InterpretationFailed,
+}
+
+/// An explanation of why a runtime call was permitted or denied.
+/// Steps which were not reached are `None`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ValidationTrace {
+    pub module: Option<MatchedGrant>,
+    pub method: Option<MatchedGrant>,
+    /// The outcome of interpreting the matched module constraints, `None` if unconstrained
+    pub module_constraints: Option<ConstraintsOutcome>,
+    pub constraints: Option<ConstraintsOutcome>,
+    pub result: Result<(), ValidationErr<RuntimeDomain>>,
+}
+
+impl Display for GrantMatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exact => write!(f, "exact"),
+            Self::Pattern => write!(f, "pattern"),
+            Self::Wildcard => write!(f, "wildcard"),
+        }
+    }
+}
+
+impl Display for MatchedGrant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}), block cooldown: ", self.key, self.matched)?;
+        match self.block_cooldown {
+            Some(cooldown) => write!(f, "{}", cooldown),
+            None => write!(f, "none"),
+        }
+    }
+}
+
+impl Display for ConstraintsOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unconstrained => write!(f, "unconstrained"),
+            Self::Satisfied => write!(f, "satisfied"),
+            Self::Unsatisfied {
+                clause: Some(clause),
+            } => write!(f, "unsatisfied at clause {}", clause),
+            Self::Unsatisfied { clause: None } => write!(f, "unsatisfied"),
+            Self::InterpretationFailed => write!(f, "interpretation failed"),
+        }
+    }
+}
+
+impl Display for ValidationTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.module {
+            Some(module) => writeln!(f, "module: {}", module)?,
+            None => writeln!(f, "module: not granted")?,
+        }
+        if self.module.is_some() {
+            match &self.method {
+                Some(method) => writeln!(f, "method: {}", method)?,
+                None => writeln!(f, "method: not granted")?,
+            }
+        }
+        if let Some(constraints) = &self.module_constraints {
+            writeln!(f, "module constraints: {}", constraints)?;
+        }
+        if let Some(constraints) = &self.constraints {
+            writeln!(f, "constraints: {}", constraints)?;
+        }
+        match &self.result {
+            Ok(()) => write!(f, "result: permitted"),
+            Err(err) => write!(f, "result: {}", err),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (C) 2019-2020 Centrality Investments Limited
+//!
+//! # CENNZnut - Usage
+//!
+//! Use counts used to enforce CENNZnut usage quotas
+//!
+
+use crate::cennznut::{ContractAddress, MethodName, ModuleName};
+use alloc::collections::BTreeMap;
+
+/// A hash identifying the CENNZnut whose grants are used, computed by the host
+pub type NutHash = [u8; 32];
+
+/// The path of a grant within a CENNZnut, naming the module and method entries or the
+/// contract entry which permit a call. Entry names may be patterns or wildcards, so that
+/// every call permitted by a grant counts towards its quota.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum GrantPath {
+    Method {
+        module: ModuleName,
+        method: MethodName,
+    },
+    Contract {
+        address: ContractAddress,
+    },
+}
+
+/// Records the number of times each grant of a CENNZnut has been used.
+/// Uses are counted per CENNZnut hash and grant path.
+pub trait UsageCounter {
+    /// Returns the number of times `grant` of the CENNZnut `nut_hash` has been used
+    fn uses(&self, nut_hash: &NutHash, grant: &GrantPath) -> u32;
+    /// Records a use of `grant` of the CENNZnut `nut_hash`
+    fn record_use(&mut self, nut_hash: &NutHash, grant: &GrantPath);
+}
+
+/// An in-memory `UsageCounter` for tests and off-chain simulation
+#[derive(Default)]
+pub struct InMemoryUsageCounter {
+    uses: BTreeMap<(NutHash, GrantPath), u32>,
+}
+
+impl InMemoryUsageCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl UsageCounter for InMemoryUsageCounter {
+    fn uses(&self, nut_hash: &NutHash, grant: &GrantPath) -> u32 {
+        self.uses
+            .get(&(*nut_hash, grant.clone()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn record_use(&mut self, nut_hash: &NutHash, grant: &GrantPath) {
+        let uses = self.uses.entry((*nut_hash, grant.clone())).or_default();
+        *uses = uses.saturating_add(1);
+    }
+}
+
+/// Returns the uses remaining of a `max_uses` quota after `uses`, or `None` if unlimited
+pub(crate) fn remaining_uses(max_uses: Option<u32>, uses: u32) -> Option<u32> {
+    max_uses.map(|max_uses| max_uses.saturating_sub(uses))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{remaining_uses, GrantPath, InMemoryUsageCounter, UsageCounter};
+
+    #[test]
+    fn it_counts_remaining_uses() {
+        assert_eq!(remaining_uses(None, 10), None);
+        assert_eq!(remaining_uses(Some(3), 1), Some(2));
+        assert_eq!(remaining_uses(Some(3), 5), Some(0));
+    }
+
+    #[test]
+    fn it_records_uses_per_nut_and_grant() {
+        let mut counter = InMemoryUsageCounter::new();
+        let method = GrantPath::Method {
+            module: "module_test".into(),
+            method: "method_test".into(),
+        };
+        let contract = GrantPath::Contract {
+            address: [0x12_u8; 32],
+        };
+        counter.record_use(&[0x01_u8; 32], &method);
+        counter.record_use(&[0x01_u8; 32], &method);
+        counter.record_use(&[0x02_u8; 32], &contract);
+
+        assert_eq!(counter.uses(&[0x01_u8; 32], &method), 2);
+        assert_eq!(counter.uses(&[0x02_u8; 32], &method), 0);
+        assert_eq!(counter.uses(&[0x02_u8; 32], &contract), 1);
+        assert_eq!(counter.uses(&[0x01_u8; 32], &contract), 0);
+    }
+}
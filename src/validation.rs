@@ -5,15 +5,36 @@
 //! Validation trait of CENNZnut for use in CENNZnet
 //!
 
+use crate::cennznut::{ContractAddress, ContractSelector};
+use crate::{CallDecodeError, ContractDomain, RateLimit, RuntimeDomain};
 use alloc::fmt::{self, Display, Formatter};
+use pact::contract::Contract as PactContract;
+use pact::interpreter::{interpret, types::PactType, InterpErr};
 
-/// Error which may occur while validating the permission domain
-#[derive(Debug, Eq, PartialEq)]
+/// Error which may occur while validating the permission domain.
+/// The `Domain` names the requested module and method, or contract address.
+#[derive(Debug, PartialEq)]
 pub enum ValidationErr<Domain: Display> {
     NoPermission(Domain),
-    ConstraintsInterpretation,
+    ConstraintsInterpretation(InterpErr),
+    CooldownNotElapsed(Domain),
+    /// A deny entry applies to the domain, regardless of any grant
+    Denied(Domain),
+    /// The block is before the first block the domain may be used at
+    NotYetValid(Domain),
+    /// The block is after the last block the domain may be used at
+    Expired(Domain),
+    /// The grant permitting the domain has been used as many times as it may be
+    QuotaExhausted(Domain),
+    /// The domain has been called as many times as its rate limit permits within the window
+    RateLimitExceeded(Domain),
+    /// The encoded call arguments do not match the call schema
+    ArgumentsDecoding(CallDecodeError),
 }
 
+// `InterpErr` is a plain value enum, so its equality is reflexive
+impl<Domain: Display + Eq> Eq for ValidationErr<Domain> {}
+
 impl<Domain: Display> Display for ValidationErr<Domain> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -22,7 +43,135 @@ impl<Domain: Display> Display for ValidationErr<Domain> {
                 "CENNZnut does not grant permission for {}",
                 permission_domain
             ),
-            Self::ConstraintsInterpretation => write!(f, "error while interpreting constraints"),
+            Self::ConstraintsInterpretation(err) => {
+                write!(f, "error while interpreting constraints: {:?}", err)
+            }
+            Self::CooldownNotElapsed(permission_domain) => write!(
+                f,
+                "CENNZnut {} block cooldown has not elapsed",
+                permission_domain
+            ),
+            Self::Denied(permission_domain) => write!(
+                f,
+                "CENNZnut explicitly denies permission for {}",
+                permission_domain
+            ),
+            Self::NotYetValid(permission_domain) => {
+                write!(f, "CENNZnut {} is not yet valid", permission_domain)
+            }
+            Self::Expired(permission_domain) => {
+                write!(f, "CENNZnut {} has expired", permission_domain)
+            }
+            Self::QuotaExhausted(permission_domain) => {
+                write!(f, "CENNZnut {} usage quota is exhausted", permission_domain)
+            }
+            Self::RateLimitExceeded(permission_domain) => {
+                write!(f, "CENNZnut {} rate limit is exceeded", permission_domain)
+            }
+            Self::ArgumentsDecoding(err) => {
+                write!(f, "error while decoding call arguments: {}", err)
+            }
         }
     }
 }
+
+/// Checks `block_number` is within the `valid_from` to `valid_until` blocks, inclusive.
+/// A bounded grant is not permitted without a `block_number` to check.
+pub(crate) fn check_validity<Domain: Display>(
+    valid_from: Option<u32>,
+    valid_until: Option<u32>,
+    block_number: Option<u32>,
+    domain: impl FnOnce() -> Domain,
+) -> Result<(), ValidationErr<Domain>> {
+    match block_number {
+        Some(block_number) if valid_from.map_or(false, |from| block_number < from) => {
+            Err(ValidationErr::NotYetValid(domain()))
+        }
+        Some(block_number) if valid_until.map_or(false, |until| block_number > until) => {
+            Err(ValidationErr::Expired(domain()))
+        }
+        None if valid_from.is_some() || valid_until.is_some() => {
+            Err(ValidationErr::NoPermission(domain()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks a grant is not bounded by a `max_uses` usage quota.
+/// A bounded grant is not permitted where its uses are not counted.
+pub(crate) fn check_unmetered<Domain: Display>(
+    max_uses: Option<u32>,
+    domain: impl FnOnce() -> Domain,
+) -> Result<(), ValidationErr<Domain>> {
+    match max_uses {
+        Some(_) => Err(ValidationErr::NoPermission(domain())),
+        None => Ok(()),
+    }
+}
+
+/// Checks a grant is not bounded by a `rate_limit`.
+/// A bounded grant is not permitted where its calls are not counted.
+pub(crate) fn check_unlimited<Domain: Display>(
+    rate_limit: Option<RateLimit>,
+    domain: impl FnOnce() -> Domain,
+) -> Result<(), ValidationErr<Domain>> {
+    match rate_limit {
+        Some(_) => Err(ValidationErr::NoPermission(domain())),
+        None => Ok(()),
+    }
+}
+
+/// Executes the Pact interpreter over `args` with the `pact` constraints
+/// of `method_name` in `module_name`
+pub(crate) fn interpret_constraints(
+    pact: &PactContract,
+    args: &[PactType],
+    module_name: &str,
+    method_name: &str,
+) -> Result<(), ValidationErr<RuntimeDomain>> {
+    interpret_with(pact, args, || RuntimeDomain::MethodArguments {
+        module: module_name.into(),
+        method: method_name.into(),
+    })
+}
+
+/// Executes the Pact interpreter over `args` with the `pact` constraints
+/// of `module_name`, for a call of `method_name`
+pub(crate) fn interpret_module_constraints(
+    pact: &PactContract,
+    args: &[PactType],
+    module_name: &str,
+    method_name: &str,
+) -> Result<(), ValidationErr<RuntimeDomain>> {
+    interpret_with(pact, args, || RuntimeDomain::ModuleArguments {
+        module: module_name.into(),
+        method: method_name.into(),
+    })
+}
+
+/// Executes the Pact interpreter over `args` with the `pact` constraints
+/// of the contract at `address` called with `selector`
+pub(crate) fn interpret_contract_constraints(
+    pact: &PactContract,
+    args: &[PactType],
+    address: ContractAddress,
+    selector: ContractSelector,
+) -> Result<(), ValidationErr<ContractDomain>> {
+    interpret_with(pact, args, || ContractDomain::SelectorArguments {
+        address,
+        selector,
+    })
+}
+
+/// Executes the Pact interpreter, naming the `arguments` domain when unsatisfied
+fn interpret_with<Domain: Display>(
+    pact: &PactContract,
+    args: &[PactType],
+    arguments: impl FnOnce() -> Domain,
+) -> Result<(), ValidationErr<Domain>> {
+    match interpret(args, pact.data_table.as_ref(), &pact.bytecode) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ValidationErr::NoPermission(arguments())),
+        Err(err) => Err(ValidationErr::ConstraintsInterpretation(err)),
+    }
+}